@@ -0,0 +1,122 @@
+use bevy_ecs::{component::ComponentId, query::Access};
+use criterion::{black_box, criterion_group, BenchmarkId, Criterion};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+criterion_group!(
+    benches,
+    access_extend,
+    access_is_compatible,
+    access_get_conflicts
+);
+
+/// Number of distinct component ids in the simulated world.
+const WORLD_COMPONENTS: usize = 256;
+/// Number of distinct resource ids in the simulated world.
+const WORLD_RESOURCES: usize = 64;
+/// Number of accesses generated per distribution, so that a single iteration covers many pairs.
+const ACCESS_COUNT: usize = 64;
+
+/// Shape of the accesses generated for a benchmark run.
+#[derive(Clone, Copy)]
+struct SystemSize {
+    name: &'static str,
+    components: usize,
+    resources: usize,
+}
+
+/// Small systems dominate real schedules, but a few wide ones exist.
+const SIZES: [SystemSize; 3] = [
+    SystemSize {
+        name: "small",
+        components: 3,
+        resources: 1,
+    },
+    SystemSize {
+        name: "medium",
+        components: 12,
+        resources: 3,
+    },
+    SystemSize {
+        name: "large",
+        components: 48,
+        resources: 8,
+    },
+];
+
+fn make_access(rng: &mut impl Rng, size: SystemSize) -> Access<ComponentId> {
+    let mut access = Access::new();
+    for _ in 0..size.components {
+        let id = ComponentId::new(rng.gen_range(0..WORLD_COMPONENTS));
+        // Roughly a quarter of component accesses are mutable.
+        if rng.gen_ratio(1, 4) {
+            access.add_component_write(id);
+        } else {
+            access.add_component_read(id);
+        }
+    }
+    for _ in 0..size.resources {
+        let id = ComponentId::new(rng.gen_range(0..WORLD_RESOURCES));
+        if rng.gen_ratio(1, 4) {
+            access.add_resource_write(id);
+        } else {
+            access.add_resource_read(id);
+        }
+    }
+    access
+}
+
+fn make_accesses(size: SystemSize) -> Vec<Access<ComponentId>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(size.components as u64);
+    core::iter::repeat_with(|| make_access(&mut rng, size))
+        .take(ACCESS_COUNT)
+        .collect()
+}
+
+fn access_extend(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("access_extend");
+    for size in SIZES {
+        let accesses = make_accesses(size);
+        group.bench_function(BenchmarkId::from_parameter(size.name), |bencher| {
+            bencher.iter(|| {
+                let mut combined = Access::new();
+                for access in &accesses {
+                    combined.extend(black_box(access));
+                }
+                combined
+            });
+        });
+    }
+    group.finish();
+}
+
+fn access_is_compatible(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("access_is_compatible");
+    for size in SIZES {
+        let accesses = make_accesses(size);
+        group.bench_function(BenchmarkId::from_parameter(size.name), |bencher| {
+            bencher.iter(|| {
+                accesses
+                    .windows(2)
+                    .filter(|pair| black_box(&pair[0]).is_compatible(&pair[1]))
+                    .count()
+            });
+        });
+    }
+    group.finish();
+}
+
+fn access_get_conflicts(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("access_get_conflicts");
+    for size in SIZES {
+        let accesses = make_accesses(size);
+        group.bench_function(BenchmarkId::from_parameter(size.name), |bencher| {
+            bencher.iter(|| {
+                for pair in accesses.windows(2) {
+                    black_box(black_box(&pair[0]).get_conflicts(&pair[1]));
+                }
+            });
+        });
+    }
+    group.finish();
+}
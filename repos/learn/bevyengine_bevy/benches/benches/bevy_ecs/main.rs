@@ -6,6 +6,7 @@
 
 use criterion::criterion_main;
 
+mod access;
 mod change_detection;
 mod components;
 mod empty_archetypes;
@@ -18,6 +19,7 @@ mod scheduling;
 mod world;
 
 criterion_main!(
+    access::benches,
     change_detection::benches,
     components::benches,
     empty_archetypes::benches,
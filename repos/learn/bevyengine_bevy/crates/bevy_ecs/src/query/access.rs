@@ -6,6 +6,7 @@ use core::{fmt, fmt::Debug, marker::PhantomData};
 use derive_more::derive::From;
 use disqualified::ShortName;
 use fixedbitset::FixedBitSet;
+use thiserror::Error;
 
 /// A wrapper struct to make Debug representations of [`FixedBitSet`] easier
 /// to read, when used to store [`SparseSetIndex`].
@@ -62,6 +63,9 @@ pub struct Access<T: SparseSetIndex> {
     resource_read_and_writes: FixedBitSet,
     /// The exclusively-accessed resources.
     resource_writes: FixedBitSet,
+    /// The accessed resources that are `!Send` and must be accessed from the main thread.
+    /// Always a subset of `Self::resource_read_and_writes`.
+    non_send_resources: FixedBitSet,
     /// Is `true` if this component can read all components *except* those
     /// present in `Self::component_read_and_writes`.
     component_read_and_writes_inverted: bool,
@@ -87,6 +91,7 @@ impl<T: SparseSetIndex> Clone for Access<T> {
             component_writes: self.component_writes.clone(),
             resource_read_and_writes: self.resource_read_and_writes.clone(),
             resource_writes: self.resource_writes.clone(),
+            non_send_resources: self.non_send_resources.clone(),
             component_read_and_writes_inverted: self.component_read_and_writes_inverted,
             component_writes_inverted: self.component_writes_inverted,
             reads_all_resources: self.reads_all_resources,
@@ -103,6 +108,8 @@ impl<T: SparseSetIndex> Clone for Access<T> {
         self.resource_read_and_writes
             .clone_from(&source.resource_read_and_writes);
         self.resource_writes.clone_from(&source.resource_writes);
+        self.non_send_resources
+            .clone_from(&source.non_send_resources);
         self.component_read_and_writes_inverted = source.component_read_and_writes_inverted;
         self.component_writes_inverted = source.component_writes_inverted;
         self.reads_all_resources = source.reads_all_resources;
@@ -130,6 +137,10 @@ impl<T: SparseSetIndex + Debug> Debug for Access<T> {
                 "resource_writes",
                 &FormattedBitSet::<T>::new(&self.resource_writes),
             )
+            .field(
+                "non_send_resources",
+                &FormattedBitSet::<T>::new(&self.non_send_resources),
+            )
             .field(
                 "component_read_and_writes_inverted",
                 &self.component_read_and_writes_inverted,
@@ -160,6 +171,7 @@ impl<T: SparseSetIndex> Access<T> {
             component_writes: FixedBitSet::new(),
             resource_read_and_writes: FixedBitSet::new(),
             resource_writes: FixedBitSet::new(),
+            non_send_resources: FixedBitSet::new(),
             archetypal: FixedBitSet::new(),
             marker: PhantomData,
         }
@@ -208,6 +220,26 @@ impl<T: SparseSetIndex> Access<T> {
             .grow_and_insert(index.sparse_set_index());
     }
 
+    /// Adds access to the `!Send` resource given by `index`.
+    ///
+    /// This is tracked like [`add_resource_read`](Self::add_resource_read), and additionally
+    /// marks the resource as requiring main thread access.
+    pub fn add_non_send_resource_read(&mut self, index: T) {
+        self.non_send_resources
+            .grow_and_insert(index.sparse_set_index());
+        self.add_resource_read(index);
+    }
+
+    /// Adds exclusive access to the `!Send` resource given by `index`.
+    ///
+    /// This is tracked like [`add_resource_write`](Self::add_resource_write), and additionally
+    /// marks the resource as requiring main thread access.
+    pub fn add_non_send_resource_write(&mut self, index: T) {
+        self.non_send_resources
+            .grow_and_insert(index.sparse_set_index());
+        self.add_resource_write(index);
+    }
+
     fn remove_component_sparse_set_index_read(&mut self, index: usize) {
         if self.component_read_and_writes_inverted {
             self.component_read_and_writes.grow_and_insert(index);
@@ -309,6 +341,20 @@ impl<T: SparseSetIndex> Access<T> {
         self.writes_all_resources || !self.resource_writes.is_clear()
     }
 
+    /// Returns `true` if this can access the `!Send` resource given by `index`.
+    pub fn has_non_send_resource(&self, index: T) -> bool {
+        self.non_send_resources.contains(index.sparse_set_index())
+    }
+
+    /// Returns `true` if this accesses any `!Send` resource, meaning it has to run on the main
+    /// thread.
+    ///
+    /// Note that access to all resources (e.g. through `&World`) does not count here, as it is
+    /// not known which `!Send` resources exist.
+    pub fn has_any_non_send_resource(&self) -> bool {
+        !self.non_send_resources.is_clear()
+    }
+
     /// Returns true if this has an archetypal (indirect) access to the component given by `index`.
     ///
     /// This is a component whose value is not accessed (and thus will never cause conflicts),
@@ -415,10 +461,38 @@ impl<T: SparseSetIndex> Access<T> {
         self.component_writes.clear();
         self.resource_read_and_writes.clear();
         self.resource_writes.clear();
+        self.non_send_resources.clear();
+    }
+
+    /// Returns `true` if neither the read nor the write component sets are inverted.
+    ///
+    /// This is the case for almost every access produced by ordinary systems and queries,
+    /// which only becomes inverted through `read_all_components`/`write_all_components`
+    /// (e.g. `&World`, `EntityRef` or `EntityMut`).
+    #[inline]
+    fn is_component_access_non_inverted(&self) -> bool {
+        !self.component_read_and_writes_inverted && !self.component_writes_inverted
     }
 
     /// Adds all access from `other`.
     pub fn extend(&mut self, other: &Access<T>) {
+        self.reads_all_resources = self.reads_all_resources || other.reads_all_resources;
+        self.writes_all_resources = self.writes_all_resources || other.writes_all_resources;
+        self.resource_read_and_writes
+            .union_with(&other.resource_read_and_writes);
+        self.resource_writes.union_with(&other.resource_writes);
+        self.non_send_resources
+            .union_with(&other.non_send_resources);
+
+        // Fast path for the common case: with no inverted sets on either side, extending is
+        // a plain union and we can skip the inversion handling below.
+        if self.is_component_access_non_inverted() && other.is_component_access_non_inverted() {
+            self.component_read_and_writes
+                .union_with(&other.component_read_and_writes);
+            self.component_writes.union_with(&other.component_writes);
+            return;
+        }
+
         let component_read_and_writes_inverted =
             self.component_read_and_writes_inverted || other.component_read_and_writes_inverted;
         let component_writes_inverted =
@@ -481,13 +555,8 @@ impl<T: SparseSetIndex> Access<T> {
             }
         }
 
-        self.reads_all_resources = self.reads_all_resources || other.reads_all_resources;
-        self.writes_all_resources = self.writes_all_resources || other.writes_all_resources;
         self.component_read_and_writes_inverted = component_read_and_writes_inverted;
         self.component_writes_inverted = component_writes_inverted;
-        self.resource_read_and_writes
-            .union_with(&other.resource_read_and_writes);
-        self.resource_writes.union_with(&other.resource_writes);
     }
 
     /// Returns `true` if the access and `other` can be active at the same time,
@@ -657,6 +726,21 @@ impl<T: SparseSetIndex> Access<T> {
     fn get_component_conflicts(&self, other: &Access<T>) -> AccessConflicts {
         let mut conflicts = FixedBitSet::new();
 
+        // Without inverted sets the conflicts are two plain intersections, which we can
+        // write straight into the result without a temporary bitset per direction.
+        if self.is_component_access_non_inverted() && other.is_component_access_non_inverted() {
+            conflicts.extend(
+                self.component_writes
+                    .intersection(&other.component_read_and_writes),
+            );
+            conflicts.extend(
+                other
+                    .component_writes
+                    .intersection(&self.component_read_and_writes),
+            );
+            return AccessConflicts::Individual(conflicts);
+        }
+
         // We have a conflict if we write and they read or write, or if they
         // write and we read or write.
         for (
@@ -751,6 +835,18 @@ impl<T: SparseSetIndex> Access<T> {
         self.resource_writes.ones().map(T::get_sparse_set_index)
     }
 
+    /// Returns the indices of the `!Send` resources this has access to.
+    pub fn non_send_resources(&self) -> impl Iterator<Item = T> + '_ {
+        self.non_send_resources.ones().map(T::get_sparse_set_index)
+    }
+
+    /// Returns the indices of the `Send` resources this has access to.
+    pub fn send_resources(&self) -> impl Iterator<Item = T> + '_ {
+        self.resource_read_and_writes
+            .difference(&self.non_send_resources)
+            .map(T::get_sparse_set_index)
+    }
+
     /// Returns the indices of the components that this has an archetypal access to.
     ///
     /// These are components whose values are not accessed (and thus will never cause conflicts),
@@ -863,7 +959,10 @@ impl<T: SparseSetIndex> From<FilteredAccess<T>> for FilteredAccessSet<T> {
 pub enum AccessConflicts {
     /// Conflict is for all indices
     All,
-    /// There is a conflict for a subset of indices
+    /// There is a conflict for a subset of indices.
+    ///
+    /// Indices are always enumerated in ascending order, regardless of the order in which the
+    /// conflicting accesses were added.
     Individual(FixedBitSet),
 }
 
@@ -978,6 +1077,16 @@ impl<T: SparseSetIndex> FilteredAccess<T> {
         self.access.add_resource_write(index.clone());
     }
 
+    /// Adds access to the `!Send` resource given by `index`.
+    pub fn add_non_send_resource_read(&mut self, index: T) {
+        self.access.add_non_send_resource_read(index);
+    }
+
+    /// Adds exclusive access to the `!Send` resource given by `index`.
+    pub fn add_non_send_resource_write(&mut self, index: T) {
+        self.access.add_non_send_resource_write(index);
+    }
+
     fn add_required(&mut self, index: T) {
         self.required.grow_and_insert(index.sparse_set_index());
     }
@@ -1178,6 +1287,22 @@ impl<T: SparseSetIndex> AccessFilters<T> {
     }
 }
 
+/// Handle to a [`FilteredAccess`] in a [`FilteredAccessSet`], returned from
+/// [`FilteredAccessSet::add`].
+///
+/// Handles are insertion indices: they are assigned in ascending order and stay stable as more
+/// accesses are added, which gives a deterministic order for reporting and diffing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FilteredAccessIndex(usize);
+
+impl FilteredAccessIndex {
+    /// Returns the insertion index of the access.
+    #[inline]
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
 /// A collection of [`FilteredAccess`] instances.
 ///
 /// Used internally to statically check if systems have conflicting access.
@@ -1253,6 +1378,34 @@ impl<T: SparseSetIndex> FilteredAccessSet<T> {
         conflicts
     }
 
+    /// Returns the conflicts between each pair of filtered accesses in this set and `other`.
+    ///
+    /// Each entry holds the [`FilteredAccessIndex`] of the access in `self`, the index of the
+    /// access in `other` and their conflicts. Pairs without conflicts are skipped and entries are
+    /// sorted by `self` index, then by `other` index, so the output is deterministic.
+    pub fn get_conflicts_by_index(
+        &self,
+        other: &FilteredAccessSet<T>,
+    ) -> Vec<(FilteredAccessIndex, FilteredAccessIndex, AccessConflicts)> {
+        let mut conflicts = Vec::new();
+        if self.combined_access.is_compatible(other.combined_access()) {
+            return conflicts;
+        }
+        for (index, filtered) in self.filtered_accesses.iter().enumerate() {
+            for (other_index, other_filtered) in other.filtered_accesses.iter().enumerate() {
+                let pair_conflicts = filtered.get_conflicts(other_filtered);
+                if !pair_conflicts.is_empty() {
+                    conflicts.push((
+                        FilteredAccessIndex(index),
+                        FilteredAccessIndex(other_index),
+                        pair_conflicts,
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+
     /// Returns a vector of elements that this set and `other` cannot access at the same time.
     pub fn get_conflicts_single(&self, filtered_access: &FilteredAccess<T>) -> AccessConflicts {
         // if the unfiltered access is incompatible, must check each pair
@@ -1265,10 +1418,111 @@ impl<T: SparseSetIndex> FilteredAccessSet<T> {
         conflicts
     }
 
+    /// Checks that this set and `other` can borrow the world at the same time from different
+    /// threads, returning a [`WorldSplit`] proving it.
+    ///
+    /// On top of [`is_compatible`](Self::is_compatible), which covers components and resources,
+    /// at most one of the two sets may access `!Send` resources, since only one of the views can
+    /// stay on the main thread. Access to all resources (e.g. through `&World`) counts as `!Send`
+    /// access here, as it can reach any `!Send` resource. The error reports the first offending
+    /// pair of accesses.
+    pub fn validate_split<'a>(
+        &'a self,
+        other: &'a FilteredAccessSet<T>,
+    ) -> Result<WorldSplit<'a, T>, WorldSplitError<T>> {
+        if let Some((first, second, conflicts)) =
+            self.get_conflicts_by_index(other).into_iter().next()
+        {
+            return Err(WorldSplitError::Conflict {
+                first,
+                second,
+                conflicts,
+            });
+        }
+
+        if let (Some((first, first_resource)), Some((second, second_resource))) =
+            (self.first_non_send_access(), other.first_non_send_access())
+        {
+            return Err(match (first_resource, second_resource) {
+                (Some(first_resource), Some(second_resource)) => {
+                    WorldSplitError::NonSendOnBothSides {
+                        first,
+                        second,
+                        first_resource,
+                        second_resource,
+                    }
+                }
+                _ => WorldSplitError::AllResourcesWithNonSend { first, second },
+            });
+        }
+
+        Ok(WorldSplit {
+            first: self,
+            second: other,
+        })
+    }
+
+    /// Returns `true` if this set may access a `!Send` resource, either by naming one or through
+    /// access to all resources, in which case it must be run on the main thread.
+    fn may_access_non_send(&self) -> bool {
+        self.has_any_non_send_resource()
+            || self.combined_access.has_read_all_resources()
+            || self.combined_access.has_write_all_resources()
+    }
+
+    /// Returns the first access that may reach a `!Send` resource, with the resource it names,
+    /// or `None` for an access to all resources.
+    fn first_non_send_access(&self) -> Option<(FilteredAccessIndex, Option<T>)> {
+        if !self.may_access_non_send() {
+            return None;
+        }
+        self.iter().find_map(|(index, filtered)| {
+            let access = filtered.access();
+            if let Some(resource) = access.non_send_resources().next() {
+                Some((index, Some(resource)))
+            } else if access.has_read_all_resources() || access.has_write_all_resources() {
+                Some((index, None))
+            } else {
+                None
+            }
+        })
+    }
+
     /// Adds the filtered access to the set.
-    pub fn add(&mut self, filtered_access: FilteredAccess<T>) {
+    ///
+    /// Returns the [`FilteredAccessIndex`] of the added access, which stays valid until the set is
+    /// [cleared](Self::clear).
+    pub fn add(&mut self, filtered_access: FilteredAccess<T>) -> FilteredAccessIndex {
+        let index = FilteredAccessIndex(self.filtered_accesses.len());
         self.combined_access.extend(&filtered_access.access);
         self.filtered_accesses.push(filtered_access);
+        index
+    }
+
+    /// Returns the filtered access with the given index.
+    #[inline]
+    pub fn get(&self, index: FilteredAccessIndex) -> Option<&FilteredAccess<T>> {
+        self.filtered_accesses.get(index.0)
+    }
+
+    /// Returns an iterator over the filtered accesses in this set, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (FilteredAccessIndex, &FilteredAccess<T>)> {
+        self.filtered_accesses
+            .iter()
+            .enumerate()
+            .map(|(index, filtered)| (FilteredAccessIndex(index), filtered))
+    }
+
+    /// Returns the number of filtered accesses in this set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.filtered_accesses.len()
+    }
+
+    /// Returns `true` if this set contains no filtered accesses.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.filtered_accesses.is_empty()
     }
 
     /// Adds a read access to a resource to the set.
@@ -1285,6 +1539,27 @@ impl<T: SparseSetIndex> FilteredAccessSet<T> {
         self.add(filter);
     }
 
+    /// Adds a read access to a `!Send` resource to the set.
+    pub(crate) fn add_unfiltered_non_send_resource_read(&mut self, index: T) {
+        let mut filter = FilteredAccess::default();
+        filter.add_non_send_resource_read(index);
+        self.add(filter);
+    }
+
+    /// Adds a write access to a `!Send` resource to the set.
+    pub(crate) fn add_unfiltered_non_send_resource_write(&mut self, index: T) {
+        let mut filter = FilteredAccess::default();
+        filter.add_non_send_resource_write(index);
+        self.add(filter);
+    }
+
+    /// Returns `true` if any access in this set is to a `!Send` resource, in which case the
+    /// whole set must be run on the main thread.
+    #[inline]
+    pub fn has_any_non_send_resource(&self) -> bool {
+        self.combined_access.has_any_non_send_resource()
+    }
+
     /// Adds read access to all resources to the set.
     pub(crate) fn add_unfiltered_read_all_resources(&mut self) {
         let mut filter = FilteredAccess::default();
@@ -1300,6 +1575,8 @@ impl<T: SparseSetIndex> FilteredAccessSet<T> {
     }
 
     /// Adds all of the accesses from the passed set to `self`.
+    ///
+    /// The added accesses keep their relative order and are placed after the existing ones.
     pub fn extend(&mut self, filtered_access_set: FilteredAccessSet<T>) {
         self.combined_access
             .extend(&filtered_access_set.combined_access);
@@ -1318,6 +1595,8 @@ impl<T: SparseSetIndex> FilteredAccessSet<T> {
     }
 
     /// Removes all accesses stored in this set.
+    ///
+    /// Any [`FilteredAccessIndex`] previously returned by [`add`](Self::add) is invalidated.
     pub fn clear(&mut self) {
         self.combined_access.clear();
         self.filtered_accesses.clear();
@@ -1333,10 +1612,133 @@ impl<T: SparseSetIndex> Default for FilteredAccessSet<T> {
     }
 }
 
+/// Proof that two [`FilteredAccessSet`]s can borrow the world at the same time, returned from
+/// [`FilteredAccessSet::validate_split`].
+///
+/// Meant to back APIs that hand out two disjoint views of a [`World`]. Both sets stay borrowed
+/// for as long as the proof is alive, so they can't change after being validated.
+#[derive(Debug, Clone, Copy)]
+pub struct WorldSplit<'a, T: SparseSetIndex> {
+    first: &'a FilteredAccessSet<T>,
+    second: &'a FilteredAccessSet<T>,
+}
+
+impl<'a, T: SparseSetIndex> WorldSplit<'a, T> {
+    /// Returns the access of the first view.
+    #[inline]
+    pub fn first(&self) -> &'a FilteredAccessSet<T> {
+        self.first
+    }
+
+    /// Returns the access of the second view.
+    #[inline]
+    pub fn second(&self) -> &'a FilteredAccessSet<T> {
+        self.second
+    }
+
+    /// Returns `true` if the first view has to stay on the main thread, because it accesses
+    /// `!Send` resources or all resources.
+    #[inline]
+    pub fn first_requires_main_thread(&self) -> bool {
+        self.first.may_access_non_send()
+    }
+
+    /// Returns `true` if the second view has to stay on the main thread, because it accesses
+    /// `!Send` resources or all resources.
+    #[inline]
+    pub fn second_requires_main_thread(&self) -> bool {
+        self.second.may_access_non_send()
+    }
+}
+
+/// Why two [`FilteredAccessSet`]s can't borrow the world at the same time, returned from
+/// [`FilteredAccessSet::validate_split`].
+#[derive(Error, Debug, PartialEq)]
+pub enum WorldSplitError<T: SparseSetIndex> {
+    /// A pair of accesses conflicts on components or resources.
+    #[error(
+        "access {} of the first set conflicts with access {} of the second set",
+        first.index(),
+        second.index()
+    )]
+    Conflict {
+        /// The conflicting access in the first set.
+        first: FilteredAccessIndex,
+        /// The conflicting access in the second set.
+        second: FilteredAccessIndex,
+        /// What the two accesses conflict on.
+        conflicts: AccessConflicts,
+    },
+    /// Both sets access `!Send` resources, so neither view could leave the main thread.
+    #[error(
+        "access {} of the first set and access {} of the second set both use `!Send` resources",
+        first.index(),
+        second.index()
+    )]
+    NonSendOnBothSides {
+        /// The first access in the first set using a `!Send` resource.
+        first: FilteredAccessIndex,
+        /// The first access in the second set using a `!Send` resource.
+        second: FilteredAccessIndex,
+        /// A `!Send` resource used by `first`.
+        first_resource: T,
+        /// A `!Send` resource used by `second`.
+        second_resource: T,
+    },
+    /// Both sets may access `!Send` resources, at least one of them through access to all
+    /// resources (e.g. `&World`), so neither view could leave the main thread.
+    #[error(
+        "access {} of the first set and access {} of the second set may both use `!Send` resources",
+        first.index(),
+        second.index()
+    )]
+    AllResourcesWithNonSend {
+        /// The first access in the first set that may use a `!Send` resource.
+        first: FilteredAccessIndex,
+        /// The first access in the second set that may use a `!Send` resource.
+        second: FilteredAccessIndex,
+    },
+}
+
+impl WorldSplitError<ComponentId> {
+    /// Returns a message naming the offending components or resources, looked up in `world`.
+    pub fn describe(&self, world: &World) -> String {
+        let name = |id: ComponentId| {
+            world
+                .components
+                .get_info(id)
+                .map(|info| format!("{}", ShortName(info.name())))
+                .unwrap_or_else(|| format!("{id:?}"))
+        };
+        match self {
+            WorldSplitError::Conflict {
+                conflicts: AccessConflicts::All,
+                ..
+            } => format!("{self}, both access the entire world"),
+            WorldSplitError::Conflict { conflicts, .. } => {
+                format!("{self} on {}", conflicts.format_conflict_list(world))
+            }
+            WorldSplitError::NonSendOnBothSides {
+                first_resource,
+                second_resource,
+                ..
+            } => format!(
+                "{self} ({} and {})",
+                name(*first_resource),
+                name(*second_resource)
+            ),
+            WorldSplitError::AllResourcesWithNonSend { .. } => {
+                format!("{self}, through access to all resources")
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::query::{
-        access::AccessFilters, Access, AccessConflicts, FilteredAccess, FilteredAccessSet,
+        access::{AccessFilters, FilteredAccessIndex},
+        Access, AccessConflicts, FilteredAccess, FilteredAccessSet, WorldSplitError,
     };
     use core::marker::PhantomData;
     use fixedbitset::FixedBitSet;
@@ -1525,6 +1927,37 @@ mod tests {
         assert_eq!(access_d.get_conflicts(&access_c), vec![0_usize].into());
     }
 
+    #[test]
+    fn access_extend() {
+        let mut access_a = Access::<usize>::default();
+        access_a.add_component_read(0);
+        access_a.add_component_write(1);
+
+        let mut access_b = Access::<usize>::default();
+        access_b.add_component_read(2);
+        access_b.add_resource_write(3);
+
+        // Neither side is inverted.
+        access_a.extend(&access_b);
+        assert!(access_a.has_component_read(0));
+        assert!(access_a.has_component_read(1));
+        assert!(access_a.has_component_read(2));
+        assert!(access_a.has_component_write(1));
+        assert!(!access_a.has_component_write(2));
+        assert!(access_a.has_resource_write(3));
+        assert!(!access_a.has_read_all_components());
+
+        // Extending with an inverted access must still take the inverted path.
+        let mut access_c = Access::<usize>::default();
+        access_c.read_all_components();
+        access_a.extend(&access_c);
+        assert!(access_a.has_read_all_components());
+        assert!(!access_a.has_write_all_components());
+        assert!(access_a.has_component_write(1));
+        assert!(!access_a.has_component_write(0));
+        assert!(access_a.has_resource_write(3));
+    }
+
     #[test]
     fn filtered_combined_access() {
         let mut access_a = FilteredAccessSet::<usize>::default();
@@ -1609,4 +2042,187 @@ mod tests {
 
         assert_eq!(access_a, expected);
     }
+
+    #[test]
+    fn filtered_access_set_indices() {
+        let mut access_a = FilteredAccessSet::<usize>::default();
+        let mut write_3 = FilteredAccess::<usize>::default();
+        write_3.add_component_write(3);
+        let mut read_1 = FilteredAccess::<usize>::default();
+        read_1.add_component_read(1);
+        let mut write_5 = FilteredAccess::<usize>::default();
+        write_5.add_component_write(5);
+
+        let first = access_a.add(write_3.clone());
+        let second = access_a.add(read_1.clone());
+        assert!(first < second);
+        assert_eq!(first.index(), 0);
+        assert_eq!(second.index(), 1);
+        assert_eq!(access_a.get(second), Some(&read_1));
+
+        let mut access_b = FilteredAccessSet::<usize>::default();
+        access_b.add(write_5.clone());
+        access_a.extend(access_b);
+        assert_eq!(access_a.len(), 3);
+        assert_eq!(
+            access_a
+                .iter()
+                .map(|(index, _)| index.index())
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(access_a.get(second), Some(&read_1));
+
+        let mut access_c = FilteredAccessSet::<usize>::default();
+        let mut write_all = FilteredAccess::<usize>::default();
+        write_all.add_component_write(5);
+        write_all.add_component_write(1);
+        write_all.add_component_write(3);
+        access_c.add(FilteredAccess::<usize>::default());
+        access_c.add(write_all);
+
+        let conflicts = access_a.get_conflicts_by_index(&access_c);
+        let pairs = conflicts
+            .iter()
+            .map(|(index, other_index, conflicts)| {
+                let AccessConflicts::Individual(indices) = conflicts else {
+                    panic!("expected individual conflicts");
+                };
+                (
+                    index.index(),
+                    other_index.index(),
+                    indices.ones().collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![(0, 1, vec![3]), (1, 1, vec![1]), (2, 1, vec![5])]
+        );
+        assert_eq!(
+            access_a.get_conflicts(&access_c),
+            AccessConflicts::from(vec![5_usize, 3, 1])
+        );
+
+        access_a.clear();
+        assert!(access_a.is_empty());
+        assert_eq!(access_a.get(first), None);
+    }
+
+    #[test]
+    fn non_send_resources() {
+        let mut access_a = Access::<usize>::default();
+        access_a.add_resource_read(0);
+        access_a.add_non_send_resource_write(1);
+        assert!(access_a.has_any_non_send_resource());
+        assert!(access_a.has_non_send_resource(1));
+        assert!(!access_a.has_non_send_resource(0));
+        assert!(access_a.has_resource_write(1));
+        assert_eq!(access_a.non_send_resources().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(access_a.send_resources().collect::<Vec<_>>(), vec![0]);
+
+        // Send-ness doesn't affect compatibility, only the read/write split does
+        let mut access_b = Access::<usize>::default();
+        access_b.add_resource_read(1);
+        assert!(!access_a.is_compatible(&access_b));
+        assert!(!access_b.has_any_non_send_resource());
+
+        let mut set = FilteredAccessSet::<usize>::default();
+        set.add_unfiltered_resource_read(0);
+        assert!(!set.has_any_non_send_resource());
+        set.add_unfiltered_non_send_resource_read(2);
+        assert!(set.has_any_non_send_resource());
+        assert!(set.combined_access().has_resource_read(2));
+
+        access_b.extend(&access_a);
+        assert_eq!(access_b.non_send_resources().collect::<Vec<_>>(), vec![1]);
+        access_b.clear();
+        assert!(!access_b.has_any_non_send_resource());
+    }
+
+    #[test]
+    fn validate_split() {
+        let mut filter_a = FilteredAccess::<usize>::default();
+        filter_a.add_component_write(0);
+        filter_a.add_resource_read(10);
+        let mut access_a = FilteredAccessSet::<usize>::default();
+        access_a.add(filter_a);
+
+        let mut filter_b = FilteredAccess::<usize>::default();
+        filter_b.add_component_read(1);
+        filter_b.add_resource_read(10);
+        let mut access_b = FilteredAccessSet::<usize>::default();
+        access_b.add(filter_b);
+        access_b.add_unfiltered_non_send_resource_write(11);
+
+        let split = access_a.validate_split(&access_b).unwrap();
+        assert!(!split.first_requires_main_thread());
+        assert!(split.second_requires_main_thread());
+
+        // Only one side may use `!Send` resources, even different ones
+        access_a.add_unfiltered_non_send_resource_read(12);
+        assert!(access_a.is_compatible(&access_b));
+        assert_eq!(
+            access_a.validate_split(&access_b).unwrap_err(),
+            WorldSplitError::NonSendOnBothSides {
+                first: FilteredAccessIndex(1),
+                second: FilteredAccessIndex(1),
+                first_resource: 12,
+                second_resource: 11,
+            }
+        );
+
+        // Conflicts are reported before `!Send` usage, for the first offending pair
+        let mut filter_c = FilteredAccess::<usize>::default();
+        filter_c.add_component_read(0);
+        let mut access_c = FilteredAccessSet::<usize>::default();
+        access_c.add_unfiltered_resource_write(10);
+        access_c.add(filter_c);
+        access_c.add_unfiltered_non_send_resource_read(13);
+        assert_eq!(
+            access_a.validate_split(&access_c).unwrap_err(),
+            WorldSplitError::Conflict {
+                first: FilteredAccessIndex(0),
+                second: FilteredAccessIndex(0),
+                conflicts: AccessConflicts::from(vec![10_usize]),
+            }
+        );
+    }
+
+    #[test]
+    fn validate_split_all_resources() {
+        // `&World` reads every resource, including any `!Send` one
+        let mut world_read = FilteredAccessSet::<usize>::default();
+        world_read.add_unfiltered_read_all_resources();
+
+        let mut non_send_read = FilteredAccessSet::<usize>::default();
+        non_send_read.add_unfiltered_non_send_resource_read(11);
+        assert!(world_read.is_compatible(&non_send_read));
+        assert_eq!(
+            world_read.validate_split(&non_send_read).unwrap_err(),
+            WorldSplitError::AllResourcesWithNonSend {
+                first: FilteredAccessIndex(0),
+                second: FilteredAccessIndex(0),
+            }
+        );
+
+        // Two readers of all resources don't conflict, but both would need the main thread
+        let mut other_world_read = FilteredAccessSet::<usize>::default();
+        other_world_read.add_unfiltered_read_all_resources();
+        assert!(world_read.is_compatible(&other_world_read));
+        assert_eq!(
+            world_read.validate_split(&other_world_read).unwrap_err(),
+            WorldSplitError::AllResourcesWithNonSend {
+                first: FilteredAccessIndex(0),
+                second: FilteredAccessIndex(0),
+            }
+        );
+
+        // Paired with `Send` resources only, the `&World` view keeps the main thread
+        let mut send_read = FilteredAccessSet::<usize>::default();
+        send_read.add_unfiltered_resource_read(10);
+        let split = world_read.validate_split(&send_read).unwrap();
+        assert!(split.first_requires_main_thread());
+        assert!(!split.second_requires_main_thread());
+    }
 }
@@ -1526,11 +1526,11 @@ unsafe impl<'a, T: 'static> SystemParam for NonSend<'a, T> {
         );
         system_meta
             .component_access_set
-            .add_unfiltered_resource_read(component_id);
+            .add_unfiltered_non_send_resource_read(component_id);
 
         system_meta
             .archetype_component_access
-            .add_resource_read(archetype_component_id);
+            .add_non_send_resource_read(archetype_component_id);
 
         component_id
     }
@@ -1637,11 +1637,11 @@ unsafe impl<'a, T: 'static> SystemParam for NonSendMut<'a, T> {
         }
         system_meta
             .component_access_set
-            .add_unfiltered_resource_write(component_id);
+            .add_unfiltered_non_send_resource_write(component_id);
 
         system_meta
             .archetype_component_access
-            .add_resource_write(archetype_component_id);
+            .add_non_send_resource_write(archetype_component_id);
 
         component_id
     }
@@ -1,5 +1,6 @@
 use crate::{
     multiprocessing::CpuFnDispatcher,
+    time::MonotonicTime,
     util::{
         lock_free_queue::{self, Receiver, Sender},
         spinlock::SpinLock,
@@ -36,12 +37,50 @@ struct Task<'a> {
 #[derive(Clone, Debug, Copy, Hash, Eq, PartialEq)]
 pub struct TaskId(u64);
 
+/// Warns when a single poll of a task holds on to the executor for too long
+#[derive(Clone)]
+pub struct StarvationDetector {
+    time: Arc<MonotonicTime>,
+    threshold_ticks: usize,
+}
+
+impl StarvationDetector {
+    pub fn new(time: Arc<MonotonicTime>, threshold_s: f32) -> StarvationDetector {
+        // Always allow at least one tick, a poll straddling a tick boundary is not starvation
+        let threshold_ticks = ((threshold_s * time.tick_freq()) as usize).max(1);
+        StarvationDetector {
+            time,
+            threshold_ticks,
+        }
+    }
+
+    fn begin(&self) -> usize {
+        self.time.get()
+    }
+
+    /// Returns true if the poll that started at start_tick ran past the threshold
+    fn end(&self, task_id: TaskId, start_tick: usize) -> bool {
+        let elapsed = self.time.get().wrapping_sub(start_tick);
+        if elapsed <= self.threshold_ticks {
+            return false;
+        }
+
+        warn!(
+            "Task {:?} monopolized the executor for {:.3}s, it should yield more often",
+            task_id,
+            elapsed as f32 / self.time.tick_freq()
+        );
+        true
+    }
+}
+
 pub struct Executor<'a> {
     cpu_dispatcher: Option<&'a CpuFnDispatcher>,
     id: TaskId,
     tasks: Arc<SpinLock<HashMap<TaskId, Task<'a>>>>,
     to_run: Receiver<TaskId>,
     queue_to_run: Sender<TaskId>,
+    starvation_detector: Option<StarvationDetector>,
 }
 
 impl<'a> Executor<'a> {
@@ -53,9 +92,14 @@ impl<'a> Executor<'a> {
             tasks: Arc::new(SpinLock::new(Default::default())),
             to_run,
             queue_to_run,
+            starvation_detector: None,
         }
     }
 
+    pub fn set_starvation_detector(&mut self, detector: StarvationDetector) {
+        self.starvation_detector = Some(detector);
+    }
+
     pub fn spawn<F: Future<Output = ()> + 'a + Send>(&mut self, fut: F) {
         let id = self.id;
         self.id.0 += 1;
@@ -124,10 +168,16 @@ impl<'a> Executor<'a> {
                     >(Arc::clone(&self.tasks))
                 };
 
+                let starvation_detector = self.starvation_detector.clone();
                 let poll_fn = move || {
                     let context_waker = Arc::clone(&task.waker).into();
                     let mut context = core::task::Context::from_waker(&context_waker);
-                    if task.future.as_mut().poll(&mut context).is_pending() {
+                    let start_tick = starvation_detector.as_ref().map(|d| d.begin());
+                    let poll_result = task.future.as_mut().poll(&mut context);
+                    if let (Some(detector), Some(start_tick)) = (&starvation_detector, start_tick) {
+                        detector.end(task_id, start_tick);
+                    }
+                    if poll_result.is_pending() {
                         tasks.lock().insert(task_id, task);
                     }
                 };
@@ -257,3 +307,144 @@ where
 pub fn poll_immediate<R, F: Future<Output = R>>(f: F) -> impl Future<Output = Option<R>> {
     PollImmediate { f: Some(f) }
 }
+
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Gives the executor a chance to run other tasks before continuing
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+pub struct Budgeted<F> {
+    f: F,
+    poll_budget: usize,
+    remaining: usize,
+}
+
+impl<F> Future for Budgeted<F>
+where
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // f is never moved out of self, so projecting the pin is fine
+        let self_mut = unsafe { self.get_unchecked_mut() };
+        if self_mut.remaining == 0 {
+            self_mut.remaining = self_mut.poll_budget;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        self_mut.remaining -= 1;
+        let f = unsafe { Pin::new_unchecked(&mut self_mut.f) };
+        f.poll(cx)
+    }
+}
+
+/// Wraps a long running future so that it is forced to yield back to the executor after every
+/// poll_budget polls, even if it would happily keep going
+pub fn budgeted<F: Future>(f: F, poll_budget: usize) -> Budgeted<F> {
+    let poll_budget = poll_budget.max(1);
+    Budgeted {
+        f,
+        poll_budget,
+        remaining: poll_budget,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::*;
+    use core::sync::atomic::AtomicUsize;
+
+    // Pending until it has been polled `polls` times, waking itself every time
+    struct PollCounter<'a> {
+        polls: &'a AtomicUsize,
+        ready_after: usize,
+    }
+
+    impl Future for PollCounter<'_> {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let polls = self.polls.fetch_add(1, Ordering::Relaxed) + 1;
+            if polls >= self.ready_after {
+                return Poll::Ready(());
+            }
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    struct CountOuterPolls<'a, F> {
+        f: F,
+        polls: &'a AtomicUsize,
+    }
+
+    impl<F: Future + Unpin> Future for CountOuterPolls<'_, F> {
+        type Output = F::Output;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            self.polls.fetch_add(1, Ordering::Relaxed);
+            Pin::new(&mut self.f).poll(cx)
+        }
+    }
+
+    create_test!(test_yield_now, {
+        test_true!(poll_immediate(yield_now()).await.is_none());
+        yield_now().await;
+        Ok(())
+    });
+
+    create_test!(test_budgeted_forces_yields, {
+        let inner_polls = AtomicUsize::new(0);
+        let outer_polls = AtomicUsize::new(0);
+        let inner = PollCounter {
+            polls: &inner_polls,
+            ready_after: 5,
+        };
+
+        CountOuterPolls {
+            f: budgeted(inner, 2),
+            polls: &outer_polls,
+        }
+        .await;
+
+        test_eq!(inner_polls.load(Ordering::Relaxed), 5);
+        // Two forced yields, after the 2nd and 4th inner poll
+        test_eq!(outer_polls.load(Ordering::Relaxed), 7);
+        Ok(())
+    });
+
+    create_test!(test_starvation_detector, {
+        let time = Arc::new(MonotonicTime::new(100.0));
+        let detector = StarvationDetector::new(Arc::clone(&time), 0.05);
+
+        time.set_tick(10);
+        let start = detector.begin();
+        time.set_tick(15);
+        test_false!(detector.end(TaskId(0), start));
+
+        time.set_tick(16);
+        test_true!(detector.end(TaskId(0), start));
+        Ok(())
+    });
+}
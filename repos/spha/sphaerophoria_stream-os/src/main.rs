@@ -66,7 +66,7 @@ use crate::{
     acpi::AcpiTable,
     cursor::Cursor,
     framebuffer::FrameBuffer,
-    future::{Either, Executor},
+    future::{Either, Executor, StarvationDetector},
     interrupts::{InitInterruptError, InterruptHandlerData},
     io::{
         io_allocator::IoAllocator,
@@ -95,6 +95,8 @@ use crate::{
 // naked function + some inline asm, but this seems much more straight forward.
 global_asm!(include_str!("boot.s"), options(att_syntax));
 
+// Polls taking longer than this get reported by the executor
+const STARVATION_THRESHOLD_S: f32 = 0.05;
 const STATIC_IP: [u8; 4] = [192, 168, 2, 2];
 
 extern "C" {
@@ -519,6 +521,10 @@ impl Kernel {
         };
 
         let mut executor = Executor::new(Some(&self.cpu_dispatcher));
+        executor.set_starvation_detector(StarvationDetector::new(
+            Arc::clone(&self.monotonic_time),
+            STARVATION_THRESHOLD_S,
+        ));
         executor.spawn(logger::service());
         executor.spawn(init_demo);
         executor.spawn(recv);
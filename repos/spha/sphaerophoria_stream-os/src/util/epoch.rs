@@ -0,0 +1,342 @@
+#![allow(unused)]
+
+use alloc::{boxed::Box, vec::Vec};
+use core::ops::{Deref, DerefMut};
+
+use crate::util::{
+    interrupt_guard::{InterruptGuard, InterruptGuarded},
+    spinlock::{SpinLock, SpinLockGuard},
+};
+
+pub const MAX_PARTICIPANTS: usize = 32;
+
+// Retire as many nodes as this before we try to free anything on our own
+const COLLECT_THRESHOLD: usize = 64;
+
+// Slot value for a participant that is not currently pinned. Sequence numbers start at 1 so that
+// a pinned slot is never confused with a free one
+const UNPINNED: u64 = 0;
+
+struct Deferred {
+    // Sequence number at the time the pointer was retired
+    seq: u64,
+    ptr: *mut (),
+    drop_fn: unsafe fn(*mut ()),
+}
+
+unsafe impl Send for Deferred {}
+
+unsafe fn drop_box<T>(ptr: *mut ()) {
+    drop(Box::from_raw(ptr as *mut T));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochStats {
+    pub sequence: u64,
+    pub pinned: usize,
+    pub pending: usize,
+    pub max_pending: usize,
+    pub freed: u64,
+}
+
+struct State {
+    sequence: u64,
+    slots: [u64; MAX_PARTICIPANTS],
+    garbage: Vec<Deferred>,
+    max_pending: usize,
+    freed: u64,
+}
+
+impl State {
+    fn oldest_pinned(&self) -> Option<u64> {
+        self.slots
+            .iter()
+            .copied()
+            .filter(|seq| *seq != UNPINNED)
+            .min()
+    }
+}
+
+/// Defers freeing of nodes removed from lock free structures until no reader can still be
+/// looking at them.
+///
+/// Readers pin the collector for the duration of their access. Every retired node is tagged with
+/// a monotonically increasing u64 sequence number, and is only freed once every pinned reader
+/// pinned after the node was retired. A u64 will not wrap in any realistic uptime, so there is no
+/// epoch wraparound handling.
+///
+/// We do not have 64 bit atomics on i686, so the bookkeeping lives behind a spinlock. The lock is
+/// only held for a handful of instructions, the nodes themselves are still accessed lock free.
+/// Interrupts are masked while it is held, so an interrupt handler can pin and retire without
+/// spinning on a lock the code it interrupted holds
+pub struct Collector {
+    interrupts: InterruptGuarded<()>,
+    state: SpinLock<State>,
+}
+
+// Field order matters, the spinlock has to be released before interrupts are enabled again
+struct StateGuard<'a> {
+    state: SpinLockGuard<'a, State>,
+    _interrupts: InterruptGuard<'a, ()>,
+}
+
+impl Deref for StateGuard<'_> {
+    type Target = State;
+
+    fn deref(&self) -> &State {
+        &self.state
+    }
+}
+
+impl DerefMut for StateGuard<'_> {
+    fn deref_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+}
+
+impl Collector {
+    pub const fn new() -> Collector {
+        Collector {
+            interrupts: InterruptGuarded::new(()),
+            state: SpinLock::new(State {
+                sequence: UNPINNED + 1,
+                slots: [UNPINNED; MAX_PARTICIPANTS],
+                garbage: Vec::new(),
+                max_pending: 0,
+                freed: 0,
+            }),
+        }
+    }
+
+    fn lock(&self) -> StateGuard<'_> {
+        let interrupts = self.interrupts.lock();
+        StateGuard {
+            state: self.state.lock(),
+            _interrupts: interrupts,
+        }
+    }
+
+    /// Marks the caller as a reader. Nodes retired after this point will not be freed until the
+    /// returned guard is dropped
+    ///
+    /// # Panics
+    /// If all MAX_PARTICIPANTS slots are already pinned. Waiting for a slot could wait on a
+    /// guard held further up our own stack, use [`Collector::try_pin`] to handle it instead
+    pub fn pin(&self) -> Guard<'_> {
+        self.try_pin().unwrap_or_else(|| {
+            panic!("epoch collector is out of slots, all {MAX_PARTICIPANTS} are pinned")
+        })
+    }
+
+    /// Like [`Collector::pin`], but returns None if all MAX_PARTICIPANTS slots are pinned
+    pub fn try_pin(&self) -> Option<Guard<'_>> {
+        let mut state = self.lock();
+        let seq = state.sequence;
+        let slot = state.slots.iter().position(|slot| *slot == UNPINNED)?;
+        state.slots[slot] = seq;
+        Some(Guard {
+            collector: self,
+            slot,
+        })
+    }
+
+    /// Schedules `ptr` to be dropped once no pinned reader can observe it
+    ///
+    /// # Safety
+    /// `ptr` must come from `Box::into_raw`, must already be unreachable for new readers, and must
+    /// not be retired twice
+    pub unsafe fn defer_free<T: Send>(&self, ptr: *mut T) {
+        let pending = {
+            let mut state = self.lock();
+            let seq = state.sequence;
+            state.sequence += 1;
+            state.garbage.push(Deferred {
+                seq,
+                ptr: ptr as *mut (),
+                drop_fn: drop_box::<T>,
+            });
+            state.max_pending = state.max_pending.max(state.garbage.len());
+            state.garbage.len()
+        };
+
+        if pending >= COLLECT_THRESHOLD {
+            self.collect();
+        }
+    }
+
+    /// Frees every retired node that no pinned reader can still reference, returning how many
+    /// were freed
+    pub fn collect(&self) -> usize {
+        // Run the drops outside of the lock so that a drop impl can retire more nodes
+        let reclaimable: Vec<Deferred> = {
+            let mut state = self.lock();
+            let oldest_pinned = state.oldest_pinned();
+            let is_reclaimable = |deferred: &Deferred| match oldest_pinned {
+                Some(oldest) => deferred.seq < oldest,
+                None => true,
+            };
+
+            let mut reclaimable = Vec::new();
+            let mut i = 0;
+            while i < state.garbage.len() {
+                if is_reclaimable(&state.garbage[i]) {
+                    reclaimable.push(state.garbage.swap_remove(i));
+                } else {
+                    i += 1;
+                }
+            }
+            state.freed += reclaimable.len() as u64;
+            reclaimable
+        };
+
+        let num_freed = reclaimable.len();
+        for deferred in reclaimable {
+            unsafe {
+                (deferred.drop_fn)(deferred.ptr);
+            }
+        }
+
+        num_freed
+    }
+
+    pub fn stats(&self) -> EpochStats {
+        let state = self.lock();
+        EpochStats {
+            sequence: state.sequence,
+            pinned: state.slots.iter().filter(|slot| **slot != UNPINNED).count(),
+            pending: state.garbage.len(),
+            max_pending: state.max_pending,
+            freed: state.freed,
+        }
+    }
+}
+
+impl Drop for Collector {
+    fn drop(&mut self) {
+        // Nobody can be pinned anymore since guards borrow the collector
+        for deferred in core::mem::take(&mut self.lock().garbage) {
+            unsafe {
+                (deferred.drop_fn)(deferred.ptr);
+            }
+        }
+    }
+}
+
+pub struct Guard<'a> {
+    collector: &'a Collector,
+    slot: usize,
+}
+
+impl Guard<'_> {
+    /// Sequence number this guard pinned at
+    pub fn sequence(&self) -> u64 {
+        self.collector.lock().slots[self.slot]
+    }
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        self.collector.lock().slots[self.slot] = UNPINNED;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::*;
+
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn retire(collector: &Collector, drops: &Arc<AtomicUsize>) {
+        let node = Box::into_raw(Box::new(DropCounter(Arc::clone(drops))));
+        unsafe {
+            collector.defer_free(node);
+        }
+    }
+
+    create_test!(unpinned_frees_immediately, {
+        let collector = Collector::new();
+        let drops = Arc::new(AtomicUsize::new(0));
+        retire(&collector, &drops);
+        test_eq!(collector.collect(), 1);
+        test_eq!(drops.load(Ordering::SeqCst), 1);
+        Ok(())
+    });
+
+    create_test!(pinned_reader_blocks_free, {
+        let collector = Collector::new();
+        let drops = Arc::new(AtomicUsize::new(0));
+
+        let guard = collector.pin();
+        retire(&collector, &drops);
+        test_eq!(collector.collect(), 0);
+        test_eq!(collector.stats().pending, 1);
+
+        drop(guard);
+        test_eq!(collector.collect(), 1);
+        test_eq!(drops.load(Ordering::SeqCst), 1);
+        Ok(())
+    });
+
+    create_test!(later_pin_does_not_block_earlier_retire, {
+        let collector = Collector::new();
+        let drops = Arc::new(AtomicUsize::new(0));
+
+        retire(&collector, &drops);
+        let _guard = collector.pin();
+        test_eq!(collector.collect(), 1);
+        test_eq!(drops.load(Ordering::SeqCst), 1);
+        Ok(())
+    });
+
+    create_test!(stats_track_backlog, {
+        let collector = Collector::new();
+        let drops = Arc::new(AtomicUsize::new(0));
+
+        let guard = collector.pin();
+        retire(&collector, &drops);
+        retire(&collector, &drops);
+        let stats = collector.stats();
+        test_eq!(stats.pinned, 1);
+        test_eq!(stats.pending, 2);
+        test_eq!(stats.max_pending, 2);
+
+        drop(guard);
+        collector.collect();
+        let stats = collector.stats();
+        test_eq!(stats.pending, 0);
+        test_eq!(stats.freed, 2);
+        Ok(())
+    });
+
+    create_test!(exhausted_slots_fail_to_pin, {
+        let collector = Collector::new();
+        let mut guards: Vec<Guard<'_>> = (0..MAX_PARTICIPANTS).map(|_| collector.pin()).collect();
+        test_eq!(collector.stats().pinned, MAX_PARTICIPANTS);
+        test_true!(collector.try_pin().is_none());
+
+        drop(guards.pop());
+        test_true!(collector.try_pin().is_some());
+        Ok(())
+    });
+
+    create_test!(drop_frees_backlog, {
+        let drops = Arc::new(AtomicUsize::new(0));
+        {
+            let collector = Collector::new();
+            let _guard = collector.pin();
+            retire(&collector, &drops);
+        }
+        test_eq!(drops.load(Ordering::SeqCst), 1);
+        Ok(())
+    });
+}
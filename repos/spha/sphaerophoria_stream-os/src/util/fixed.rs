@@ -0,0 +1,450 @@
+#![allow(unused)]
+
+use core::{
+    fmt,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+const FRAC_BITS: u32 = 16;
+const FRAC_MASK: i32 = (1 << FRAC_BITS) - 1;
+
+// Number of table steps in a quarter sine period
+const QUARTER_STEPS: usize = 256;
+const PERIOD_STEPS: i64 = QUARTER_STEPS as i64 * 4;
+
+/// Q16.16 fixed point number
+///
+/// Everything except the f32 conversions is integer only, so this can be used in interrupt
+/// handlers without having to save FPU state
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+pub struct Fx32(i32);
+
+impl Fx32 {
+    pub const ZERO: Fx32 = Fx32(0);
+    pub const ONE: Fx32 = Fx32(1 << FRAC_BITS);
+    pub const HALF: Fx32 = Fx32(1 << (FRAC_BITS - 1));
+    pub const MAX: Fx32 = Fx32(i32::MAX);
+    pub const MIN: Fx32 = Fx32(i32::MIN);
+    /// Smallest representable positive value
+    pub const EPSILON: Fx32 = Fx32(1);
+    pub const PI: Fx32 = Fx32(205887);
+    pub const FRAC_PI_2: Fx32 = Fx32(102944);
+    pub const TWO_PI: Fx32 = Fx32(411775);
+
+    pub const fn from_bits(bits: i32) -> Fx32 {
+        Fx32(bits)
+    }
+
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    /// Only -32768..=32767 fit in the integer part. Anything outside of that wraps, which debug
+    /// builds catch with an assertion
+    pub const fn from_int(val: i32) -> Fx32 {
+        debug_assert!(
+            val >= Self::MIN.to_int() && val <= Self::MAX.to_int(),
+            "integer out of range for Fx32"
+        );
+        Fx32(val << FRAC_BITS)
+    }
+
+    /// Builds `num / denom` without going through floating point, e.g. `from_ratio(3, 4)` for 0.75.
+    /// Saturates like [`Fx32::saturating_div`] when the result is out of range or `denom` is zero
+    pub const fn from_ratio(num: i32, denom: i32) -> Fx32 {
+        // The raw values are both scaled by the same factor, so their quotient is num / denom
+        Fx32(num).saturating_div(Fx32(denom))
+    }
+
+    /// Rounds towards negative infinity
+    pub const fn to_int(self) -> i32 {
+        self.0 >> FRAC_BITS
+    }
+
+    /// Rounds halves towards positive infinity
+    pub const fn round(self) -> i32 {
+        // Widened so values within half of MAX don't overflow
+        ((self.0 as i64 + Self::HALF.0 as i64) >> FRAC_BITS) as i32
+    }
+
+    pub const fn frac(self) -> Fx32 {
+        Fx32(self.0 & FRAC_MASK)
+    }
+
+    pub const fn abs(self) -> Fx32 {
+        Fx32(self.0.wrapping_abs())
+    }
+
+    /// Uses the FPU, do not call from interrupt context
+    pub fn from_f32(val: f32) -> Fx32 {
+        Fx32((val * Self::ONE.0 as f32) as i32)
+    }
+
+    /// Uses the FPU, do not call from interrupt context
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / Self::ONE.0 as f32
+    }
+
+    pub const fn saturating_add(self, other: Fx32) -> Fx32 {
+        Fx32(self.0.saturating_add(other.0))
+    }
+
+    pub const fn saturating_sub(self, other: Fx32) -> Fx32 {
+        Fx32(self.0.saturating_sub(other.0))
+    }
+
+    pub const fn saturating_mul(self, other: Fx32) -> Fx32 {
+        let val = (self.0 as i64 * other.0 as i64) >> FRAC_BITS;
+        if val > i32::MAX as i64 {
+            Self::MAX
+        } else if val < i32::MIN as i64 {
+            Self::MIN
+        } else {
+            Fx32(val as i32)
+        }
+    }
+
+    /// Division by zero saturates towards the sign of `self`, 0 / 0 is zero
+    pub const fn saturating_div(self, other: Fx32) -> Fx32 {
+        if other.0 == 0 {
+            return if self.0 > 0 {
+                Self::MAX
+            } else if self.0 < 0 {
+                Self::MIN
+            } else {
+                Self::ZERO
+            };
+        }
+
+        let val = ((self.0 as i64) << FRAC_BITS) / other.0 as i64;
+        if val > i32::MAX as i64 {
+            Self::MAX
+        } else if val < i32::MIN as i64 {
+            Self::MIN
+        } else {
+            Fx32(val as i32)
+        }
+    }
+
+    pub const fn checked_div(self, other: Fx32) -> Option<Fx32> {
+        if other.0 == 0 {
+            return None;
+        }
+
+        let val = ((self.0 as i64) << FRAC_BITS) / other.0 as i64;
+        if val > i32::MAX as i64 || val < i32::MIN as i64 {
+            None
+        } else {
+            Some(Fx32(val as i32))
+        }
+    }
+
+    /// Square root, negative inputs return zero
+    pub fn sqrt(self) -> Fx32 {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+
+        // sqrt(x / 2^16) * 2^16 == sqrt(x * 2^16)
+        Fx32(isqrt((self.0 as u64) << FRAC_BITS) as i32)
+    }
+
+    /// Sine of an angle in radians, linearly interpolated from a quarter wave table
+    pub fn sin(self) -> Fx32 {
+        // Position in the period measured in table steps, with FRAC_BITS of fraction
+        let pos = ((self.0 as i64 * PERIOD_STEPS) << FRAC_BITS) / Self::TWO_PI.0 as i64;
+        let pos = pos.rem_euclid(PERIOD_STEPS << FRAC_BITS);
+
+        let step = pos >> FRAC_BITS;
+        let frac = pos & FRAC_MASK as i64;
+
+        let a = sin_step(step) as i64;
+        let b = sin_step((step + 1) % PERIOD_STEPS) as i64;
+        Fx32((a + (((b - a) * frac) >> FRAC_BITS)) as i32)
+    }
+
+    pub fn cos(self) -> Fx32 {
+        (self + Self::FRAC_PI_2).sin()
+    }
+}
+
+// Sine at the given table step in 0..PERIOD_STEPS, mirroring the quarter wave
+fn sin_step(step: i64) -> i32 {
+    let quarter = QUARTER_STEPS as i64;
+    let idx = (step % quarter) as usize;
+    match step / quarter {
+        0 => SIN_TABLE[idx],
+        1 => SIN_TABLE[QUARTER_STEPS - idx],
+        2 => -SIN_TABLE[idx],
+        _ => -SIN_TABLE[QUARTER_STEPS - idx],
+    }
+}
+
+fn isqrt(val: u64) -> u64 {
+    let mut remainder = val;
+    let mut result = 0u64;
+    let mut bit = 1u64 << 62;
+
+    while bit > remainder {
+        bit >>= 2;
+    }
+
+    while bit != 0 {
+        if remainder >= result + bit {
+            remainder -= result + bit;
+            result = (result >> 1) + bit;
+        } else {
+            result >>= 1;
+        }
+        bit >>= 2;
+    }
+
+    result
+}
+
+// sin(x) for x in [0, pi/2], evaluated at compile time so no floating point ends up in the
+// kernel image
+const SIN_TABLE: [i32; QUARTER_STEPS + 1] = build_sin_table();
+
+const fn build_sin_table() -> [i32; QUARTER_STEPS + 1] {
+    let mut table = [0; QUARTER_STEPS + 1];
+    let mut i = 0;
+    while i <= QUARTER_STEPS {
+        let x = core::f64::consts::FRAC_PI_2 * i as f64 / QUARTER_STEPS as f64;
+        // Taylor series, plenty accurate on [0, pi/2] for 16 fractional bits
+        let x2 = x * x;
+        let mut term = x;
+        let mut sum = x;
+        let mut n = 1;
+        while n < 10 {
+            term = -term * x2 / ((2 * n) * (2 * n + 1)) as f64;
+            sum += term;
+            n += 1;
+        }
+        let scaled = sum * (1 << FRAC_BITS) as f64 + 0.5;
+        table[i] = scaled as i32;
+        i += 1;
+    }
+    table
+}
+
+impl Add for Fx32 {
+    type Output = Fx32;
+
+    fn add(self, other: Fx32) -> Fx32 {
+        Fx32(self.0.wrapping_add(other.0))
+    }
+}
+
+impl Sub for Fx32 {
+    type Output = Fx32;
+
+    fn sub(self, other: Fx32) -> Fx32 {
+        Fx32(self.0.wrapping_sub(other.0))
+    }
+}
+
+impl Mul for Fx32 {
+    type Output = Fx32;
+
+    fn mul(self, other: Fx32) -> Fx32 {
+        Fx32(((self.0 as i64 * other.0 as i64) >> FRAC_BITS) as i32)
+    }
+}
+
+// Saturates instead of panicking, so a division in an interrupt handler can't take the kernel
+// down. Use checked_div to find out whether it did
+impl Div for Fx32 {
+    type Output = Fx32;
+
+    fn div(self, other: Fx32) -> Fx32 {
+        self.saturating_div(other)
+    }
+}
+
+impl Neg for Fx32 {
+    type Output = Fx32;
+
+    fn neg(self) -> Fx32 {
+        Fx32(self.0.wrapping_neg())
+    }
+}
+
+impl AddAssign for Fx32 {
+    fn add_assign(&mut self, other: Fx32) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Fx32 {
+    fn sub_assign(&mut self, other: Fx32) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign for Fx32 {
+    fn mul_assign(&mut self, other: Fx32) {
+        *self = *self * other;
+    }
+}
+
+impl DivAssign for Fx32 {
+    fn div_assign(&mut self, other: Fx32) {
+        *self = *self / other;
+    }
+}
+
+impl From<i32> for Fx32 {
+    fn from(val: i32) -> Fx32 {
+        Fx32::from_int(val)
+    }
+}
+
+impl fmt::Display for Fx32 {
+    // Prints 4 decimal places using integer math only
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = (self.0 as i64).abs();
+        let int = abs >> FRAC_BITS;
+        let frac = ((abs & FRAC_MASK as i64) * 10000 + (1 << (FRAC_BITS - 1))) >> FRAC_BITS;
+        // Rounding the fraction up can carry into the integer part
+        let (int, frac) = if frac == 10000 {
+            (int + 1, 0)
+        } else {
+            (int, frac)
+        };
+        write!(f, "{}{}.{:04}", sign, int, frac)
+    }
+}
+
+impl fmt::Debug for Fx32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fx32({})", self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::*;
+
+    fn close(a: Fx32, b: Fx32, tolerance: Fx32) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    create_test!(arithmetic, {
+        let a = Fx32::from_ratio(3, 2);
+        let b = Fx32::from_int(2);
+        test_eq!(a + b, Fx32::from_ratio(7, 2));
+        test_eq!(a - b, Fx32::from_ratio(-1, 2));
+        test_eq!(a * b, Fx32::from_int(3));
+        test_eq!(a / b, Fx32::from_ratio(3, 4));
+        test_eq!(-a, Fx32::from_ratio(-3, 2));
+        Ok(())
+    });
+
+    create_test!(conversions, {
+        test_eq!(Fx32::from_int(-3).to_int(), -3);
+        test_eq!(Fx32::from_ratio(-1, 2).to_int(), -1);
+        test_eq!(Fx32::from_ratio(5, 2).round(), 3);
+        test_eq!(Fx32::from_ratio(-5, 2).round(), -2);
+        test_eq!(Fx32::MAX.round(), 32768);
+        test_eq!(Fx32::MIN.round(), -32768);
+        test_eq!(Fx32::from_int(32767).to_int(), 32767);
+        test_eq!(Fx32::from_int(-32768).to_int(), -32768);
+        test_eq!(Fx32::from_ratio(9, 4).frac(), Fx32::from_ratio(1, 4));
+        test_eq!(Fx32::from_f32(1.5), Fx32::from_ratio(3, 2));
+        test_eq!(Fx32::from_ratio(-5, 4).to_f32(), -1.25);
+        Ok(())
+    });
+
+    create_test!(saturating_and_checked, {
+        test_eq!(Fx32::MAX.saturating_add(Fx32::ONE), Fx32::MAX);
+        test_eq!(Fx32::MIN.saturating_sub(Fx32::ONE), Fx32::MIN);
+        test_eq!(
+            Fx32::from_int(30000).saturating_mul(Fx32::from_int(30000)),
+            Fx32::MAX
+        );
+        test_eq!(Fx32::ONE.checked_div(Fx32::ZERO), None::<Fx32>);
+        test_eq!(
+            Fx32::ONE.checked_div(Fx32::from_int(4)),
+            Some(Fx32::from_ratio(1, 4))
+        );
+        test_eq!(
+            Fx32::from_int(20000).checked_div(Fx32::from_ratio(1, 2)),
+            None::<Fx32>
+        );
+        test_eq!(Fx32::MIN.checked_div(-Fx32::ONE), None::<Fx32>);
+        Ok(())
+    });
+
+    create_test!(div_saturates, {
+        test_eq!(Fx32::ONE / Fx32::ZERO, Fx32::MAX);
+        test_eq!(-Fx32::ONE / Fx32::ZERO, Fx32::MIN);
+        test_eq!(Fx32::ZERO.saturating_div(Fx32::ZERO), Fx32::ZERO);
+        test_eq!(Fx32::from_int(20000) / Fx32::from_ratio(1, 2), Fx32::MAX);
+        test_eq!(Fx32::from_int(-20000) / Fx32::from_ratio(1, 2), Fx32::MIN);
+        test_eq!(Fx32::MIN / -Fx32::ONE, Fx32::MAX);
+        test_eq!(
+            Fx32::from_int(3) / Fx32::from_int(-4),
+            Fx32::from_ratio(-3, 4)
+        );
+        Ok(())
+    });
+
+    create_test!(from_ratio_saturates, {
+        test_eq!(Fx32::from_ratio(1, 0), Fx32::MAX);
+        test_eq!(Fx32::from_ratio(-1, 0), Fx32::MIN);
+        test_eq!(Fx32::from_ratio(0, 0), Fx32::ZERO);
+        test_eq!(Fx32::from_ratio(40000, 1), Fx32::MAX);
+        test_eq!(Fx32::from_ratio(i32::MIN, 1), Fx32::MIN);
+        test_eq!(
+            Fx32::from_ratio(65535, 2),
+            Fx32::from_bits(65535 << (FRAC_BITS - 1))
+        );
+        test_eq!(Fx32::from_ratio(-6, -4), Fx32::from_ratio(3, 2));
+        Ok(())
+    });
+
+    create_test!(sqrt, {
+        test_eq!(Fx32::from_int(4).sqrt(), Fx32::from_int(2));
+        test_eq!(Fx32::from_ratio(1, 4).sqrt(), Fx32::HALF);
+        test_eq!(Fx32::from_int(-4).sqrt(), Fx32::ZERO);
+        test_true!(close(
+            Fx32::from_int(2).sqrt(),
+            Fx32::from_bits(92682),
+            Fx32::EPSILON
+        ));
+        Ok(())
+    });
+
+    create_test!(trig, {
+        let tolerance = Fx32::from_ratio(1, 1000);
+        test_true!(close(Fx32::ZERO.sin(), Fx32::ZERO, tolerance));
+        test_true!(close(Fx32::FRAC_PI_2.sin(), Fx32::ONE, tolerance));
+        test_true!(close(Fx32::PI.sin(), Fx32::ZERO, tolerance));
+        test_true!(close((-Fx32::FRAC_PI_2).sin(), -Fx32::ONE, tolerance));
+        test_true!(close(Fx32::ZERO.cos(), Fx32::ONE, tolerance));
+        test_true!(close(Fx32::PI.cos(), -Fx32::ONE, tolerance));
+        // sin(pi / 6) == 0.5
+        test_true!(close(
+            (Fx32::PI / Fx32::from_int(6)).sin(),
+            Fx32::HALF,
+            tolerance
+        ));
+        // Wraps around for angles outside of a single period
+        test_true!(close(
+            (Fx32::FRAC_PI_2 + Fx32::TWO_PI * Fx32::from_int(3)).sin(),
+            Fx32::ONE,
+            tolerance
+        ));
+        Ok(())
+    });
+
+    create_test!(display, {
+        test_eq!(alloc::format!("{}", Fx32::from_ratio(5, 4)), "1.2500");
+        test_eq!(alloc::format!("{}", Fx32::from_ratio(-1, 2)), "-0.5000");
+        test_eq!(alloc::format!("{}", Fx32::from_bits(65535)), "1.0000");
+        Ok(())
+    });
+}
@@ -2,10 +2,13 @@ pub mod async_channel;
 pub mod async_mutex;
 pub mod atomic_cell;
 pub mod bit_manipulation;
+pub mod epoch;
+pub mod fixed;
 pub mod histogram;
 pub mod interrupt_guard;
 pub mod lock_free_queue;
 pub mod oneshot;
+pub mod pool;
 pub mod spinlock;
 pub mod updated_val;
 pub mod waker_list;
@@ -0,0 +1,201 @@
+#![allow(unused)]
+
+use core::{
+    cell::UnsafeCell,
+    ops::{Deref, DerefMut},
+};
+
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::util::{interrupt_guard::InterruptGuarded, spinlock::SpinLock};
+
+/// Fixed capacity set of reusable objects. Objects are checked out with `try_acquire` and go
+/// back into the pool when the returned handle is dropped, without any allocation after
+/// construction. Objects are handed out again as they were returned, callers reset whatever
+/// state they care about.
+///
+/// The free list lives behind a spinlock, so the pool can be shared between CPUs. It is taken
+/// with interrupts disabled, so interrupt handlers can check out and return objects as well.
+pub struct Pool<T> {
+    // None until first checked out when constructing on demand
+    slots: Box<[UnsafeCell<Option<T>>]>,
+    // Indices of slots not currently checked out. Allocated with the full capacity up front so
+    // pushing back never reallocates.
+    free: SpinLock<Vec<usize>>,
+    interrupts: InterruptGuarded<()>,
+    init: Option<Box<dyn Fn() -> T + Send + Sync>>,
+}
+
+impl<T> Pool<T> {
+    /// Pool of `capacity` objects, each built by `init` the first time its slot is checked out
+    pub fn new<F>(capacity: usize, init: F) -> Pool<T>
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        Pool::with_slots((0..capacity).map(|_| None).collect(), Some(Box::new(init)))
+    }
+
+    /// Pool over objects that are already constructed
+    pub fn from_objects(objects: Vec<T>) -> Pool<T> {
+        Pool::with_slots(objects.into_iter().map(Some).collect(), None)
+    }
+
+    fn with_slots(
+        slots: Vec<Option<T>>,
+        init: Option<Box<dyn Fn() -> T + Send + Sync>>,
+    ) -> Pool<T> {
+        // Reversed so objects are handed out in index order
+        let free = (0..slots.len()).rev().collect();
+        Pool {
+            slots: slots.into_iter().map(UnsafeCell::new).collect(),
+            free: SpinLock::new(free),
+            interrupts: InterruptGuarded::new(()),
+            init,
+        }
+    }
+
+    // Same lock order as the allocator, interrupts first so a handler on this CPU can't spin on
+    // a lock it interrupted
+    fn with_free_list<R>(&self, f: impl FnOnce(&mut Vec<usize>) -> R) -> R {
+        let _interrupts = self.interrupts.lock();
+        let mut free = self.free.lock();
+        f(&mut free)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Number of objects that can currently be checked out
+    pub fn available(&self) -> usize {
+        self.with_free_list(|free| free.len())
+    }
+
+    /// Checks out an object, None if all of them are in use
+    pub fn try_acquire(&self) -> Option<PoolHandle<'_, T>> {
+        let index = self.with_free_list(Vec::pop)?;
+
+        // SAFETY: index was on the free list, so nothing else references this slot until the
+        // handle puts it back
+        let slot = unsafe { &mut *self.slots[index].get() };
+        if slot.is_none() {
+            let init = self
+                .init
+                .as_ref()
+                .expect("Pools without init always have constructed objects");
+            *slot = Some(init());
+        }
+
+        Some(PoolHandle { pool: self, index })
+    }
+
+    fn release(&self, index: usize) {
+        self.with_free_list(|free| free.push(index));
+    }
+}
+
+// Slots are only ever accessed through the handle owning their index
+unsafe impl<T: Send> Sync for Pool<T> {}
+unsafe impl<T: Send> Send for Pool<T> {}
+
+/// Checked out object, returned to its pool on drop
+pub struct PoolHandle<'a, T> {
+    pool: &'a Pool<T>,
+    index: usize,
+}
+
+impl<'a, T> PoolHandle<'a, T> {
+    /// Slot of this object in the pool, stable across checkouts. Useful for e.g. matching DMA
+    /// descriptors to the hardware ring position they were set up for.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<'a, T> Deref for PoolHandle<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: see try_acquire, the slot is constructed before a handle exists
+        unsafe {
+            (*self.pool.slots[self.index].get())
+                .as_ref()
+                .unwrap_unchecked()
+        }
+    }
+}
+
+impl<'a, T> DerefMut for PoolHandle<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe {
+            (*self.pool.slots[self.index].get())
+                .as_mut()
+                .unwrap_unchecked()
+        }
+    }
+}
+
+impl<'a, T> Drop for PoolHandle<'a, T> {
+    fn drop(&mut self) {
+        self.pool.release(self.index);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::*;
+
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    create_test!(test_pool_exhaustion, {
+        let pool = Pool::from_objects(alloc::vec![0u32, 1, 2]);
+        test_eq!(pool.capacity(), 3);
+
+        let a = pool.try_acquire().unwrap();
+        let b = pool.try_acquire().unwrap();
+        let c = pool.try_acquire().unwrap();
+        test_eq!((*a, *b, *c), (0, 1, 2));
+        test_true!(pool.try_acquire().is_none());
+        test_eq!(pool.available(), 0);
+
+        drop(b);
+        test_eq!(pool.available(), 1);
+        let b = pool.try_acquire().unwrap();
+        test_eq!(b.index(), 1);
+        test_eq!(*b, 1);
+        Ok(())
+    });
+
+    create_test!(test_pool_objects_are_reused, {
+        let pool = Pool::from_objects(alloc::vec![Vec::<u8>::new()]);
+        {
+            let mut buf = pool.try_acquire().unwrap();
+            buf.extend_from_slice(&[1, 2, 3]);
+        }
+
+        let buf = pool.try_acquire().unwrap();
+        test_eq!(buf.as_slice(), &[1, 2, 3]);
+        Ok(())
+    });
+
+    create_test!(test_pool_constructs_on_demand, {
+        let constructed = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&constructed);
+        let pool = Pool::new(4, move || counter.fetch_add(1, Ordering::Relaxed));
+        test_eq!(constructed.load(Ordering::Relaxed), 0);
+
+        let first = pool.try_acquire().unwrap();
+        test_eq!(*first, 0);
+        drop(first);
+
+        // The same slot is handed out again, nothing new is built
+        let first = pool.try_acquire().unwrap();
+        test_eq!(*first, 0);
+        let second = pool.try_acquire().unwrap();
+        test_eq!(*second, 1);
+        test_eq!(constructed.load(Ordering::Relaxed), 2);
+        Ok(())
+    });
+}
@@ -0,0 +1,220 @@
+// Walks a simulated agent along a path from Navigation, re-pathing whenever it drifts too far
+// off course. Run from the directory containing Navigation.dll / libNavigation.so:
+//
+//     cargo run --example agent_sim -- --speed 7 --drift 1.5 --smooth
+
+use nav_wrapper::nav::{NavError, Navigation, XYZ};
+use nav_wrapper::session::NavSession;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const USAGE: &str = "usage: agent_sim [--map <id>] [--speed <yards/s>] [--arrival-radius <yards>] \
+[--repath-distance <yards>] [--tick <seconds>] [--drift <yards/s>] [--smooth]";
+
+// Gives up instead of looping forever on a path that can't be followed
+const MAX_REPATHS: u32 = 10;
+const MAX_TICKS: u32 = 100_000;
+
+struct Options {
+    map_id: u32,
+    speed: f32,
+    arrival_radius: f32,
+    repath_distance: f32,
+    tick: f32,
+    // Sideways push, simulates the agent being knocked off its path
+    drift: f32,
+    smooth: bool,
+}
+
+impl Options {
+    fn parse() -> Result<Options, String> {
+        let mut options = Options {
+            map_id: 0,
+            speed: 7.0,
+            arrival_radius: 1.0,
+            repath_distance: 5.0,
+            tick: 0.1,
+            drift: 0.0,
+            smooth: false,
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--smooth" {
+                options.smooth = true;
+                continue;
+            }
+            let value = args.next().ok_or_else(|| format!("{} requires a value", arg))?;
+            match arg.as_str() {
+                "--map" => options.map_id = parse_value(&arg, &value)?,
+                "--speed" => options.speed = parse_value(&arg, &value)?,
+                "--arrival-radius" => options.arrival_radius = parse_value(&arg, &value)?,
+                "--repath-distance" => options.repath_distance = parse_value(&arg, &value)?,
+                "--tick" => options.tick = parse_value(&arg, &value)?,
+                "--drift" => options.drift = parse_value(&arg, &value)?,
+                _ => return Err(USAGE.to_string()),
+            }
+        }
+
+        // Written as !(x > 0.0) so NaN is rejected too, which `x <= 0.0` would let through
+        let positive = [options.speed, options.tick, options.arrival_radius, options.repath_distance];
+        #[allow(clippy::neg_cmp_op_on_partial_ord)]
+        let invalid = positive.iter().any(|value| !(*value > 0.0));
+        if invalid {
+            return Err("--speed, --tick, --arrival-radius and --repath-distance must be positive".to_string());
+        }
+        Ok(options)
+    }
+}
+
+fn parse_value<T: FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value '{}' for {}", value, arg))
+}
+
+fn distance(a: XYZ, b: XYZ) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+}
+
+// Distance from p to the segment a-b
+fn distance_to_segment(p: XYZ, a: XYZ, b: XYZ) -> f32 {
+    let ab = (b.x - a.x, b.y - a.y, b.z - a.z);
+    let length_sq = ab.0 * ab.0 + ab.1 * ab.1 + ab.2 * ab.2;
+    if length_sq == 0.0 {
+        return distance(p, a);
+    }
+    let t = (((p.x - a.x) * ab.0 + (p.y - a.y) * ab.1 + (p.z - a.z) * ab.2) / length_sq).clamp(0.0, 1.0);
+    distance(p, XYZ::new(a.x + ab.0 * t, a.y + ab.1 * t, a.z + ab.2 * t))
+}
+
+struct Agent {
+    position: XYZ,
+    path: Vec<XYZ>,
+    // Index of the waypoint currently walked towards, path[waypoint - 1] is the one behind
+    waypoint: usize,
+}
+
+impl Agent {
+    fn new(position: XYZ, path: Vec<XYZ>) -> Agent {
+        // The first point is the start position itself
+        Agent { position, path, waypoint: 1 }
+    }
+
+    fn arrived(&self) -> bool {
+        self.waypoint >= self.path.len()
+    }
+
+    // How far the agent is from the segment it should be walking along
+    fn deviation(&self) -> f32 {
+        if self.arrived() {
+            return 0.0;
+        }
+        distance_to_segment(self.position, self.path[self.waypoint - 1], self.path[self.waypoint])
+    }
+
+    fn step(&mut self, options: &Options) {
+        let mut budget = options.speed * options.tick;
+        while !self.arrived() && budget > 0.0 {
+            let target = self.path[self.waypoint];
+            let remaining = distance(self.position, target);
+            if remaining <= budget {
+                self.position = target;
+                budget -= remaining;
+            } else {
+                let t = budget / remaining;
+                let heading = ((target.x - self.position.x) / remaining, (target.y - self.position.y) / remaining);
+                self.position.x += (target.x - self.position.x) * t;
+                self.position.y += (target.y - self.position.y) * t;
+                self.position.z += (target.z - self.position.z) * t;
+                // Push sideways, to the right of the heading on the ground plane
+                self.position.x += heading.1 * options.drift * options.tick;
+                self.position.y -= heading.0 * options.drift * options.tick;
+                budget = 0.0;
+            }
+
+            if distance(self.position, target) <= options.arrival_radius {
+                self.waypoint += 1;
+            }
+        }
+    }
+}
+
+// Retries once after a panic, like the main binary does
+fn query_path(session: &Arc<NavSession>, options: &Options, start: XYZ, end: XYZ) -> Result<Vec<XYZ>, NavError> {
+    match session.calculate_path(options.map_id, start, end, options.smooth) {
+        Err(NavError::Panicked) => {
+            println!("Navigation panicked, reloading and retrying once...");
+            session.reset()?;
+            session.calculate_path(options.map_id, start, end, options.smooth)
+        }
+        result => result,
+    }
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let session = NavSession::new(Navigation::load()?);
+
+    let start = XYZ::new(-10531.08, -1189.0, 28.0);
+    let end = XYZ::new(-10501.042, -1185.1096, 28.1375);
+
+    let path = query_path(&session, options, start, end)?;
+    println!("Initial path: {} points, {:.2} yards straight line", path.len(), distance(start, end));
+    let mut agent = Agent::new(start, path);
+
+    let mut repaths = 0;
+    let mut ticks = 0;
+    while !agent.arrived() {
+        if ticks >= MAX_TICKS {
+            return Err(format!("agent did not arrive within {} ticks", MAX_TICKS).into());
+        }
+        ticks += 1;
+        agent.step(options);
+
+        let deviation = agent.deviation();
+        println!(
+            "t={:>7.2}s pos=({:.2}, {:.2}, {:.2}) waypoint {}/{} deviation {:.2} to goal {:.2}",
+            ticks as f32 * options.tick,
+            agent.position.x,
+            agent.position.y,
+            agent.position.z,
+            agent.waypoint.min(agent.path.len() - 1),
+            agent.path.len() - 1,
+            deviation,
+            distance(agent.position, end)
+        );
+
+        if deviation > options.repath_distance {
+            if repaths >= MAX_REPATHS {
+                return Err(format!("gave up after {} re-paths", MAX_REPATHS).into());
+            }
+            repaths += 1;
+            println!("Off course by {:.2} yards, re-pathing ({}/{})", deviation, repaths, MAX_REPATHS);
+            let path = query_path(&session, options, agent.position, end)?;
+            agent = Agent::new(agent.position, path);
+        }
+    }
+
+    println!(
+        "Arrived after {:.2}s ({} ticks, {} re-paths), {:.2} yards from the goal",
+        ticks as f32 * options.tick,
+        ticks,
+        repaths,
+        distance(agent.position, end)
+    );
+    session.print_stats();
+    Ok(())
+}
+
+fn main() {
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(&options) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,47 @@
+/* C API of the safe Navigation wrapper (src/capi.rs), link against nav_wrapper */
+#ifndef NAV_WRAPPER_H
+#define NAV_WRAPPER_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define NAV_OK 0
+#define NAV_ERR_NULL_ARGUMENT 1
+#define NAV_ERR_INVALID_INPUT 2
+#define NAV_ERR_LOAD 3
+#define NAV_ERR_NO_PATH 4
+/* A Rust panic unwound out of Navigation. C++ exceptions and crashes are not caught. */
+#define NAV_ERR_PANICKED 5
+#define NAV_ERR_POISONED 6
+#define NAV_ERR_INTERNAL 7
+
+typedef struct NavHandle NavHandle;
+
+typedef struct XYZ {
+    float x;
+    float y;
+    float z;
+} XYZ;
+
+/* path may be NULL to use NAVIGATION_LIB or the default library name */
+int nav_open(const char *path, NavHandle **out_handle);
+void nav_close(NavHandle *handle);
+
+/* On success *out_points must be released with nav_free_path */
+int nav_calculate_path(const NavHandle *handle, unsigned int map_id, XYZ start, XYZ end,
+                       int smooth, XYZ **out_points, size_t *out_length);
+void nav_free_path(XYZ *points, size_t length);
+
+/* Reloads the library after NAV_ERR_PANICKED */
+int nav_reset(const NavHandle *handle);
+
+const char *nav_error_message(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,171 @@
+// C API over the safe wrapper, see include/nav_wrapper.h. Callers get validated input,
+// panics contained at the boundary and paths they free through nav_free_path instead of
+// dealing with the raw Navigation library directly.
+
+use crate::nav::{NavError, Navigation, XYZ};
+use crate::session::NavSession;
+use libc::{c_char, c_int, c_uint};
+use std::ffi::{CStr, OsString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Arc;
+
+pub const NAV_OK: c_int = 0;
+pub const NAV_ERR_NULL_ARGUMENT: c_int = 1;
+pub const NAV_ERR_INVALID_INPUT: c_int = 2;
+pub const NAV_ERR_LOAD: c_int = 3;
+pub const NAV_ERR_NO_PATH: c_int = 4;
+pub const NAV_ERR_PANICKED: c_int = 5;
+pub const NAV_ERR_POISONED: c_int = 6;
+// Something inside the wrapper itself panicked
+pub const NAV_ERR_INTERNAL: c_int = 7;
+
+// Opaque to C, only ever handled through a pointer
+pub struct NavHandle {
+    session: Arc<NavSession>,
+}
+
+fn error_code(error: &NavError) -> c_int {
+    match error {
+        NavError::Load(_) => NAV_ERR_LOAD,
+        NavError::NoPath => NAV_ERR_NO_PATH,
+        NavError::Panicked => NAV_ERR_PANICKED,
+        NavError::Poisoned => NAV_ERR_POISONED,
+    }
+}
+
+fn is_valid_point(point: &XYZ) -> bool {
+    point.x.is_finite() && point.y.is_finite() && point.z.is_finite()
+}
+
+// Runs f, turning a panic in our own code into NAV_ERR_INTERNAL so it never crosses into C
+fn guarded(f: impl FnOnce() -> c_int) -> c_int {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(NAV_ERR_INTERNAL)
+}
+
+/// Loads Navigation from `path`, or from the default location when `path` is null.
+///
+/// # Safety
+/// `path` must be null or a valid NUL terminated string, `out_handle` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nav_open(path: *const c_char, out_handle: *mut *mut NavHandle) -> c_int {
+    if out_handle.is_null() {
+        return NAV_ERR_NULL_ARGUMENT;
+    }
+    *out_handle = ptr::null_mut();
+
+    guarded(|| {
+        let navigation = if path.is_null() {
+            Navigation::load()
+        } else {
+            match CStr::from_ptr(path).to_str() {
+                Ok(path) => Navigation::load_from(OsString::from(path)),
+                Err(_) => return NAV_ERR_INVALID_INPUT,
+            }
+        };
+
+        match navigation {
+            Ok(navigation) => {
+                let handle = Box::new(NavHandle {
+                    session: NavSession::new(navigation),
+                });
+                *out_handle = Box::into_raw(handle);
+                NAV_OK
+            }
+            Err(e) => error_code(&e),
+        }
+    })
+}
+
+/// Unloads Navigation. Null is ignored.
+///
+/// # Safety
+/// `handle` must be null or come from `nav_open` and not have been closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn nav_close(handle: *mut NavHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Calculates a path on `map_id`. On success `*out_points` holds `*out_length` points which
+/// must be released with `nav_free_path`, on failure they are set to null and 0.
+///
+/// # Safety
+/// `handle` must come from `nav_open`, `out_points` and `out_length` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nav_calculate_path(
+    handle: *const NavHandle,
+    map_id: c_uint,
+    start: XYZ,
+    end: XYZ,
+    smooth: c_int,
+    out_points: *mut *mut XYZ,
+    out_length: *mut usize,
+) -> c_int {
+    if handle.is_null() || out_points.is_null() || out_length.is_null() {
+        return NAV_ERR_NULL_ARGUMENT;
+    }
+    *out_points = ptr::null_mut();
+    *out_length = 0;
+
+    if !is_valid_point(&start) || !is_valid_point(&end) {
+        return NAV_ERR_INVALID_INPUT;
+    }
+
+    let session = &(*handle).session;
+    guarded(|| match session.calculate_path(map_id, start, end, smooth != 0) {
+        Ok(path) => {
+            let path = path.into_boxed_slice();
+            *out_length = path.len();
+            *out_points = Box::into_raw(path) as *mut XYZ;
+            NAV_OK
+        }
+        Err(e) => error_code(&e),
+    })
+}
+
+/// Releases a path returned by `nav_calculate_path`. Null is ignored.
+///
+/// # Safety
+/// `points` and `length` must be exactly what `nav_calculate_path` returned, freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn nav_free_path(points: *mut XYZ, length: usize) {
+    if !points.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(points, length)));
+    }
+}
+
+/// Reloads Navigation after NAV_ERR_PANICKED so calls can succeed again.
+///
+/// # Safety
+/// `handle` must come from `nav_open`.
+#[no_mangle]
+pub unsafe extern "C" fn nav_reset(handle: *const NavHandle) -> c_int {
+    if handle.is_null() {
+        return NAV_ERR_NULL_ARGUMENT;
+    }
+
+    let session = &(*handle).session;
+    guarded(|| match session.reset() {
+        Ok(()) => NAV_OK,
+        Err(e) => error_code(&e),
+    })
+}
+
+/// Static description of an error code, never null and never freed by the caller.
+#[no_mangle]
+pub extern "C" fn nav_error_message(code: c_int) -> *const c_char {
+    let message: &'static [u8] = match code {
+        NAV_OK => b"ok\0",
+        NAV_ERR_NULL_ARGUMENT => b"a required pointer argument was null\0",
+        NAV_ERR_INVALID_INPUT => b"invalid input\0",
+        NAV_ERR_LOAD => b"failed to load Navigation\0",
+        NAV_ERR_NO_PATH => b"no path found\0",
+        NAV_ERR_PANICKED => b"Navigation panicked during the call\0",
+        NAV_ERR_POISONED => b"Navigation is poisoned by an earlier panic, reset it first\0",
+        NAV_ERR_INTERNAL => b"internal error in the wrapper\0",
+        _ => b"unknown error code\0",
+    };
+    message.as_ptr() as *const c_char
+}
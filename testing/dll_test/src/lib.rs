@@ -0,0 +1,8 @@
+// Safe wrapper around the Navigation library. Used by the dll_test binary and also built as a
+// cdylib exposing a C API (capi.rs) and, with the `python` feature, a Python module.
+
+pub mod capi;
+pub mod nav;
+#[cfg(feature = "python")]
+mod python;
+pub mod session;
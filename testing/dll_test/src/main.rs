@@ -1,31 +1,31 @@
-use libc::{c_float, c_int, c_uint};
-use std::ptr;
-use std::ffi::CString;
-use std::os::raw::c_char;
-use std::slice;
+use nav_wrapper::nav::{NavError, Navigation, XYZ};
+use nav_wrapper::session::{self, NavSession};
+use std::path::Path;
 
 // OBS: copy Navigation.dll to source dir to be able to run from there... Or Run from same dir as
 // build.rs...
 
-#[repr(C)]
-struct XYZ {
-    x: c_float,
-    y: c_float,
-    z: c_float,
-}
-
-#[link(name = "Navigation", kind = "dylib")]
-extern "C" {
-    fn CalculatePath(
-        id: c_uint,
-        start: XYZ,
-        end: XYZ,
-        smooth_path: c_int,
-        path_length: *mut c_int,
-    ) -> *mut XYZ;
-}
+// Optional list of map ids to load in the background at startup
+const PREWARM_LIST: &str = "prewarm_maps.txt";
 
 fn main() {
+    let navigation = match Navigation::load() {
+        Ok(navigation) => navigation,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let session = NavSession::new(navigation);
+
+    let prewarm_maps = session::load_prewarm_list(Path::new(PREWARM_LIST));
+    let prewarm = if prewarm_maps.is_empty() {
+        None
+    } else {
+        println!("Prewarming maps {:?}...", prewarm_maps);
+        Some(session.prewarm(prewarm_maps))
+    };
+
     let start = XYZ {
         x: -10531.080078125,
         y: -1189.0,
@@ -38,29 +38,45 @@ fn main() {
         z: 28.13749926004446,
     };
 
-    let mut path_length: c_int = 0;
-
-    unsafe {
-        println!("calling function...");
-
-        let path_ptr = CalculatePath(0, start, end, 0, &mut path_length);
+    println!("calling function...");
 
-        if !path_ptr.is_null() && path_length > 0 {
-            println!("Path Length: {}", path_length);
+    let mut result = session.calculate_path(0, start, end, false);
+    if let Err(NavError::Panicked) = result {
+        println!("Navigation panicked, reloading and retrying once...");
+        result = match session.reset() {
+            Ok(()) => session.calculate_path(0, start, end, false),
+            Err(e) => Err(e),
+        };
+    }
 
-            let path_slice = slice::from_raw_parts(path_ptr, path_length as usize);
+    match result {
+        Ok(path) => {
+            println!("Path Length: {}", path.len());
 
-            for (i, point) in path_slice.iter().enumerate() {
+            for (i, point) in path.iter().enumerate() {
                 println!(
                     "Point {}: X={}, Y={}, Z={}",
                     i, point.x, point.y, point.z
                 );
             }
-        } else {
-            println!("Failed to calculate path or an error occurred.");
         }
+        Err(e) => println!("Failed to calculate path: {}", e),
+    }
+
+    if session.is_poisoned() {
+        println!("Navigation is poisoned, further calls will fail until it is reset.");
+    }
+
+    if let Some(stats) = session.map_stats(0) {
+        println!("Map 0 first query took {:?}", stats.first_query_time.unwrap_or_default());
     }
 
+    if let Some(handle) = prewarm {
+        let _ = handle.join();
+    }
+
+    println!("Queried maps: {:?}", session.queried_maps());
+    session.print_stats();
+
     println!("End.");
 }
-
@@ -0,0 +1,145 @@
+use libc::{c_float, c_int, c_uint};
+use libloading::Library;
+use std::ffi::OsString;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XYZ {
+    pub x: c_float,
+    pub y: c_float,
+    pub z: c_float,
+}
+
+impl XYZ {
+    pub fn new(x: f32, y: f32, z: f32) -> XYZ {
+        XYZ { x, y, z }
+    }
+}
+
+// "C-unwind" so a Rust panic unwinding out of the library reaches catch_unwind instead of being
+// UB. That is all the isolation there is: a C++ exception is not a Rust panic and aborts the
+// process when it reaches catch_unwind, and an access violation or other crash inside the
+// library takes the whole process down. Only a worker process would contain those.
+type CalculatePathFn =
+    unsafe extern "C-unwind" fn(c_uint, XYZ, XYZ, c_int, *mut c_int) -> *mut XYZ;
+
+// Overrides where the library is loaded from, defaults to Navigation.dll / libNavigation.so
+// next to the working directory
+const LIBRARY_PATH_VAR: &str = "NAVIGATION_LIB";
+
+#[derive(Debug)]
+pub enum NavError {
+    Load(libloading::Error),
+    NoPath,
+    // A Rust panic unwound out of the call, the library state can no longer be trusted. C++
+    // exceptions and crashes are not caught, they end the process instead.
+    Panicked,
+    // An earlier call panicked and reset() hasn't been called since
+    Poisoned,
+}
+
+impl fmt::Display for NavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NavError::Load(e) => write!(f, "failed to load Navigation: {}", e),
+            NavError::NoPath => write!(f, "no path found"),
+            NavError::Panicked => write!(f, "Navigation panicked during the call"),
+            NavError::Poisoned => write!(f, "Navigation is poisoned by an earlier panic, reset it first"),
+        }
+    }
+}
+
+impl std::error::Error for NavError {}
+
+struct Loaded {
+    // Kept alive for as long as calculate_path may be called
+    _library: Library,
+    calculate_path: CalculatePathFn,
+}
+
+pub struct Navigation {
+    path: OsString,
+    loaded: Option<Loaded>,
+    poisoned: bool,
+}
+
+impl Navigation {
+    pub fn load() -> Result<Navigation, NavError> {
+        let path = std::env::var_os(LIBRARY_PATH_VAR)
+            .unwrap_or_else(|| libloading::library_filename("Navigation"));
+        Navigation::load_from(path)
+    }
+
+    pub fn load_from(path: OsString) -> Result<Navigation, NavError> {
+        let mut navigation = Navigation {
+            path,
+            loaded: None,
+            poisoned: false,
+        };
+        navigation.reload()?;
+        Ok(navigation)
+    }
+
+    fn reload(&mut self) -> Result<(), NavError> {
+        // Drop the old handle first, otherwise the loader just hands back the same instance
+        self.loaded = None;
+
+        unsafe {
+            let library = Library::new(&self.path).map_err(NavError::Load)?;
+            let calculate_path = *library
+                .get::<CalculatePathFn>(b"CalculatePath\0")
+                .map_err(NavError::Load)?;
+            self.loaded = Some(Loaded {
+                _library: library,
+                calculate_path,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    // Unloads and reloads the library, clearing the poison flag on success
+    pub fn reset(&mut self) -> Result<(), NavError> {
+        self.reload()?;
+        self.poisoned = false;
+        Ok(())
+    }
+
+    // Calls into Navigation and copies the result out of the native buffer
+    pub fn calculate_path(&mut self, map_id: u32, start: XYZ, end: XYZ, smooth: bool) -> Result<Vec<XYZ>, NavError> {
+        if self.poisoned {
+            return Err(NavError::Poisoned);
+        }
+        let calculate_path = match &self.loaded {
+            Some(loaded) => loaded.calculate_path,
+            None => return Err(NavError::Poisoned),
+        };
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+            let mut path_length: c_int = 0;
+            let path_ptr = calculate_path(map_id, start, end, smooth as c_int, &mut path_length);
+
+            if path_ptr.is_null() || path_length <= 0 {
+                return None;
+            }
+
+            Some(slice::from_raw_parts(path_ptr, path_length as usize).to_vec())
+        }));
+
+        match result {
+            Ok(Some(path)) => Ok(path),
+            Ok(None) => Err(NavError::NoPath),
+            Err(_) => {
+                self.poisoned = true;
+                Err(NavError::Panicked)
+            }
+        }
+    }
+}
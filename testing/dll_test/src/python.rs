@@ -0,0 +1,66 @@
+// Python bindings, built with `--features python` (e.g. through maturin)
+
+use crate::nav::{NavError, Navigation, XYZ};
+use crate::session::NavSession;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use std::ffi::OsString;
+use std::sync::Arc;
+
+type Point = (f32, f32, f32);
+
+fn to_py_err(error: NavError) -> PyErr {
+    PyRuntimeError::new_err(error.to_string())
+}
+
+fn to_xyz(point: Point) -> PyResult<XYZ> {
+    let (x, y, z) = point;
+    if !(x.is_finite() && y.is_finite() && z.is_finite()) {
+        return Err(PyValueError::new_err("coordinates must be finite"));
+    }
+    Ok(XYZ::new(x, y, z))
+}
+
+#[pyclass(name = "Navigation")]
+struct PyNavigation {
+    session: Arc<NavSession>,
+}
+
+#[pymethods]
+impl PyNavigation {
+    #[new]
+    #[pyo3(signature = (path=None))]
+    fn new(path: Option<String>) -> PyResult<Self> {
+        let navigation = match path {
+            Some(path) => Navigation::load_from(OsString::from(path)),
+            None => Navigation::load(),
+        };
+        Ok(PyNavigation {
+            session: NavSession::new(navigation.map_err(to_py_err)?),
+        })
+    }
+
+    #[pyo3(signature = (map_id, start, end, smooth=false))]
+    fn calculate_path(&self, py: Python<'_>, map_id: u32, start: Point, end: Point, smooth: bool) -> PyResult<Vec<Point>> {
+        let (start, end) = (to_xyz(start)?, to_xyz(end)?);
+        let session = Arc::clone(&self.session);
+        let path = py
+            .allow_threads(move || session.calculate_path(map_id, start, end, smooth))
+            .map_err(to_py_err)?;
+        Ok(path.into_iter().map(|point| (point.x, point.y, point.z)).collect())
+    }
+
+    fn reset(&self) -> PyResult<()> {
+        self.session.reset().map_err(to_py_err)
+    }
+
+    fn is_poisoned(&self) -> bool {
+        self.session.is_poisoned()
+    }
+}
+
+#[pymodule]
+fn nav_wrapper(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyNavigation>()?;
+    Ok(())
+}
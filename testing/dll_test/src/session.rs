@@ -0,0 +1,232 @@
+use crate::nav::{NavError, Navigation, XYZ};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default)]
+pub struct MapStats {
+    pub queries: u32,
+    pub failures: u32,
+    pub panics: u32,
+    pub total_points: usize,
+    pub total_time: Duration,
+    // Time of the first call on this map, which includes loading the navmesh unless it was
+    // prewarmed
+    pub first_query_time: Option<Duration>,
+    pub prewarmed: bool,
+    // Time of the prewarm call, kept apart so it doesn't count as a query
+    pub prewarm_time: Option<Duration>,
+}
+
+impl MapStats {
+    pub fn average_time(&self) -> Duration {
+        if self.queries == 0 {
+            Duration::ZERO
+        } else {
+            self.total_time / self.queries
+        }
+    }
+
+    fn record(&mut self, path: &Result<Vec<XYZ>, NavError>, elapsed: Duration) {
+        self.queries += 1;
+        self.total_time += elapsed;
+        self.first_query_time.get_or_insert(elapsed);
+        match path {
+            Ok(points) => self.total_points += points.len(),
+            Err(NavError::Panicked) => {
+                self.failures += 1;
+                self.panics += 1;
+            }
+            Err(_) => self.failures += 1,
+        }
+    }
+
+    fn record_prewarm(&mut self, elapsed: Duration) {
+        self.prewarmed = true;
+        self.prewarm_time = Some(elapsed);
+    }
+}
+
+// Keeps track of which maps have been queried through Navigation and how they perform.
+// Native calls are serialized since the library gives no thread-safety guarantees.
+pub struct NavSession {
+    navigation: Mutex<Navigation>,
+    stats: Mutex<BTreeMap<u32, MapStats>>,
+}
+
+impl NavSession {
+    pub fn new(navigation: Navigation) -> Arc<NavSession> {
+        Arc::new(NavSession {
+            navigation: Mutex::new(navigation),
+            stats: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    pub fn calculate_path(&self, map_id: u32, start: XYZ, end: XYZ, smooth: bool) -> Result<Vec<XYZ>, NavError> {
+        let (path, elapsed) = self.timed_call(map_id, start, end, smooth);
+
+        self.stats.lock().unwrap().entry(map_id).or_default().record(&path, elapsed);
+        path
+    }
+
+    fn timed_call(&self, map_id: u32, start: XYZ, end: XYZ, smooth: bool) -> (Result<Vec<XYZ>, NavError>, Duration) {
+        let mut navigation = self.navigation.lock().unwrap();
+        let begin = Instant::now();
+        let path = navigation.calculate_path(map_id, start, end, smooth);
+        (path, begin.elapsed())
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.navigation.lock().unwrap().is_poisoned()
+    }
+
+    // Reloads Navigation after a panic so a long running process can keep serving requests.
+    // Loaded navmeshes are gone after this, so maps are marked as no longer prewarmed.
+    pub fn reset(&self) -> Result<(), NavError> {
+        self.navigation.lock().unwrap().reset()?;
+        for stats in self.stats.lock().unwrap().values_mut() {
+            stats.prewarmed = false;
+        }
+        Ok(())
+    }
+
+    // Forces Navigation to load the navmesh for each map on a background thread.
+    // The dummy query result is ignored, only the load side effect matters.
+    pub fn prewarm(self: &Arc<Self>, map_ids: Vec<u32>) -> JoinHandle<()> {
+        let session = Arc::clone(self);
+        thread::spawn(move || {
+            for map_id in map_ids {
+                let origin = XYZ::new(0.0, 0.0, 0.0);
+                let (result, elapsed) = session.timed_call(map_id, origin, origin, false);
+                if let Err(e @ (NavError::Panicked | NavError::Poisoned)) = result {
+                    eprintln!("Stopping prewarm at map {}: {}", map_id, e);
+                    return;
+                }
+
+                session.stats.lock().unwrap().entry(map_id).or_default().record_prewarm(elapsed);
+                println!("Prewarmed map {} in {:?}", map_id, elapsed);
+            }
+        })
+    }
+
+    pub fn queried_maps(&self) -> Vec<u32> {
+        self.stats
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, stats)| stats.queries > 0)
+            .map(|(map_id, _)| *map_id)
+            .collect()
+    }
+
+    pub fn map_stats(&self, map_id: u32) -> Option<MapStats> {
+        self.stats.lock().unwrap().get(&map_id).cloned()
+    }
+
+    pub fn print_stats(&self) {
+        let stats = self.stats.lock().unwrap();
+        println!("{:>6} {:>8} {:>8} {:>8} {:>8} {:>12} {:>12} {:>12}", "map", "queries", "failed", "panics", "points", "avg", "first", "prewarm");
+        for (map_id, s) in stats.iter() {
+            println!(
+                "{:>6} {:>8} {:>8} {:>8} {:>8} {:>12?} {:>12?} {:>12}",
+                map_id,
+                s.queries,
+                s.failures,
+                s.panics,
+                s.total_points,
+                s.average_time(),
+                s.first_query_time.unwrap_or_default(),
+                match s.prewarm_time {
+                    Some(time) if s.prewarmed => format!("{:?}", time),
+                    _ => "-".to_string(),
+                }
+            );
+        }
+    }
+}
+
+// Reads map ids to prewarm, one per line. Blank lines and '#' comments are ignored.
+// A missing file just means nothing is prewarmed.
+pub fn load_prewarm_list(path: &Path) -> Vec<u32> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| match line.parse::<u32>() {
+            Ok(map_id) => Some(map_id),
+            Err(_) => {
+                eprintln!("Ignoring invalid map id '{}' in {}", line, path.display());
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("dll_test_{}_{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn prewarm_list_skips_comments_blank_lines_and_bad_ids() {
+        let path = temp_file("prewarm.txt", "# maps to load\n0\n\n  1  \n530 # outland\nabc\n-1\n   \n#571\n");
+        let map_ids = load_prewarm_list(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(map_ids, vec![0, 1, 530]);
+    }
+
+    #[test]
+    fn missing_prewarm_list_is_empty() {
+        let path = std::env::temp_dir().join(format!("dll_test_{}_missing.txt", std::process::id()));
+        assert!(load_prewarm_list(&path).is_empty());
+    }
+
+    #[test]
+    fn stats_count_queries_points_and_failures() {
+        let mut stats = MapStats::default();
+        let point = XYZ::new(0.0, 0.0, 0.0);
+        stats.record(&Ok(vec![point; 3]), Duration::from_millis(30));
+        stats.record(&Ok(vec![point; 2]), Duration::from_millis(10));
+        stats.record(&Err(NavError::NoPath), Duration::from_millis(5));
+        stats.record(&Err(NavError::Panicked), Duration::from_millis(15));
+        stats.record(&Err(NavError::Poisoned), Duration::ZERO);
+
+        assert_eq!(stats.queries, 5);
+        assert_eq!(stats.failures, 3);
+        assert_eq!(stats.panics, 1);
+        assert_eq!(stats.total_points, 5);
+        assert_eq!(stats.total_time, Duration::from_millis(60));
+        assert_eq!(stats.average_time(), Duration::from_millis(12));
+        assert_eq!(stats.first_query_time, Some(Duration::from_millis(30)));
+    }
+
+    #[test]
+    fn prewarm_is_not_counted_as_a_query() {
+        let mut stats = MapStats::default();
+        assert_eq!(stats.average_time(), Duration::ZERO);
+
+        stats.record_prewarm(Duration::from_secs(2));
+        assert!(stats.prewarmed);
+        assert_eq!(stats.prewarm_time, Some(Duration::from_secs(2)));
+        assert_eq!(stats.queries, 0);
+        assert_eq!(stats.first_query_time, None);
+
+        stats.record(&Ok(Vec::new()), Duration::from_millis(4));
+        assert_eq!(stats.first_query_time, Some(Duration::from_millis(4)));
+        assert_eq!(stats.prewarm_time, Some(Duration::from_secs(2)));
+    }
+}
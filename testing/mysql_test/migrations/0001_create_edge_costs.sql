@@ -0,0 +1,7 @@
+-- Extra traversal cost per node link, on top of the plain distance
+CREATE TABLE IF NOT EXISTS tool_edge_costs (
+    from_node INT UNSIGNED NOT NULL,
+    to_node INT UNSIGNED NOT NULL,
+    cost FLOAT NOT NULL DEFAULT 0,
+    PRIMARY KEY (from_node, to_node)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,9 @@
+-- One row per command run against this database
+CREATE TABLE IF NOT EXISTS tool_audit_log (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    command VARCHAR(64) NOT NULL,
+    arguments TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,19 @@
+-- Saved copies of the wander node table, rows keep the node columns as they were
+CREATE TABLE IF NOT EXISTS tool_snapshots (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    label VARCHAR(255) NOT NULL,
+    node_count INT UNSIGNED NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS tool_snapshot_nodes (
+    snapshot_id INT UNSIGNED NOT NULL,
+    id INT UNSIGNED NOT NULL,
+    mapid INT UNSIGNED NOT NULL,
+    x FLOAT NOT NULL,
+    y FLOAT NOT NULL,
+    z FLOAT NOT NULL,
+    links TEXT NOT NULL,
+    PRIMARY KEY (snapshot_id, id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
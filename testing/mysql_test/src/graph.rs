@@ -0,0 +1,189 @@
+use crate::{distance, Node};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+// Links are stored as whitespace separated "id:weight" entries, only the id is used here
+pub fn parse_links(links: &str) -> Vec<u32> {
+    links
+        .split_whitespace()
+        .filter_map(|entry| entry.split(':').next())
+        .filter_map(|id| id.parse().ok())
+        .collect()
+}
+
+pub struct NodeGraph<'a> {
+    nodes: &'a [Node],
+    index: HashMap<u32, usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+// Min-heap entry for the A* open set
+struct OpenEntry {
+    estimate: f64,
+    node: usize,
+}
+
+impl PartialEq for OpenEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl Eq for OpenEntry {}
+
+impl PartialOrd for OpenEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so BinaryHeap pops the lowest estimate first
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<'a> NodeGraph<'a> {
+    // Wander node links are walked in both directions, so edges are added both ways.
+    // Links to nodes that aren't loaded (other maps, deleted nodes) are dropped.
+    pub fn new(nodes: &'a [Node]) -> NodeGraph<'a> {
+        let index: HashMap<u32, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id, i))
+            .collect();
+
+        let mut edges = vec![Vec::new(); nodes.len()];
+        for (i, node) in nodes.iter().enumerate() {
+            for link in parse_links(&node.links) {
+                if let Some(&j) = index.get(&link) {
+                    if i != j {
+                        edges[i].push(j);
+                        edges[j].push(i);
+                    }
+                }
+            }
+        }
+        for neighbours in &mut edges {
+            neighbours.sort_unstable();
+            neighbours.dedup();
+        }
+
+        NodeGraph { nodes, index, edges }
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum::<usize>() / 2
+    }
+
+    // A* between two node ids, returns the nodes along the way including both ends
+    pub fn find_path(&self, from_id: u32, to_id: u32) -> Option<Vec<&'a Node>> {
+        let start = *self.index.get(&from_id)?;
+        let goal = *self.index.get(&to_id)?;
+
+        let mut cost = vec![f64::INFINITY; self.nodes.len()];
+        let mut came_from: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut open = BinaryHeap::new();
+
+        cost[start] = 0.0;
+        open.push(OpenEntry {
+            estimate: distance(&self.nodes[start], &self.nodes[goal]),
+            node: start,
+        });
+
+        while let Some(OpenEntry { estimate, node }) = open.pop() {
+            if node == goal {
+                let mut path = vec![&self.nodes[goal]];
+                let mut current = goal;
+                while let Some(previous) = came_from[current] {
+                    path.push(&self.nodes[previous]);
+                    current = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            // Stale heap entry, a cheaper way here was already expanded
+            if estimate > cost[node] + distance(&self.nodes[node], &self.nodes[goal]) {
+                continue;
+            }
+
+            for &next in &self.edges[node] {
+                let next_cost = cost[node] + distance(&self.nodes[node], &self.nodes[next]);
+                if next_cost < cost[next] {
+                    cost[next] = next_cost;
+                    came_from[next] = Some(node);
+                    open.push(OpenEntry {
+                        estimate: next_cost + distance(&self.nodes[next], &self.nodes[goal]),
+                        node: next,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: u32, x: f64, y: f64, links: &str) -> Node {
+        Node { id, x, y, z: 0.0, links: links.to_string() }
+    }
+
+    fn ids(path: Option<Vec<&Node>>) -> Option<Vec<u32>> {
+        path.map(|path| path.iter().map(|node| node.id).collect())
+    }
+
+    #[test]
+    fn parse_links_skips_malformed_entries() {
+        assert_eq!(parse_links("12:3  7:0.5\t8"), vec![12, 7, 8]);
+        assert_eq!(parse_links("abc :5 9:x -1 4294967296 x:9 10:"), vec![9, 10]);
+        assert_eq!(parse_links(""), Vec::<u32>::new());
+        assert_eq!(parse_links("   "), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn links_go_both_ways_and_unknown_ones_are_dropped() {
+        let nodes = [node(1, 0.0, 0.0, "2:1 2:1 1:1 99:1"), node(2, 1.0, 0.0, ""), node(3, 2.0, 0.0, "")];
+        let graph = NodeGraph::new(&nodes);
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(ids(graph.find_path(2, 1)), Some(vec![2, 1]));
+    }
+
+    #[test]
+    fn find_path_takes_the_shorter_way() {
+        // A square with one corner pulled in, 1 -> 2 -> 4 is shorter than 1 -> 3 -> 4
+        let nodes = [
+            node(1, 0.0, 0.0, "2:1 3:1"),
+            node(2, 5.0, 4.0, "4:1"),
+            node(3, 0.0, 10.0, "4:1"),
+            node(4, 10.0, 10.0, ""),
+        ];
+        let graph = NodeGraph::new(&nodes);
+        assert_eq!(ids(graph.find_path(1, 4)), Some(vec![1, 2, 4]));
+        assert_eq!(ids(graph.find_path(4, 1)), Some(vec![4, 2, 1]));
+    }
+
+    #[test]
+    fn find_path_to_itself_is_the_node() {
+        let nodes = [node(1, 0.0, 0.0, "2:1"), node(2, 1.0, 0.0, "")];
+        let graph = NodeGraph::new(&nodes);
+        assert_eq!(ids(graph.find_path(1, 1)), Some(vec![1]));
+    }
+
+    #[test]
+    fn find_path_fails_for_unreachable_or_unknown_nodes() {
+        let nodes = [node(1, 0.0, 0.0, "2:1"), node(2, 1.0, 0.0, ""), node(3, 5.0, 5.0, "")];
+        let graph = NodeGraph::new(&nodes);
+        assert_eq!(ids(graph.find_path(1, 3)), None);
+        assert_eq!(ids(graph.find_path(1, 42)), None);
+        assert_eq!(ids(graph.find_path(42, 1)), None);
+    }
+}
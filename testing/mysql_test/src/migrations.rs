@@ -0,0 +1,191 @@
+use mysql::prelude::*;
+use mysql::PooledConn;
+use std::collections::BTreeSet;
+
+pub const MIGRATIONS_TABLE: &str = "tool_migrations";
+
+// Auxiliary tables owned by this tool, never anything in the world schema itself
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    sql: &'static str,
+}
+
+// Applied in order, append only. An applied migration must never be edited, add a new one.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_edge_costs",
+        sql: include_str!("../migrations/0001_create_edge_costs.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "create_audit_log",
+        sql: include_str!("../migrations/0002_create_audit_log.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "create_snapshots",
+        sql: include_str!("../migrations/0003_create_snapshots.sql"),
+    },
+];
+
+#[derive(Default)]
+pub struct MigrationReport {
+    pub applied: Vec<&'static Migration>,
+    // Versions recorded in the database that this build doesn't know, i.e. a newer tool ran
+    pub unknown: Vec<u32>,
+}
+
+fn applied_versions(conn: &mut PooledConn) -> mysql::Result<BTreeSet<u32>> {
+    conn.query_drop(format!(
+        r"
+        CREATE TABLE IF NOT EXISTS {} (
+            version INT UNSIGNED NOT NULL,
+            name VARCHAR(255) NOT NULL,
+            applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (version)
+        ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+        ",
+        MIGRATIONS_TABLE
+    ))?;
+
+    let versions: Vec<u32> = conn.query(format!("SELECT version FROM {}", MIGRATIONS_TABLE))?;
+    Ok(versions.into_iter().collect())
+}
+
+// Splits a migration file into statements on `;`, skipping the ones inside '...', "..." and
+// `...` quotes. `--` and `#` line comments and `/* */` block comments are dropped, so they may
+// contain anything, and statements left empty are skipped.
+fn statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                current.push(c);
+                while let Some(inner) = chars.next() {
+                    current.push(inner);
+                    if inner == '\\' && c != '`' {
+                        current.extend(chars.next());
+                    } else if inner == c {
+                        // A doubled quote is an escaped quote, not the end of the literal
+                        if chars.peek() == Some(&c) {
+                            current.extend(chars.next());
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                while chars.next_if(|&next| next != '\n').is_some() {}
+            }
+            '#' => while chars.next_if(|&next| next != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = '\0';
+                for inner in chars.by_ref() {
+                    if previous == '*' && inner == '/' {
+                        break;
+                    }
+                    previous = inner;
+                }
+                current.push(' ');
+            }
+            ';' => statements.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    statements.push(current);
+
+    statements
+        .into_iter()
+        .map(|statement| statement.trim().to_string())
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+// Brings the auxiliary tables up to date, safe to call on every run. MySQL commits DDL
+// implicitly so a migration can't be rolled back together with its version row, which is why
+// every statement has to be idempotent (CREATE TABLE IF NOT EXISTS and friends): an
+// interrupted migration is simply applied again on the next run.
+pub fn run_migrations(conn: &mut PooledConn) -> mysql::Result<MigrationReport> {
+    let applied = applied_versions(conn)?;
+    let mut report = MigrationReport {
+        unknown: applied
+            .iter()
+            .copied()
+            .filter(|version| !MIGRATIONS.iter().any(|migration| migration.version == *version))
+            .collect(),
+        ..MigrationReport::default()
+    };
+
+    for migration in MIGRATIONS.iter().filter(|migration| !applied.contains(&migration.version)) {
+        for statement in statements(migration.sql) {
+            conn.query_drop(statement)?;
+        }
+        conn.exec_drop(
+            format!("INSERT INTO {} (version, name) VALUES (?, ?)", MIGRATIONS_TABLE),
+            (migration.version, migration.name),
+        )?;
+        report.applied.push(migration);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_semicolons() {
+        assert_eq!(
+            statements("CREATE TABLE a (id INT);\nCREATE TABLE b (id INT);"),
+            ["CREATE TABLE a (id INT)", "CREATE TABLE b (id INT)"]
+        );
+    }
+
+    #[test]
+    fn semicolons_inside_string_literals_do_not_split() {
+        assert_eq!(
+            statements("INSERT INTO a VALUES ('x;y', \"it's;\", 'don''t;', 'back\\';slash');\nSELECT `odd;name` FROM a;"),
+            [
+                "INSERT INTO a VALUES ('x;y', \"it's;\", 'don''t;', 'back\\';slash')",
+                "SELECT `odd;name` FROM a"
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_are_dropped_including_their_semicolons() {
+        assert_eq!(
+            statements("-- first; comment\nCREATE TABLE a (id INT); -- trailing;\n# hash; comment\n/* block;\n comment */ DROP TABLE b;"),
+            ["CREATE TABLE a (id INT)", "DROP TABLE b"]
+        );
+    }
+
+    #[test]
+    fn trailing_whitespace_and_empty_statements_are_skipped() {
+        assert_eq!(statements("CREATE TABLE a (id INT);\n\n  \t\n"), ["CREATE TABLE a (id INT)"]);
+        assert_eq!(statements("CREATE TABLE a (id INT);;\n-- only a comment\n;"), ["CREATE TABLE a (id INT)"]);
+        assert!(statements("").is_empty());
+    }
+
+    #[test]
+    fn every_migration_has_statements() {
+        for migration in MIGRATIONS {
+            let statements = statements(migration.sql);
+            assert!(!statements.is_empty(), "{} is empty", migration.name);
+            assert!(statements.iter().all(|statement| statement.starts_with("CREATE TABLE IF NOT EXISTS")));
+        }
+    }
+
+    #[test]
+    fn versions_are_increasing() {
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+    }
+}
@@ -0,0 +1,188 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+// Bumped whenever a JSON field is renamed or removed, new fields may be added freely
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Process exit codes, the same for both output formats
+pub const EXIT_OK: u8 = 0;
+pub const EXIT_WARNINGS: u8 = 1;
+pub const EXIT_ERROR: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    fn parse(value: &str) -> Result<OutputFormat, String> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("unknown output format '{}', expected text or json", value)),
+        }
+    }
+}
+
+// Removes `--output <format>` or `--output=<format>` from the arguments wherever it appears,
+// so it works as a global flag in front of or after any command
+pub fn take_output_format(args: &mut Vec<String>) -> Result<OutputFormat, String> {
+    let mut format = OutputFormat::Text;
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == "--output" {
+            if i + 1 >= args.len() {
+                return Err("--output requires a value".to_string());
+            }
+            format = OutputFormat::parse(&args[i + 1])?;
+            args.drain(i..i + 2);
+        } else if let Some(value) = args[i].strip_prefix("--output=") {
+            format = OutputFormat::parse(value)?;
+            args.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    Ok(format)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl Status {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Status::Ok => EXIT_OK,
+            Status::Warning => EXIT_WARNINGS,
+            Status::Error => EXIT_ERROR,
+        }
+    }
+}
+
+// Outcome of a single command. Text mode prints as it goes and only uses this for the exit
+// code, JSON mode prints it as the one and only document on stdout.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    schema_version: u32,
+    command: String,
+    status: Status,
+    warnings: Vec<String>,
+    errors: Vec<String>,
+    result: Option<Value>,
+}
+
+impl Report {
+    pub fn new(command: &str) -> Report {
+        Report {
+            schema_version: SCHEMA_VERSION,
+            command: command.to_string(),
+            status: Status::Ok,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            result: None,
+        }
+    }
+
+    pub fn warn(&mut self, warning: impl fmt::Display) {
+        self.warnings.push(warning.to_string());
+        if self.status == Status::Ok {
+            self.status = Status::Warning;
+        }
+    }
+
+    pub fn error(&mut self, error: impl fmt::Display) {
+        self.errors.push(error.to_string());
+        self.status = Status::Error;
+    }
+
+    pub fn set_result(&mut self, result: Value) {
+        self.result = Some(result);
+    }
+
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        self.status.exit_code()
+    }
+
+    pub fn print_json(&self) {
+        println!("{}", serde_json::to_string_pretty(self).expect("report is always serializable"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn output_flag_is_taken_from_any_position() {
+        for (input, rest) in [
+            (&["--output", "json", "closest", "1", "2", "3"][..], &["closest", "1", "2", "3"][..]),
+            (&["closest", "--output", "json", "1", "2", "3"], &["closest", "1", "2", "3"]),
+            (&["closest", "1", "2", "3", "--output", "json"], &["closest", "1", "2", "3"]),
+            (&["--output=json", "route"], &["route"]),
+            (&["route", "--output=json"], &["route"]),
+        ] {
+            let mut input = args(input);
+            assert_eq!(take_output_format(&mut input), Ok(OutputFormat::Json));
+            assert_eq!(input, args(rest));
+        }
+    }
+
+    #[test]
+    fn output_defaults_to_text_and_the_last_flag_wins() {
+        let mut input = args(&["closest", "1", "2", "3"]);
+        assert_eq!(take_output_format(&mut input), Ok(OutputFormat::Text));
+        assert_eq!(input.len(), 4);
+
+        let mut input = args(&["--output", "json", "route", "--output=text"]);
+        assert_eq!(take_output_format(&mut input), Ok(OutputFormat::Text));
+        assert_eq!(input, args(&["route"]));
+    }
+
+    #[test]
+    fn output_flag_without_a_value_is_an_error() {
+        let mut input = args(&["closest", "--output"]);
+        assert_eq!(take_output_format(&mut input), Err("--output requires a value".to_string()));
+    }
+
+    #[test]
+    fn unknown_output_format_is_an_error() {
+        let expected = Err("unknown output format 'xml', expected text or json".to_string());
+        assert_eq!(take_output_format(&mut args(&["--output", "xml"])), expected);
+        assert_eq!(take_output_format(&mut args(&["--output=xml"])), expected);
+        assert!(take_output_format(&mut args(&["--output="])).is_err());
+        assert!(take_output_format(&mut args(&["--output", "JSON"])).is_err());
+    }
+
+    // Scripts depend on these, they must not change
+    #[test]
+    fn exit_codes() {
+        assert_eq!(Status::Ok.exit_code(), 0);
+        assert_eq!(Status::Warning.exit_code(), 1);
+        assert_eq!(Status::Error.exit_code(), 2);
+
+        let mut report = Report::new("closest");
+        assert_eq!(report.exit_code(), 0);
+        report.warn("no links");
+        assert_eq!(report.exit_code(), 1);
+        report.error("no nodes");
+        assert_eq!(report.exit_code(), 2);
+        // A later warning doesn't hide the error
+        report.warn("another");
+        assert_eq!(report.exit_code(), 2);
+    }
+}
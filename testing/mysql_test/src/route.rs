@@ -0,0 +1,239 @@
+use crate::graph::NodeGraph;
+use crate::{distance, get_closest_node, Node};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Point {
+    fn from_node(node: &Node) -> Point {
+        Point { x: node.x, y: node.y, z: node.z }
+    }
+
+    fn distance_to(&self, other: &Point) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2) + (self.z - other.z).powi(2)).sqrt()
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({:.2}, {:.2}, {:.2})", self.x, self.y, self.z)
+    }
+}
+
+// Where a segment of the route came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SegmentKind {
+    // Straight line between a true endpoint and its snapped node
+    Direct,
+    // Edge of the wander node graph
+    Graph,
+}
+
+impl fmt::Display for SegmentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SegmentKind::Direct => write!(f, "direct"),
+            SegmentKind::Graph => write!(f, "graph"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub from: Point,
+    pub to: Point,
+    pub kind: SegmentKind,
+    // Node ids at either end, None for the true endpoints
+    pub from_node: Option<u32>,
+    pub to_node: Option<u32>,
+}
+
+impl Segment {
+    pub fn length(&self) -> f64 {
+        self.from.distance_to(&self.to)
+    }
+}
+
+#[derive(Debug)]
+pub struct Route {
+    pub start_node: u32,
+    pub end_node: u32,
+    pub segments: Vec<Segment>,
+}
+
+impl Route {
+    pub fn length(&self) -> f64 {
+        self.segments.iter().map(Segment::length).sum()
+    }
+
+    // Continuous polyline, every segment starts where the previous one ended
+    pub fn points(&self) -> Vec<Point> {
+        let mut points = Vec::with_capacity(self.segments.len() + 1);
+        if let Some(first) = self.segments.first() {
+            points.push(first.from);
+        }
+        points.extend(self.segments.iter().map(|segment| segment.to));
+        points
+    }
+}
+
+#[derive(Debug)]
+pub enum RouteError {
+    NoNodes,
+    NoGraphPath { from: u32, to: u32 },
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::NoNodes => write!(f, "no nodes to route through"),
+            RouteError::NoGraphPath { from, to } => {
+                write!(f, "no graph path between node {} and node {}", from, to)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+// Snaps both endpoints to their closest nodes, searches the graph between them and stitches
+// straight segments from the real endpoints onto either end of the graph path
+pub fn build_route(graph: &NodeGraph, nodes: &[Node], start: &Node, end: &Node) -> Result<Route, RouteError> {
+    let start_node = get_closest_node(start, nodes).ok_or(RouteError::NoNodes)?;
+    let end_node = get_closest_node(end, nodes).ok_or(RouteError::NoNodes)?;
+
+    let graph_path = graph
+        .find_path(start_node.id, end_node.id)
+        .ok_or(RouteError::NoGraphPath { from: start_node.id, to: end_node.id })?;
+
+    let mut segments = Vec::new();
+
+    if distance(start, start_node) > 0.0 {
+        segments.push(Segment {
+            from: Point::from_node(start),
+            to: Point::from_node(start_node),
+            kind: SegmentKind::Direct,
+            from_node: None,
+            to_node: Some(start_node.id),
+        });
+    }
+
+    for pair in graph_path.windows(2) {
+        segments.push(Segment {
+            from: Point::from_node(pair[0]),
+            to: Point::from_node(pair[1]),
+            kind: SegmentKind::Graph,
+            from_node: Some(pair[0].id),
+            to_node: Some(pair[1].id),
+        });
+    }
+
+    if distance(end_node, end) > 0.0 {
+        segments.push(Segment {
+            from: Point::from_node(end_node),
+            to: Point::from_node(end),
+            kind: SegmentKind::Direct,
+            from_node: Some(end_node.id),
+            to_node: None,
+        });
+    }
+
+    // Both endpoints are the same node, a zero length segment keeps the route a polyline
+    if segments.is_empty() {
+        segments.push(Segment {
+            from: Point::from_node(start),
+            to: Point::from_node(end),
+            kind: SegmentKind::Direct,
+            from_node: None,
+            to_node: None,
+        });
+    }
+
+    Ok(Route {
+        start_node: start_node.id,
+        end_node: end_node.id,
+        segments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: u32, x: f64, y: f64, links: &str) -> Node {
+        Node { id, x, y, z: 0.0, links: links.to_string() }
+    }
+
+    fn position(x: f64, y: f64) -> Node {
+        node(0, x, y, "")
+    }
+
+    // 1 - 2 - 3 along the x axis, 4 on its own
+    fn nodes() -> Vec<Node> {
+        vec![
+            node(1, 0.0, 0.0, "2:1"),
+            node(2, 10.0, 0.0, "3:1"),
+            node(3, 20.0, 0.0, ""),
+            node(4, 0.0, 50.0, ""),
+        ]
+    }
+
+    #[test]
+    fn route_stitches_direct_segments_onto_the_graph_path() {
+        let nodes = nodes();
+        let graph = NodeGraph::new(&nodes);
+        let route = build_route(&graph, &nodes, &position(0.0, 3.0), &position(20.0, -4.0)).unwrap();
+
+        let kinds: Vec<SegmentKind> = route.segments.iter().map(|segment| segment.kind).collect();
+        assert_eq!(kinds, [SegmentKind::Direct, SegmentKind::Graph, SegmentKind::Graph, SegmentKind::Direct]);
+        assert_eq!((route.start_node, route.end_node), (1, 3));
+        assert_eq!(route.points().len(), 5);
+        assert!((route.length() - 27.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn endpoints_on_the_same_node_give_a_zero_length_route() {
+        let nodes = nodes();
+        let graph = NodeGraph::new(&nodes);
+        let route = build_route(&graph, &nodes, &position(10.0, 0.0), &position(10.0, 0.0)).unwrap();
+
+        assert_eq!((route.start_node, route.end_node), (2, 2));
+        assert_eq!(route.segments.len(), 1);
+        assert_eq!(route.length(), 0.0);
+        let point = Point { x: 10.0, y: 0.0, z: 0.0 };
+        assert_eq!(route.points(), vec![point, point]);
+    }
+
+    #[test]
+    fn coinciding_endpoints_off_the_graph_go_to_the_node_and_back() {
+        let nodes = nodes();
+        let graph = NodeGraph::new(&nodes);
+        let route = build_route(&graph, &nodes, &position(10.0, 2.0), &position(10.0, 2.0)).unwrap();
+
+        assert_eq!(route.segments.len(), 2);
+        assert!(route.segments.iter().all(|segment| segment.kind == SegmentKind::Direct));
+        assert_eq!(route.length(), 4.0);
+    }
+
+    #[test]
+    fn unreachable_end_is_an_error() {
+        let nodes = nodes();
+        let graph = NodeGraph::new(&nodes);
+        let result = build_route(&graph, &nodes, &position(0.0, 0.0), &position(0.0, 45.0));
+        assert!(matches!(result, Err(RouteError::NoGraphPath { from: 1, to: 4 })));
+    }
+
+    #[test]
+    fn no_nodes_is_an_error() {
+        let graph = NodeGraph::new(&[]);
+        let result = build_route(&graph, &[], &position(0.0, 0.0), &position(1.0, 0.0));
+        assert!(matches!(result, Err(RouteError::NoNodes)));
+    }
+}
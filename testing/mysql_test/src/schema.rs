@@ -0,0 +1,237 @@
+use mysql::prelude::*;
+use mysql::PooledConn;
+use std::collections::HashMap;
+use std::fmt;
+
+pub const NODES_TABLE: &str = "creature_template_npcbot_wander_nodes";
+
+// Broad type families we care about when mapping rows into Rust types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Integer,
+    Float,
+    Decimal,
+    Text,
+    Other,
+}
+
+impl ColumnKind {
+    fn from_data_type(data_type: &str) -> ColumnKind {
+        match data_type.to_ascii_lowercase().as_str() {
+            "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" => ColumnKind::Integer,
+            "float" | "double" | "real" => ColumnKind::Float,
+            "decimal" | "numeric" => ColumnKind::Decimal,
+            "char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext" => ColumnKind::Text,
+            _ => ColumnKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for ColumnKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColumnKind::Integer => "integer",
+            ColumnKind::Float => "floating point",
+            ColumnKind::Decimal => "decimal",
+            ColumnKind::Text => "text",
+            ColumnKind::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+struct ExpectedColumn {
+    name: &'static str,
+    kind: ColumnKind,
+    // Required columns abort startup, optional ones only disable features
+    required: bool,
+}
+
+const NODE_COLUMNS: &[ExpectedColumn] = &[
+    ExpectedColumn { name: "id", kind: ColumnKind::Integer, required: true },
+    ExpectedColumn { name: "mapid", kind: ColumnKind::Integer, required: true },
+    ExpectedColumn { name: "x", kind: ColumnKind::Float, required: true },
+    ExpectedColumn { name: "y", kind: ColumnKind::Float, required: true },
+    ExpectedColumn { name: "z", kind: ColumnKind::Float, required: true },
+    ExpectedColumn { name: "links", kind: ColumnKind::Text, required: false },
+];
+
+#[derive(Debug)]
+pub enum Mismatch {
+    MissingTable {
+        table: String,
+    },
+    MissingColumn {
+        table: String,
+        column: String,
+        required: bool,
+    },
+    WrongType {
+        table: String,
+        column: String,
+        expected: ColumnKind,
+        found: String,
+        required: bool,
+    },
+}
+
+impl Mismatch {
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            Mismatch::MissingTable { .. } => true,
+            Mismatch::MissingColumn { required, .. } => *required,
+            Mismatch::WrongType { required, .. } => *required,
+        }
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::MissingTable { table } => write!(f, "table `{}` does not exist", table),
+            Mismatch::MissingColumn { table, column, .. } => {
+                write!(f, "column `{}`.`{}` is missing", table, column)
+            }
+            Mismatch::WrongType { table, column, expected, found, .. } => write!(
+                f,
+                "column `{}`.`{}` has type `{}`, expected {}",
+                table, column, found, expected
+            ),
+        }
+    }
+}
+
+// Optional capabilities that depend on the schema of the connected server
+#[derive(Debug, Clone, Copy)]
+pub struct Features {
+    pub links: bool,
+}
+
+#[derive(Debug)]
+pub struct SchemaReport {
+    pub mismatches: Vec<Mismatch>,
+    pub features: Features,
+}
+
+impl SchemaReport {
+    pub fn is_compatible(&self) -> bool {
+        !self.mismatches.iter().any(Mismatch::is_fatal)
+    }
+
+    pub fn print(&self) {
+        if self.mismatches.is_empty() {
+            println!("Schema check passed.");
+            return;
+        }
+
+        for mismatch in &self.mismatches {
+            let level = if mismatch.is_fatal() { "ERROR" } else { "WARNING" };
+            println!("Schema {}: {}", level, mismatch);
+        }
+
+        if !self.features.links {
+            println!("Node links are unavailable, graph features are disabled.");
+        }
+    }
+}
+
+// Returns column name -> (DATA_TYPE, COLUMN_TYPE) for the given table in the current database
+fn table_columns(
+    conn: &mut PooledConn,
+    table: &str,
+) -> mysql::Result<HashMap<String, (String, String)>> {
+    let rows: Vec<(String, String, String)> = conn.exec(
+        r"
+        SELECT COLUMN_NAME, DATA_TYPE, COLUMN_TYPE
+        FROM information_schema.COLUMNS
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?
+        ",
+        (table,),
+    )?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(name, data_type, column_type)| (name.to_ascii_lowercase(), (data_type, column_type)))
+        .collect())
+}
+
+pub fn check_schema(conn: &mut PooledConn) -> mysql::Result<SchemaReport> {
+    let mut mismatches = Vec::new();
+    let mut features = Features { links: true };
+
+    let columns = table_columns(conn, NODES_TABLE)?;
+    if columns.is_empty() {
+        mismatches.push(Mismatch::MissingTable {
+            table: NODES_TABLE.to_string(),
+        });
+        features.links = false;
+        return Ok(SchemaReport { mismatches, features });
+    }
+
+    for expected in NODE_COLUMNS {
+        let mismatch = match columns.get(expected.name) {
+            None => Some(Mismatch::MissingColumn {
+                table: NODES_TABLE.to_string(),
+                column: expected.name.to_string(),
+                required: expected.required,
+            }),
+            Some((data_type, column_type)) => {
+                if ColumnKind::from_data_type(data_type) != expected.kind {
+                    Some(Mismatch::WrongType {
+                        table: NODES_TABLE.to_string(),
+                        column: expected.name.to_string(),
+                        expected: expected.kind,
+                        found: column_type.clone(),
+                        required: expected.required,
+                    })
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some(mismatch) = mismatch {
+            if expected.name == "links" {
+                features.links = false;
+            }
+            mismatches.push(mismatch);
+        }
+    }
+
+    Ok(SchemaReport { mismatches, features })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_types_map_to_column_kinds() {
+        let cases = [
+            ("tinyint", ColumnKind::Integer),
+            ("INT", ColumnKind::Integer),
+            ("bigint", ColumnKind::Integer),
+            ("integer", ColumnKind::Integer),
+            ("float", ColumnKind::Float),
+            ("Double", ColumnKind::Float),
+            ("real", ColumnKind::Float),
+            ("decimal", ColumnKind::Decimal),
+            ("numeric", ColumnKind::Decimal),
+            ("varchar", ColumnKind::Text),
+            ("char", ColumnKind::Text),
+            ("LONGTEXT", ColumnKind::Text),
+            ("blob", ColumnKind::Other),
+            ("datetime", ColumnKind::Other),
+            ("", ColumnKind::Other),
+        ];
+        for (data_type, kind) in cases {
+            assert_eq!(ColumnKind::from_data_type(data_type), kind, "{}", data_type);
+        }
+    }
+
+    #[test]
+    fn expects_the_data_type_not_the_column_type() {
+        // information_schema's COLUMN_TYPE carries the width and flags, DATA_TYPE doesn't
+        assert_eq!(ColumnKind::from_data_type("int(10) unsigned"), ColumnKind::Other);
+    }
+}
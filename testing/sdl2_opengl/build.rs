@@ -1,22 +1,32 @@
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-fn main() {
-    // Tell Rust where to find the SDL2.lib or equivalent
-    println!("cargo:rustc-link-search=native=C:/local/SDL2-devel-2.30.6-VC/SDL2-2.30.6/lib/x64");
-    println!("cargo:rustc-link-lib=SDL2");
+// Where the SDL2, SDL2_ttf and SDL2_mixer development packages are unpacked
+const SDL2_LIB_DIR: &str = "C:/local/SDL2-devel-2.30.6-VC/SDL2-2.30.6/lib/x64";
+const SDL2_TTF_LIB_DIR: &str = "C:/local/SDL2_ttf-devel-2.22.0-VC/SDL2_ttf-2.22.0/lib/x64";
+const SDL2_MIXER_LIB_DIR: &str = "C:/local/SDL2_mixer-devel-2.8.0-VC/SDL2_mixer-2.8.0/lib/x64";
+
+// Tells Rust where to find name.lib and copies name.dll next to the executable, the game
+// doesn't start without it
+fn link_and_copy(lib_dir: &str, name: &str, target_dir: &Path) {
+    println!("cargo:rustc-link-search=native={}", lib_dir);
+    println!("cargo:rustc-link-lib={}", name);
+
+    let dll_name = format!("{}.dll", name);
+    let dll_src = PathBuf::from(lib_dir).join(&dll_name);
+    fs::copy(&dll_src, target_dir.join(&dll_name))
+        .unwrap_or_else(|e| panic!("Failed to copy {} to the target directory: {}", dll_name, e));
+}
 
+fn main() {
     // Get the path to the target directory (where the build artifacts are)
     let out_dir = env::var("OUT_DIR").unwrap();
     let target_dir = PathBuf::from(out_dir).ancestors().nth(3).unwrap().to_path_buf();
 
-    // Path to the SDL2.dll in your local development environment
-    let dll_src = PathBuf::from("C:/local/SDL2-devel-2.30.6-VC/SDL2-2.30.6/lib/x64/SDL2.dll");
-
-    // Destination path: copy to the target directory
-    let dll_dest = target_dir.join("SDL2.dll");
-
-    // Copy the SDL2.dll to the target directory
-    fs::copy(&dll_src, &dll_dest).expect("Failed to copy SDL2.dll to the target directory");
+    link_and_copy(SDL2_LIB_DIR, "SDL2", &target_dir);
+    // The sdl2 crate's ttf feature links SDL2_ttf, so it is needed even when the font is missing
+    link_and_copy(SDL2_TTF_LIB_DIR, "SDL2_ttf", &target_dir);
+    // Same for the mixer feature and SDL2_mixer, even without an audio device
+    link_and_copy(SDL2_MIXER_LIB_DIR, "SDL2_mixer", &target_dir);
 }
@@ -0,0 +1,6 @@
+#version 330 core
+out vec4 color;
+uniform vec4 lineColor;
+void main() {
+    color = lineColor;
+}
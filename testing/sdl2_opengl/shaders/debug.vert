@@ -0,0 +1,9 @@
+#version 330 core
+layout(location = 0) in vec2 position;
+// World to clip space, identity for screen space overlays
+uniform mat4 projection;
+uniform vec2 offset;
+uniform vec2 scale;
+void main() {
+    gl_Position = projection * vec4(position * scale + offset, 0.0, 1.0);
+}
@@ -0,0 +1,8 @@
+#version 330 core
+in vec2 uv;
+in vec4 tint;
+out vec4 color;
+uniform sampler2D fontTexture;
+void main() {
+    color = tint * texture(fontTexture, uv);
+}
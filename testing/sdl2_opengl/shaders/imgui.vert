@@ -0,0 +1,13 @@
+#version 330 core
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 texCoord;
+layout(location = 2) in vec4 vertexColor;
+// Overlay pixels to clip space
+uniform mat4 projection;
+out vec2 uv;
+out vec4 tint;
+void main() {
+    uv = texCoord;
+    tint = vertexColor;
+    gl_Position = projection * vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,6 @@
+#version 330 core
+out vec4 color;
+uniform vec4 obstacleColor;
+void main() {
+    color = obstacleColor;
+}
@@ -0,0 +1,9 @@
+#version 330 core
+// Obstacles are drawn with one instanced call per behavior, the array size is MAX_OBSTACLES
+layout(location = 0) in vec2 position;
+// World to clip space, identity for screen space overlays
+uniform mat4 projection;
+uniform vec2 offsets[32];
+void main() {
+    gl_Position = projection * vec4(position + offsets[gl_InstanceID], 0.0, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 330 core
+// Instanced variant of sprite.vert for the obstacles, used with sprite.frag
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 texCoord;
+// World to clip space, identity for screen space overlays
+uniform mat4 projection;
+uniform vec2 offsets[32];
+uniform vec2 halfSize;
+// Animation frame per instance as (u, v, width, height)
+uniform vec4 uvRects[32];
+out vec2 uv;
+void main() {
+    uv = uvRects[gl_InstanceID].xy + texCoord * uvRects[gl_InstanceID].zw;
+    gl_Position = projection * vec4(position * halfSize + offsets[gl_InstanceID], 0.0, 1.0);
+}
@@ -0,0 +1,9 @@
+#version 330 core
+// Soft round dot, drawn with additive blending so overlapping particles glow
+in vec2 local;
+in vec4 tint;
+out vec4 color;
+void main() {
+    float falloff = clamp(1.0 - length(local), 0.0, 1.0);
+    color = vec4(tint.rgb, tint.a * falloff);
+}
@@ -0,0 +1,15 @@
+#version 330 core
+// Instanced quads, one per particle. The corner runs -1..1 across the quad.
+layout(location = 0) in vec2 corner;
+layout(location = 1) in vec2 center;
+layout(location = 2) in float size;
+layout(location = 3) in vec4 particleColor;
+// World to clip space
+uniform mat4 projection;
+out vec2 local;
+out vec4 tint;
+void main() {
+    local = corner;
+    tint = particleColor;
+    gl_Position = projection * vec4(center + corner * size * 0.5, 0.0, 1.0);
+}
@@ -0,0 +1,41 @@
+#version 330 core
+in vec2 uv;
+out vec4 color;
+uniform sampler2D scene;
+// Texture coordinate offset, non-zero while the screen shakes
+uniform vec2 shakeOffset;
+// 0 to 1, how much red is mixed in
+uniform float flash;
+uniform bool crt;
+// Drawable size in pixels
+uniform vec2 resolution;
+// Seconds since start, scrolls the scanlines
+uniform float time;
+
+// Bulges the image like a curved tube screen
+vec2 curve(vec2 coord) {
+    vec2 centered = coord * 2.0 - 1.0;
+    centered *= 1.0 + 0.08 * dot(centered.yx, centered.yx);
+    return centered * 0.5 + 0.5;
+}
+
+void main() {
+    vec2 coord = uv + shakeOffset;
+    if (crt) {
+        coord = curve(coord);
+    }
+    vec3 scene_color = texture(scene, coord).rgb;
+    // Whatever the shake or the curvature pulls in from outside the scene is black
+    if (coord.x < 0.0 || coord.x > 1.0 || coord.y < 0.0 || coord.y > 1.0) {
+        scene_color = vec3(0.0);
+    }
+
+    if (crt) {
+        float scanline = 0.8 + 0.2 * sin((coord.y * resolution.y + time * 30.0) * 3.14159);
+        vec2 edge = coord * (1.0 - coord);
+        float vignette = clamp(pow(edge.x * edge.y * 16.0, 0.25), 0.0, 1.0);
+        scene_color *= scanline * vignette;
+    }
+
+    color = vec4(mix(scene_color, vec3(1.0, 0.0, 0.0), flash), 1.0);
+}
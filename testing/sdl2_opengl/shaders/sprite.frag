@@ -0,0 +1,8 @@
+#version 330 core
+in vec2 uv;
+out vec4 color;
+uniform sampler2D spriteTexture;
+uniform vec4 tint;
+void main() {
+    color = texture(spriteTexture, uv) * tint;
+}
@@ -0,0 +1,14 @@
+#version 330 core
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 texCoord;
+// World to clip space, identity for screen space overlays
+uniform mat4 projection;
+uniform vec2 offset;
+uniform vec2 halfSize;
+// Frame of the texture to show as (u, v, width, height)
+uniform vec4 uvRect;
+out vec2 uv;
+void main() {
+    uv = uvRect.xy + texCoord * uvRect.zw;
+    gl_Position = projection * vec4(position * halfSize + offset, 0.0, 1.0);
+}
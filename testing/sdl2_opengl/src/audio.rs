@@ -0,0 +1,91 @@
+use sdl2::mixer::{self, Channel, Chunk, Music, AUDIO_S16LSB, DEFAULT_CHANNELS, MAX_VOLUME};
+use sdl2::{AudioSubsystem, Sdl};
+
+const HIT_SOUND_PATH: &str = "assets/sounds/hit.wav";
+const MUSIC_PATH: &str = "assets/sounds/music.wav";
+
+// Volume change per key press, out of MAX_VOLUME
+const VOLUME_STEP: i32 = 16;
+
+// Sound effects and background music through SDL2_mixer. Missing sound files only produce a
+// warning, the rest keeps working. The SDL2_mixer library is linked in, so unlike the sound
+// files it can't be missing.
+pub struct Audio {
+    _audio_subsystem: AudioSubsystem,
+    hit: Option<Chunk>,
+    music: Option<Music<'static>>,
+    volume: i32,
+    muted: bool,
+}
+
+fn load_or_warn<T>(path: &str, result: Result<T, String>) -> Option<T> {
+    result.map_err(|e| eprintln!("Failed to load sound {}: {}", path, e)).ok()
+}
+
+impl Audio {
+    pub fn new(sdl: &Sdl) -> Result<Audio, String> {
+        let audio_subsystem = sdl.audio()?;
+        mixer::open_audio(44_100, AUDIO_S16LSB, DEFAULT_CHANNELS, 1_024)?;
+        mixer::allocate_channels(8);
+
+        let audio = Audio {
+            _audio_subsystem: audio_subsystem,
+            hit: load_or_warn(HIT_SOUND_PATH, Chunk::from_file(HIT_SOUND_PATH)),
+            music: load_or_warn(MUSIC_PATH, Music::from_file(MUSIC_PATH)),
+            volume: MAX_VOLUME / 2,
+            muted: false,
+        };
+        audio.apply_volume();
+        Ok(audio)
+    }
+
+    // Loops until the game exits
+    pub fn play_music(&self) {
+        if let Some(music) = &self.music {
+            if let Err(e) = music.play(-1) {
+                eprintln!("Failed to play music: {}", e);
+            }
+        }
+    }
+
+    pub fn play_hit(&self) {
+        if let Some(hit) = &self.hit {
+            // Running out of free channels just drops the effect
+            let _ = Channel::all().play(hit, 0);
+        }
+    }
+
+    pub fn change_volume(&mut self, steps: i32) {
+        self.volume = (self.volume + steps * VOLUME_STEP).clamp(0, MAX_VOLUME);
+        self.muted = false;
+        self.apply_volume();
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.apply_volume();
+    }
+
+    pub fn volume(&self) -> i32 {
+        if self.muted {
+            0
+        } else {
+            self.volume
+        }
+    }
+
+    fn apply_volume(&self) {
+        Channel::all().set_volume(self.volume());
+        Music::set_volume(self.volume());
+    }
+}
+
+impl Drop for Audio {
+    fn drop(&mut self) {
+        // Free everything before the device goes away
+        Music::halt();
+        self.music = None;
+        self.hit = None;
+        mixer::close_audio();
+    }
+}
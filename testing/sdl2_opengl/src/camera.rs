@@ -0,0 +1,72 @@
+// World units are what gameplay positions and sizes are in. The playing field spans
+// -ARENA_HALF_SIZE..ARENA_HALF_SIZE on both axes.
+pub const ARENA_HALF_SIZE: f32 = 1.0;
+
+const MIN_ZOOM: f32 = 1.0;
+const MAX_ZOOM: f32 = 3.0;
+// Zoom multiplier per mouse wheel notch
+const ZOOM_STEP: f32 = 1.1;
+// How quickly the camera catches up with its target, higher is snappier
+const FOLLOW_RATE: f32 = 5.0;
+
+pub const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 1.0, 0.0,
+    0.0, 0.0, 0.0, 1.0,
+];
+
+// 2D orthographic camera. At zoom 1 the whole arena fits along the shorter side of the view,
+// zoomed in it follows its target but doesn't show past the arena edges where it can avoid it.
+pub struct Camera {
+    pub x: f32,
+    pub y: f32,
+    zoom: f32,
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera { x: 0.0, y: 0.0, zoom: MIN_ZOOM }
+    }
+
+    // Half the width and height of the visible area in world units, `aspect` is the view's
+    // width over its height
+    fn half_extent(&self, aspect: f32) -> (f32, f32) {
+        let half = ARENA_HALF_SIZE / self.zoom;
+        if aspect >= 1.0 {
+            (half * aspect, half)
+        } else {
+            (half, half / aspect)
+        }
+    }
+
+    // `notches` as reported by the mouse wheel event, positive zooms in
+    pub fn zoom_by(&mut self, notches: i32) {
+        self.zoom = (self.zoom * ZOOM_STEP.powi(notches)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    // Eases towards `target`, framerate independent
+    pub fn follow(&mut self, target: (f32, f32), dt: f32) {
+        let t = 1.0 - (-FOLLOW_RATE * dt).exp();
+        self.x += (target.0 - self.x) * t;
+        self.y += (target.1 - self.y) * t;
+    }
+
+    // World to clip space for a view of the given aspect ratio, column major for
+    // glUniformMatrix4fv. The view is kept inside the arena along any axis it fits on.
+    pub fn projection(&self, aspect: f32) -> [f32; 16] {
+        let (half_width, half_height) = self.half_extent(aspect);
+        let clamp = |center: f32, half: f32| {
+            let limit = (ARENA_HALF_SIZE - half).max(0.0);
+            center.clamp(-limit, limit)
+        };
+        let (x, y) = (clamp(self.x, half_width), clamp(self.y, half_height));
+        let (scale_x, scale_y) = (1.0 / half_width, 1.0 / half_height);
+        [
+            scale_x, 0.0, 0.0, 0.0,
+            0.0, scale_y, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            -x * scale_x, -y * scale_y, 0.0, 1.0,
+        ]
+    }
+}
@@ -0,0 +1,328 @@
+// Narrow phase tests between the shapes used in the game. Not every pair is used by the game
+// loop yet.
+#![allow(dead_code)]
+
+use crate::spatial_hash::Aabb;
+
+pub type Point = (f32, f32);
+
+// How two overlapping shapes touch. `normal` is unit length and points from the first shape
+// towards the second, moving the first shape by -normal * depth separates them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub normal: (f32, f32),
+    pub depth: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Circle {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+}
+
+// Corners can be in either winding order
+#[derive(Debug, Clone, Copy)]
+pub struct Triangle {
+    pub points: [Point; 3],
+}
+
+impl Triangle {
+    // Same shape as the obstacle vertices: apex at the top, base along the bottom
+    pub fn obstacle(x: f32, y: f32, half_size: f32) -> Triangle {
+        Triangle {
+            points: [(x, y + half_size), (x - half_size, y - half_size), (x + half_size, y - half_size)],
+        }
+    }
+
+    fn center(&self) -> Point {
+        let [a, b, c] = self.points;
+        ((a.0 + b.0 + c.0) / 3.0, (a.1 + b.1 + c.1) / 3.0)
+    }
+}
+
+fn corners(aabb: &Aabb) -> [Point; 4] {
+    [
+        (aabb.min_x, aabb.min_y),
+        (aabb.max_x, aabb.min_y),
+        (aabb.max_x, aabb.max_y),
+        (aabb.min_x, aabb.max_y),
+    ]
+}
+
+fn aabb_center(aabb: &Aabb) -> Point {
+    ((aabb.min_x + aabb.max_x) / 2.0, (aabb.min_y + aabb.max_y) / 2.0)
+}
+
+fn dot(a: Point, b: Point) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn normalize(v: Point) -> Option<Point> {
+    let length = dot(v, v).sqrt();
+    if length > f32::EPSILON {
+        Some((v.0 / length, v.1 / length))
+    } else {
+        None
+    }
+}
+
+fn project(points: &[Point], axis: Point) -> (f32, f32) {
+    points.iter().fold((f32::MAX, f32::MIN), |(min, max), point| {
+        let d = dot(*point, axis);
+        (min.min(d), max.max(d))
+    })
+}
+
+// Unit normals of every edge, one per edge, pointing either way
+fn edge_normals(points: &[Point]) -> impl Iterator<Item = Point> + '_ {
+    (0..points.len()).filter_map(move |i| {
+        let (a, b) = (points[i], points[(i + 1) % points.len()]);
+        normalize((a.1 - b.1, b.0 - a.0))
+    })
+}
+
+// Separating axis test. Keeps the axis with the smallest overlap and flips it to point from
+// the first shape's center towards the second's.
+fn min_overlap<A, B>(axes: impl Iterator<Item = Point>, first: (Point, A), second: (Point, B)) -> Option<Contact>
+where
+    A: Fn(Point) -> (f32, f32),
+    B: Fn(Point) -> (f32, f32),
+{
+    let ((first_center, first_project), (second_center, second_project)) = (first, second);
+    let mut best: Option<Contact> = None;
+    for axis in axes {
+        let (min_a, max_a) = first_project(axis);
+        let (min_b, max_b) = second_project(axis);
+        let depth = max_a.min(max_b) - min_a.max(min_b);
+        if depth <= 0.0 {
+            return None;
+        }
+        if best.is_none_or(|best| depth < best.depth) {
+            best = Some(Contact { normal: axis, depth });
+        }
+    }
+
+    best.map(|mut contact| {
+        let between = (second_center.0 - first_center.0, second_center.1 - first_center.1);
+        if dot(between, contact.normal) < 0.0 {
+            contact.normal = (-contact.normal.0, -contact.normal.1);
+        }
+        contact
+    })
+}
+
+fn polygon_polygon(a: &[Point], a_center: Point, b: &[Point], b_center: Point) -> Option<Contact> {
+    min_overlap(
+        edge_normals(a).chain(edge_normals(b)),
+        (a_center, |axis| project(a, axis)),
+        (b_center, |axis| project(b, axis)),
+    )
+}
+
+// Besides the polygon's edges, the only other axis that can separate a circle from a polygon
+// runs from the closest corner to the circle's center
+fn polygon_circle(polygon: &[Point], center: Point, circle: &Circle) -> Option<Contact> {
+    let circle_center = (circle.x, circle.y);
+    let distance_sq = |p: &Point| dot((p.0 - circle.x, p.1 - circle.y), (p.0 - circle.x, p.1 - circle.y));
+    let closest = polygon
+        .iter()
+        .copied()
+        .min_by(|a, b| distance_sq(a).total_cmp(&distance_sq(b)))?;
+    let corner_axis = normalize((circle.x - closest.0, circle.y - closest.1));
+
+    min_overlap(
+        edge_normals(polygon).chain(corner_axis),
+        (center, |axis| project(polygon, axis)),
+        (circle_center, |axis| {
+            let d = dot(circle_center, axis);
+            (d - circle.radius, d + circle.radius)
+        }),
+    )
+}
+
+pub fn aabb_aabb(a: &Aabb, b: &Aabb) -> Option<Contact> {
+    let overlap_x = a.max_x.min(b.max_x) - a.min_x.max(b.min_x);
+    let overlap_y = a.max_y.min(b.max_y) - a.min_y.max(b.min_y);
+    if overlap_x <= 0.0 || overlap_y <= 0.0 {
+        return None;
+    }
+
+    let (a_center, b_center) = (aabb_center(a), aabb_center(b));
+    let contact = if overlap_x < overlap_y {
+        let sign = if b_center.0 >= a_center.0 { 1.0 } else { -1.0 };
+        Contact { normal: (sign, 0.0), depth: overlap_x }
+    } else {
+        let sign = if b_center.1 >= a_center.1 { 1.0 } else { -1.0 };
+        Contact { normal: (0.0, sign), depth: overlap_y }
+    };
+    Some(contact)
+}
+
+pub fn circle_circle(a: &Circle, b: &Circle) -> Option<Contact> {
+    let between = (b.x - a.x, b.y - a.y);
+    let distance = dot(between, between).sqrt();
+    let depth = a.radius + b.radius - distance;
+    if depth <= 0.0 {
+        return None;
+    }
+    // Concentric circles have no preferred direction, push straight up
+    let normal = normalize(between).unwrap_or((0.0, 1.0));
+    Some(Contact { normal, depth })
+}
+
+pub fn aabb_circle(a: &Aabb, b: &Circle) -> Option<Contact> {
+    polygon_circle(&corners(a), aabb_center(a), b)
+}
+
+pub fn aabb_triangle(a: &Aabb, b: &Triangle) -> Option<Contact> {
+    polygon_polygon(&corners(a), aabb_center(a), &b.points, b.center())
+}
+
+pub fn circle_triangle(a: &Circle, b: &Triangle) -> Option<Contact> {
+    // Computed from the triangle's side, so the normal has to be flipped back
+    polygon_circle(&b.points, b.center(), a).map(|contact| Contact {
+        normal: (-contact.normal.0, -contact.normal.1),
+        depth: contact.depth,
+    })
+}
+
+pub fn triangle_triangle(a: &Triangle, b: &Triangle) -> Option<Contact> {
+    polygon_polygon(&a.points, a.center(), &b.points, b.center())
+}
+
+// Any of the shapes above, for code that doesn't care which ones it is testing
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Aabb(Aabb),
+    Circle(Circle),
+    Triangle(Triangle),
+}
+
+fn flipped(contact: Contact) -> Contact {
+    Contact {
+        normal: (-contact.normal.0, -contact.normal.1),
+        depth: contact.depth,
+    }
+}
+
+pub fn shapes(a: &Shape, b: &Shape) -> Option<Contact> {
+    match (a, b) {
+        (Shape::Aabb(a), Shape::Aabb(b)) => aabb_aabb(a, b),
+        (Shape::Aabb(a), Shape::Circle(b)) => aabb_circle(a, b),
+        (Shape::Aabb(a), Shape::Triangle(b)) => aabb_triangle(a, b),
+        (Shape::Circle(a), Shape::Aabb(b)) => aabb_circle(b, a).map(flipped),
+        (Shape::Circle(a), Shape::Circle(b)) => circle_circle(a, b),
+        (Shape::Circle(a), Shape::Triangle(b)) => circle_triangle(a, b),
+        (Shape::Triangle(a), Shape::Aabb(b)) => aabb_triangle(b, a).map(flipped),
+        (Shape::Triangle(a), Shape::Circle(b)) => circle_triangle(b, a).map(flipped),
+        (Shape::Triangle(a), Shape::Triangle(b)) => triangle_triangle(a, b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aabb(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Aabb {
+        Aabb { min_x, min_y, max_x, max_y }
+    }
+
+    fn circle(x: f32, y: f32, radius: f32) -> Circle {
+        Circle { x, y, radius }
+    }
+
+    #[track_caller]
+    fn assert_contact(contact: Option<Contact>, normal: (f32, f32), depth: f32) {
+        let contact = contact.expect("shapes should overlap");
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        assert!(
+            close(contact.normal.0, normal.0) && close(contact.normal.1, normal.1) && close(contact.depth, depth),
+            "expected normal {:?} and depth {}, got {:?}",
+            normal,
+            depth,
+            contact
+        );
+    }
+
+    #[test]
+    fn box_in_the_triangles_empty_corner_does_not_collide() {
+        let triangle = Triangle::obstacle(0.0, 0.0, 1.0);
+        // Top left of the triangle's bounding box, left of its sloped edge
+        let corner = aabb(-1.0, 0.5, -0.6, 1.0);
+        assert!(aabb_aabb(&corner, &aabb(-1.0, -1.0, 1.0, 1.0)).is_some());
+        assert_eq!(aabb_triangle(&corner, &triangle), None);
+        assert_eq!(shapes(&Shape::Triangle(triangle), &Shape::Aabb(corner)), None);
+        // Same for a circle just past the sloped edge
+        let ball = circle(-0.8, 0.8, 0.2);
+        assert_eq!(circle_triangle(&ball, &triangle), None);
+    }
+
+    #[test]
+    fn separated_shapes_do_not_collide() {
+        assert_eq!(aabb_aabb(&aabb(0.0, 0.0, 1.0, 1.0), &aabb(1.0, 0.0, 2.0, 1.0)), None);
+        assert_eq!(circle_circle(&circle(0.0, 0.0, 1.0), &circle(2.0, 0.0, 1.0)), None);
+        assert_eq!(aabb_circle(&aabb(0.0, 0.0, 1.0, 1.0), &circle(2.0, 2.0, 1.0)), None);
+        let triangles = (Triangle::obstacle(0.0, 0.0, 1.0), Triangle::obstacle(2.5, 0.0, 1.0));
+        assert_eq!(triangle_triangle(&triangles.0, &triangles.1), None);
+    }
+
+    #[test]
+    fn aabb_aabb_pushes_along_the_smaller_overlap() {
+        let (a, b) = (aabb(0.0, 0.0, 2.0, 2.0), aabb(1.5, 0.5, 3.5, 1.5));
+        assert_contact(aabb_aabb(&a, &b), (1.0, 0.0), 0.5);
+        assert_contact(shapes(&Shape::Aabb(b), &Shape::Aabb(a)), (-1.0, 0.0), 0.5);
+
+        let below = aabb(0.5, -1.7, 1.5, 0.3);
+        assert_contact(aabb_aabb(&a, &below), (0.0, -1.0), 0.3);
+    }
+
+    #[test]
+    fn circle_circle_normal_points_from_first_to_second() {
+        let (a, b) = (circle(0.0, 0.0, 1.0), circle(0.0, 1.5, 1.0));
+        assert_contact(circle_circle(&a, &b), (0.0, 1.0), 0.5);
+        assert_contact(shapes(&Shape::Circle(b), &Shape::Circle(a)), (0.0, -1.0), 0.5);
+        // Concentric circles are pushed straight up
+        assert_contact(circle_circle(&a, &circle(0.0, 0.0, 0.5)), (0.0, 1.0), 1.5);
+    }
+
+    #[test]
+    fn aabb_circle_in_both_orders() {
+        let (a, b) = (aabb(0.0, 0.0, 2.0, 2.0), circle(2.5, 1.0, 1.0));
+        assert_contact(aabb_circle(&a, &b), (1.0, 0.0), 0.5);
+        assert_contact(shapes(&Shape::Aabb(a), &Shape::Circle(b)), (1.0, 0.0), 0.5);
+        assert_contact(shapes(&Shape::Circle(b), &Shape::Aabb(a)), (-1.0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn aabb_circle_on_a_corner_pushes_diagonally() {
+        let a = aabb(0.0, 0.0, 1.0, 1.0);
+        let offset = 0.5 / 2.0_f32.sqrt();
+        let b = circle(1.0 + offset, 1.0 + offset, 0.6);
+        let diagonal = 1.0 / 2.0_f32.sqrt();
+        assert_contact(aabb_circle(&a, &b), (diagonal, diagonal), 0.1);
+    }
+
+    #[test]
+    fn aabb_triangle_in_both_orders() {
+        let (a, b) = (aabb(-0.5, -1.8, 0.5, -0.8), Triangle::obstacle(0.0, 0.0, 1.0));
+        assert_contact(aabb_triangle(&a, &b), (0.0, 1.0), 0.2);
+        assert_contact(shapes(&Shape::Aabb(a), &Shape::Triangle(b)), (0.0, 1.0), 0.2);
+        assert_contact(shapes(&Shape::Triangle(b), &Shape::Aabb(a)), (0.0, -1.0), 0.2);
+    }
+
+    #[test]
+    fn circle_triangle_in_both_orders() {
+        let (a, b) = (circle(0.0, -1.5, 1.0), Triangle::obstacle(0.0, 0.0, 1.0));
+        assert_contact(circle_triangle(&a, &b), (0.0, 1.0), 0.5);
+        assert_contact(shapes(&Shape::Circle(a), &Shape::Triangle(b)), (0.0, 1.0), 0.5);
+        assert_contact(shapes(&Shape::Triangle(b), &Shape::Circle(a)), (0.0, -1.0), 0.5);
+    }
+
+    #[test]
+    fn triangle_triangle_pushes_along_the_base() {
+        let (a, b) = (Triangle::obstacle(0.0, 0.0, 1.0), Triangle::obstacle(0.0, -1.8, 1.0));
+        assert_contact(triangle_triangle(&a, &b), (0.0, -1.0), 0.2);
+        assert_contact(shapes(&Shape::Triangle(b), &Shape::Triangle(a)), (0.0, 1.0), 0.2);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::collision::{Circle, Shape, Triangle};
+use crate::spatial_hash::Aabb;
+use serde::{Deserialize, Serialize};
+
+// Components shared by several kinds of entities. Components that only make sense for one kind
+// live next to it: Obstacle, Projectile, PowerUp.
+
+// Center of the entity in world units
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub x: f32,
+    pub y: f32,
+}
+
+// World units per second
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Velocity {
+    pub x: f32,
+    pub y: f32,
+}
+
+// Collision shape around the entity's position
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Collider {
+    Box { half_width: f32, half_height: f32 },
+    // Obstacle shaped, apex up
+    Triangle { half_size: f32 },
+    Circle { radius: f32 },
+}
+
+impl Collider {
+    pub fn bounds(&self, position: &Position) -> Aabb {
+        match *self {
+            Collider::Box { half_width, half_height } => Aabb::from_center(position.x, position.y, half_width, half_height),
+            Collider::Triangle { half_size } => Aabb::from_center(position.x, position.y, half_size, half_size),
+            Collider::Circle { radius } => Aabb::from_center(position.x, position.y, radius, radius),
+        }
+    }
+
+    pub fn shape(&self, position: &Position) -> Shape {
+        match *self {
+            Collider::Box { .. } => Shape::Aabb(self.bounds(position)),
+            Collider::Triangle { half_size } => Shape::Triangle(Triangle::obstacle(position.x, position.y, half_size)),
+            Collider::Circle { radius } => Shape::Circle(Circle {
+                x: position.x,
+                y: position.y,
+                radius,
+            }),
+        }
+    }
+}
+
+// Seconds left before the entity despawns on its own
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Lifetime(pub f32);
+
+// Which clock an entity moves by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Clock {
+    Real,
+    // Sped up by the difficulty and slowed down by slow motion
+    Scaled,
+}
+
+// What an entity does when it reaches the arena edge or a wall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Confinement {
+    // Pushed back in, its velocity reflected
+    Bounce,
+    // Pushed out of walls so it slides along them, free to leave the arena
+    Slide,
+    Despawn,
+}
+
+// The whole texture, for sprites that aren't animated
+pub const FULL_TEXTURE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+// Sprite sheet animation. Frames are (u, v, width, height) rectangles in texture coordinates,
+// shown `fps` times per second. One that doesn't loop stays on its last frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Animation {
+    pub frames: Vec<[f32; 4]>,
+    pub fps: f32,
+    pub looping: bool,
+    // Seconds since the animation started, advanced on the entity's clock
+    pub time: f32,
+    // Frame to draw, updated by the animation system
+    pub uv: [f32; 4],
+}
+
+impl Animation {
+    pub fn new(frames: Vec<[f32; 4]>, fps: f32, looping: bool) -> Animation {
+        let uv = frames.first().copied().unwrap_or(FULL_TEXTURE);
+        Animation {
+            frames,
+            fps,
+            looping,
+            time: 0.0,
+            uv,
+        }
+    }
+
+    // `count` equally wide frames side by side, filling the texture
+    pub fn strip(count: u32, fps: f32, looping: bool) -> Animation {
+        let width = 1.0 / count.max(1) as f32;
+        let frames = (0..count.max(1)).map(|i| [i as f32 * width, 0.0, width, 1.0]).collect();
+        Animation::new(frames, fps, looping)
+    }
+
+    pub fn frame_index(&self) -> usize {
+        let frame = (self.time * self.fps) as usize;
+        match self.frames.len() {
+            0 => 0,
+            count if self.looping => frame % count,
+            count => frame.min(count - 1),
+        }
+    }
+}
+
+// How the render system draws the entity
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Renderable {
+    // Player sprite or rectangle, colored by the player's state
+    Player,
+    // Obstacle sprite tinted with `tint`, or a flat triangle in `color`. Drawn instanced, one
+    // call per distinct pair.
+    Obstacle { tint: [f32; 4], color: [f32; 4] },
+    // Filled square covering the collider bounds
+    Square { color: [f32; 4] },
+}
@@ -0,0 +1,162 @@
+use crate::display::Resolution;
+use crate::input::InputMap;
+use crate::{PLAYER_SPEED, WIN_HEIGHT, WIN_WIDTH};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const CONFIG_PATH: &str = "game.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Vsync {
+    Off,
+    On,
+    // Waits for the vertical blank unless the frame is late, then tears instead of stuttering.
+    // Falls back to On where the driver doesn't support it.
+    Adaptive,
+}
+
+// Older configs have `vsync = true/false`
+fn deserialize_vsync<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vsync, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Setting {
+        Flag(bool),
+        Mode(Vsync),
+    }
+    Ok(match Setting::deserialize(deserializer)? {
+        Setting::Flag(true) => Vsync::On,
+        Setting::Flag(false) => Vsync::Off,
+        Setting::Mode(mode) => mode,
+    })
+}
+
+// How the square arena is fit into a window of a different shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scaling {
+    // Largest centered square, black bars on the remaining sides
+    Letterbox,
+    // The whole window, showing more of the world along the longer side
+    Expand,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub width: u32,
+    pub height: u32,
+    #[serde(deserialize_with = "deserialize_vsync")]
+    pub vsync: Vsync,
+    // Software frame rate cap, 0 for none. --max-fps overrides it.
+    pub max_fps: u32,
+    // Scanlines and screen curvature over the game, toggled in game
+    pub crt_filter: bool,
+    pub scaling: Scaling,
+    // Starts in fullscreen, toggled in game
+    pub fullscreen: bool,
+    // Index of the display the window opens on, see --list-display-modes
+    pub display: i32,
+    // Fullscreen resolution, "1920x1080" or "1920x1080@60". Empty for desktop fullscreen at the
+    // display's own resolution.
+    pub display_mode: String,
+}
+
+impl Default for WindowConfig {
+    fn default() -> WindowConfig {
+        WindowConfig {
+            width: WIN_WIDTH,
+            height: WIN_HEIGHT,
+            vsync: Vsync::On,
+            max_fps: 0,
+            crt_filter: false,
+            scaling: Scaling::Letterbox,
+            fullscreen: false,
+            display: 0,
+            display_mode: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerConfig {
+    // World units per second
+    pub speed: f32,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> PlayerConfig {
+        PlayerConfig { speed: PLAYER_SPEED }
+    }
+}
+
+// Settings read at startup. Missing tables and fields fall back to the defaults, so an old or
+// hand trimmed file keeps working.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub window: WindowConfig,
+    pub player: PlayerConfig,
+    // Action id -> SDL key name, see InputMap::from_bindings
+    pub bindings: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            window: WindowConfig::default(),
+            player: PlayerConfig::default(),
+            bindings: InputMap::default().to_bindings(),
+        }
+    }
+}
+
+impl Config {
+    // Writes the defaults if the file doesn't exist yet so there is something to edit. An
+    // invalid file is left alone and the defaults are used for this run.
+    pub fn load_or_create<P: AsRef<Path>>(path: P) -> Config {
+        let path = path.as_ref();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                let config = Config::default();
+                if let Err(e) = config.save(path) {
+                    eprintln!("Failed to write default config {}: {}", path.display(), e);
+                }
+                return config;
+            }
+        };
+
+        let mut config: Config = match toml::from_str(&contents) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Ignoring invalid config {}: {}", path.display(), e);
+                return Config::default();
+            }
+        };
+
+        let defaults = Config::default();
+        if config.window.width == 0 || config.window.height == 0 {
+            eprintln!("Ignoring window size {}x{} in {}", config.window.width, config.window.height, path.display());
+            config.window.width = defaults.window.width;
+            config.window.height = defaults.window.height;
+        }
+        if let Err(e) = Resolution::parse(&config.window.display_mode) {
+            eprintln!("Ignoring {} in {}", e, path.display());
+            config.window.display_mode = defaults.window.display_mode;
+        }
+        if !(config.player.speed.is_finite() && config.player.speed > 0.0) {
+            eprintln!("Ignoring player speed {} in {}", config.player.speed, path.display());
+            config.player.speed = defaults.player.speed;
+        }
+        config
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let contents = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,137 @@
+use crate::input::{Action, InputMap};
+use crate::renderer::Renderer;
+use crate::text;
+use crate::texture::Texture;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::ttf::Font;
+
+// Opens and closes the menu. Not rebindable so the menu can always be reached again.
+pub const MENU_KEY: Keycode = Keycode::F1;
+
+const MENU_MARGIN: f32 = 40.0;
+const LINE_SPACING: f32 = 6.0;
+
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const SELECTED_COLOR: [f32; 4] = [1.0, 0.9, 0.2, 1.0];
+const CONFLICT_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+const HINT_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+
+// Full screen list of actions and their keys. Up/Down select an action, Enter waits for the
+// next key press and binds it, Backspace restores the default key. Actions sharing a key are
+// drawn in red. The game is paused while the menu is open.
+pub struct ControlsMenu {
+    open: bool,
+    selected: usize,
+    // Set while waiting for the key to bind to the selected action
+    capturing: bool,
+    // Rendered white and tinted when drawn, rebuilt only when the text changes
+    lines: Vec<Texture>,
+    dirty: bool,
+}
+
+impl ControlsMenu {
+    pub fn new() -> ControlsMenu {
+        ControlsMenu {
+            open: false,
+            selected: 0,
+            capturing: false,
+            lines: Vec::new(),
+            dirty: true,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.capturing = false;
+        self.dirty = true;
+    }
+
+    // Returns true if a binding changed, the caller saves the map
+    pub fn handle_key(&mut self, key: Keycode, input_map: &mut InputMap) -> bool {
+        let action = Action::ALL[self.selected];
+        self.dirty = true;
+
+        if self.capturing {
+            self.capturing = false;
+            // Escape cancels, the menu key can't be taken by an action
+            if key == Keycode::Escape || key == MENU_KEY {
+                return false;
+            }
+            input_map.bind(action, key);
+            return true;
+        }
+
+        match key {
+            Keycode::Up => self.selected = (self.selected + Action::ALL.len() - 1) % Action::ALL.len(),
+            Keycode::Down => self.selected = (self.selected + 1) % Action::ALL.len(),
+            Keycode::Return | Keycode::KpEnter => self.capturing = true,
+            Keycode::Backspace => {
+                input_map.reset(action);
+                return true;
+            }
+            Keycode::Escape => self.open = false,
+            _ => (),
+        }
+        false
+    }
+
+    fn line_texts(&self, input_map: &InputMap) -> Vec<String> {
+        let mut texts = vec!["Controls".to_string(), String::new()];
+        for (i, action) in Action::ALL.iter().enumerate() {
+            let key = if self.capturing && i == self.selected {
+                "<press a key>".to_string()
+            } else {
+                input_map.key(*action).name()
+            };
+            let marker = if i == self.selected { ">" } else { " " };
+            texts.push(format!("{} {:<22}{}", marker, action.label(), key));
+        }
+        texts.push(String::new());
+        if input_map.has_conflicts() {
+            texts.push("Keys in red are bound to more than one action".to_string());
+        }
+        texts.push("Up/Down select  Enter rebind  Backspace default  Esc/F1 close".to_string());
+        texts
+    }
+
+    fn line_color(&self, line: usize, input_map: &InputMap) -> [f32; 4] {
+        // The first two lines are the title and a blank one
+        match line.checked_sub(2).and_then(|row| Action::ALL.get(row).map(|action| (row, action))) {
+            Some((_, action)) if input_map.is_conflicting(*action) => CONFLICT_COLOR,
+            Some((row, _)) if row == self.selected => SELECTED_COLOR,
+            Some(_) => TEXT_COLOR,
+            None if line == 0 => TEXT_COLOR,
+            None => HINT_COLOR,
+        }
+    }
+
+    pub fn draw(&mut self, font: &Font, input_map: &InputMap, renderer: &mut Renderer) {
+        if self.dirty {
+            let rendered: Result<Vec<Texture>, String> = self
+                .line_texts(input_map)
+                .iter()
+                // SDL2_ttf refuses to render empty strings
+                .map(|line| text::render_text(font, if line.is_empty() { " " } else { line }, Color::RGBA(255, 255, 255, 255)))
+                .collect();
+            match rendered {
+                Ok(lines) => self.lines = lines,
+                Err(e) => {
+                    eprintln!("Failed to render controls menu: {}", e);
+                    return;
+                }
+            }
+            self.dirty = false;
+        }
+
+        let mut y = MENU_MARGIN;
+        for (i, line) in self.lines.iter().enumerate() {
+            renderer.draw_texture_at_pixel(line, (MENU_MARGIN, y), self.line_color(i, input_map));
+            y += line.height() as f32 + LINE_SPACING;
+        }
+    }
+}
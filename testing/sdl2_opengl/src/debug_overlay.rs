@@ -0,0 +1,175 @@
+use crate::renderer::Renderer;
+use crate::texture::Texture;
+use crate::world::{World, MAX_DIFFICULTY};
+use imgui::{Condition, Context, Key, MouseButton, TextureId};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton as SdlMouseButton;
+use sdl2::video::Window;
+
+// Range of the player speed slider in world units per second
+const MAX_PLAYER_SPEED: f32 = 3.0;
+
+fn key(keycode: Keycode) -> Option<Key> {
+    Some(match keycode {
+        Keycode::Tab => Key::Tab,
+        Keycode::Left => Key::LeftArrow,
+        Keycode::Right => Key::RightArrow,
+        Keycode::Up => Key::UpArrow,
+        Keycode::Down => Key::DownArrow,
+        Keycode::Home => Key::Home,
+        Keycode::End => Key::End,
+        Keycode::Delete => Key::Delete,
+        Keycode::Backspace => Key::Backspace,
+        Keycode::Return | Keycode::KpEnter => Key::Enter,
+        Keycode::Escape => Key::Escape,
+        Keycode::LCtrl => Key::LeftCtrl,
+        Keycode::RCtrl => Key::RightCtrl,
+        Keycode::A => Key::A,
+        Keycode::C => Key::C,
+        Keycode::V => Key::V,
+        Keycode::X => Key::X,
+        Keycode::Z => Key::Z,
+        _ => return None,
+    })
+}
+
+fn mouse_button(button: SdlMouseButton) -> Option<MouseButton> {
+    match button {
+        SdlMouseButton::Left => Some(MouseButton::Left),
+        SdlMouseButton::Right => Some(MouseButton::Right),
+        SdlMouseButton::Middle => Some(MouseButton::Middle),
+        _ => None,
+    }
+}
+
+// imgui panel with entity counts, speed sliders and debug view toggles, drawn on top of
+// everything while visible. Events go to imgui first while it is shown, the game only gets the
+// ones imgui doesn't want. Tweaks aren't part of a replay, a run changed through the panel
+// doesn't play back the same, so draw() reports them and the game stops recording.
+pub struct DebugOverlay {
+    imgui: Context,
+    // The font atlas, imgui refers to it by its GL id
+    _font_texture: Texture,
+    visible: bool,
+    // Bounding boxes of all colliders in the scene
+    pub show_colliders: bool,
+}
+
+impl DebugOverlay {
+    pub fn new() -> DebugOverlay {
+        let mut imgui = Context::create();
+        // Window positions don't need to survive a restart
+        imgui.set_ini_filename(None);
+        let font_texture = {
+            let atlas = imgui.fonts().build_rgba32_texture();
+            // Uploaded top row first, which is what imgui's texture coordinates expect
+            Texture::from_rgba(atlas.width, atlas.height, atlas.data)
+        };
+        imgui.fonts().tex_id = TextureId::new(font_texture.id() as usize);
+
+        DebugOverlay {
+            imgui,
+            _font_texture: font_texture,
+            visible: false,
+            show_colliders: false,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Feeds the event to imgui, returns true if imgui wants it for itself. The game still gets
+    // everything while the overlay is hidden.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if !self.visible {
+            return false;
+        }
+        let io = self.imgui.io_mut();
+        match *event {
+            Event::MouseMotion { x, y, .. } => {
+                io.add_mouse_pos_event([x as f32, y as f32]);
+                io.want_capture_mouse
+            }
+            Event::MouseButtonDown { mouse_btn, .. } | Event::MouseButtonUp { mouse_btn, .. } => {
+                if let Some(button) = mouse_button(mouse_btn) {
+                    io.add_mouse_button_event(button, matches!(event, Event::MouseButtonDown { .. }));
+                }
+                io.want_capture_mouse
+            }
+            Event::MouseWheel { x, y, .. } => {
+                io.add_mouse_wheel_event([x as f32, y as f32]);
+                io.want_capture_mouse
+            }
+            Event::TextInput { ref text, .. } => {
+                text.chars().for_each(|c| io.add_input_character(c));
+                io.want_capture_keyboard
+            }
+            Event::KeyDown { keycode: Some(keycode), .. } | Event::KeyUp { keycode: Some(keycode), .. } => {
+                let down = matches!(event, Event::KeyDown { .. });
+                if matches!(keycode, Keycode::LCtrl | Keycode::RCtrl) {
+                    io.add_key_event(Key::ModCtrl, down);
+                }
+                if let Some(key) = key(keycode) {
+                    io.add_key_event(key, down);
+                }
+                io.want_capture_keyboard
+            }
+            _ => false,
+        }
+    }
+
+    // Builds this frame's panel, applies whatever was changed in it and draws it. Call last,
+    // after the scene and the HUD. Returns true if a slider changed the world.
+    pub fn draw(
+        &mut self,
+        window: &Window,
+        dt: f32,
+        world: &mut World,
+        particle_count: usize,
+        show_broad_phase: &mut bool,
+        renderer: &mut Renderer,
+    ) -> bool {
+        if !self.visible {
+            return false;
+        }
+        let (width, height) = window.size();
+        let (drawable_width, drawable_height) = window.drawable_size();
+        let io = self.imgui.io_mut();
+        io.display_size = [width as f32, height as f32];
+        if width > 0 && height > 0 {
+            io.display_framebuffer_scale = [
+                drawable_width as f32 / width as f32,
+                drawable_height as f32 / height as f32,
+            ];
+        }
+        // imgui asserts on a zero time step
+        io.delta_time = dt.max(1e-4);
+
+        let show_colliders = &mut self.show_colliders;
+        let ui = self.imgui.new_frame();
+        let tweaked = ui
+            .window("Debug")
+            .position([10.0, 10.0], Condition::FirstUseEver)
+            .always_auto_resize(true)
+            .build(|| {
+                ui.text(format!("Entities: {}", world.entities.len()));
+                ui.text(format!("Obstacles: {}", world.obstacles.len()));
+                ui.text(format!("Projectiles: {}", world.projectiles.len()));
+                ui.text(format!("Power-ups: {}", world.power_ups.len()));
+                ui.text(format!("Particles: {}", particle_count));
+                ui.separator();
+                let mut tweaked = ui.slider("Player speed", 0.1, MAX_PLAYER_SPEED, &mut world.player_speed);
+                // The difficulty keeps ramping up from whatever it is set to
+                tweaked |= ui.slider("Obstacle speed", 0.1, MAX_DIFFICULTY, &mut world.difficulty);
+                ui.separator();
+                ui.checkbox("Collision boxes", show_colliders);
+                ui.checkbox("Broad phase grid", show_broad_phase);
+                tweaked
+            })
+            .unwrap_or(false);
+        renderer.draw_imgui(self.imgui.render());
+        tweaked
+    }
+}
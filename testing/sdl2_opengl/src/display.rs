@@ -0,0 +1,101 @@
+use sdl2::video::{DisplayMode, FullscreenType, Window, WindowPos};
+use sdl2::VideoSubsystem;
+
+// Resolution asked for in the config, "1920x1080" or "1920x1080@60"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: i32,
+    pub height: i32,
+    // Any refresh rate if missing
+    pub refresh_rate: Option<i32>,
+}
+
+impl Resolution {
+    // Empty means none, desktop fullscreen at the display's own resolution
+    pub fn parse(text: &str) -> Result<Option<Resolution>, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let invalid = || format!("invalid display mode '{}', expected WIDTHxHEIGHT or WIDTHxHEIGHT@HZ", text);
+        let (size, refresh_rate) = match text.split_once('@') {
+            Some((size, rate)) => (size, Some(rate.trim().parse::<i32>().map_err(|_| invalid())?)),
+            None => (text, None),
+        };
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        let width = width.trim().parse::<i32>().map_err(|_| invalid())?;
+        let height = height.trim().parse::<i32>().map_err(|_| invalid())?;
+        if width <= 0 || height <= 0 || refresh_rate.is_some_and(|rate| rate <= 0) {
+            return Err(invalid());
+        }
+        Ok(Some(Resolution {
+            width,
+            height,
+            refresh_rate,
+        }))
+    }
+}
+
+// For `--list-display-modes`, so there is something to copy into the config
+pub fn print_display_modes(video: &VideoSubsystem) -> Result<(), String> {
+    for display in 0..video.num_video_displays()? {
+        let bounds = video.display_bounds(display)?;
+        println!(
+            "Display {}: {} ({}x{} at {}, {})",
+            display,
+            video.display_name(display)?,
+            bounds.width(),
+            bounds.height(),
+            bounds.x(),
+            bounds.y()
+        );
+        for mode in 0..video.num_display_modes(display)? {
+            let mode = video.display_mode(display, mode)?;
+            println!("  {}x{}@{}", mode.w, mode.h, mode.refresh_rate);
+        }
+    }
+    Ok(())
+}
+
+// Centers the window on the given display
+pub fn move_to_display(window: &mut Window, video: &VideoSubsystem, display: i32) -> Result<(), String> {
+    let displays = video.num_video_displays()?;
+    if !(0..displays).contains(&display) {
+        return Err(format!("no display {}, there are {}", display, displays));
+    }
+    let bounds = video.display_bounds(display)?;
+    let (width, height) = window.size();
+    let x = bounds.x() + (bounds.width() as i32 - width as i32) / 2;
+    let y = bounds.y() + (bounds.height() as i32 - height as i32) / 2;
+    window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
+    Ok(())
+}
+
+// Exclusive fullscreen in the display mode closest to `resolution` on the window's current
+// display, or desktop fullscreen without one. The window sends a size change event afterwards.
+pub fn set_fullscreen(
+    window: &mut Window,
+    video: &VideoSubsystem,
+    fullscreen: bool,
+    resolution: Option<Resolution>,
+) -> Result<(), String> {
+    if !fullscreen {
+        return window.set_fullscreen(FullscreenType::Off);
+    }
+    match resolution {
+        Some(resolution) => {
+            let display = window.display_index()?;
+            let wanted = DisplayMode::new(
+                window.window_pixel_format(),
+                resolution.width,
+                resolution.height,
+                resolution.refresh_rate.unwrap_or(0),
+            );
+            let mode = video.closest_display_mode(display, &wanted)?;
+            println!("Fullscreen in {}x{}@{}", mode.w, mode.h, mode.refresh_rate);
+            window.set_display_mode(mode)?;
+            window.set_fullscreen(FullscreenType::True)
+        }
+        None => window.set_fullscreen(FullscreenType::Desktop),
+    }
+}
@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+
+// Handle to an entity. The generation tells a despawned entity apart from a later one that
+// reuses its slot, so stale handles never reach the new entity's components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+// Hands out entity handles and keeps track of which are alive
+#[derive(Default, Serialize, Deserialize)]
+pub struct Entities {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    // Slots of despawned entities, reused before the vectors grow
+    free: Vec<u32>,
+}
+
+impl Entities {
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = index as usize;
+                self.generations[slot] += 1;
+                self.alive[slot] = true;
+                Entity {
+                    index,
+                    generation: self.generations[slot],
+                }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity {
+                    index: (self.alive.len() - 1) as u32,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    // False if the entity was already gone. Its components have to be removed separately.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        self.alive[entity.index as usize] = false;
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.alive.iter().filter(|alive| **alive).count()
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let slot = entity.index as usize;
+        slot < self.alive.len() && self.alive[slot] && self.generations[slot] == entity.generation
+    }
+
+    // Checks what spawn and is_alive rely on, for entities that were loaded rather than built
+    // up here
+    pub fn validate(&self) -> Result<(), String> {
+        if self.generations.len() != self.alive.len() {
+            return Err(format!(
+                "{} generations for {} entity slots",
+                self.generations.len(),
+                self.alive.len()
+            ));
+        }
+        let mut seen = vec![false; self.alive.len()];
+        for &index in &self.free {
+            let slot = index as usize;
+            if slot >= self.alive.len() {
+                return Err(format!("free entity slot {} is out of range", index));
+            }
+            if self.alive[slot] {
+                return Err(format!("free entity slot {} is alive", index));
+            }
+            if std::mem::replace(&mut seen[slot], true) {
+                return Err(format!("entity slot {} is free twice", index));
+            }
+        }
+        Ok(())
+    }
+}
+
+// One component type for all entities, indexed by entity slot. Serialized as a list of
+// entity and component pairs, TOML has no way to write the empty slots.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "Vec<Entry<T>>", into = "Vec<Entry<T>>", bound(serialize = "T: Clone + Serialize"))]
+pub struct Storage<T> {
+    slots: Vec<Option<(u32, T)>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    entity: Entity,
+    component: T,
+}
+
+impl<T> From<Vec<Entry<T>>> for Storage<T> {
+    fn from(entries: Vec<Entry<T>>) -> Storage<T> {
+        let mut storage = Storage::default();
+        for entry in entries {
+            storage.insert(entry.entity, entry.component);
+        }
+        storage
+    }
+}
+
+impl<T> From<Storage<T>> for Vec<Entry<T>> {
+    fn from(storage: Storage<T>) -> Vec<Entry<T>> {
+        let slots = storage.slots.into_iter().enumerate();
+        slots
+            .filter_map(|(index, slot)| {
+                slot.map(|(generation, component)| Entry {
+                    entity: Entity {
+                        index: index as u32,
+                        generation,
+                    },
+                    component,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Storage<T> {
+        Storage { slots: Vec::new() }
+    }
+}
+
+impl<T> Storage<T> {
+    // Replaces the component if the entity already has one
+    pub fn insert(&mut self, entity: Entity, component: T) {
+        let slot = entity.index as usize;
+        if slot >= self.slots.len() {
+            self.slots.resize_with(slot + 1, || None);
+        }
+        self.slots[slot] = Some((entity.generation, component));
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slots.get_mut(entity.index as usize)?;
+        match slot {
+            Some((generation, _)) if *generation == entity.generation => slot.take().map(|(_, component)| component),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.slots.get(entity.index as usize)? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.slots.get_mut(entity.index as usize)? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.as_ref().map(|(generation, component)| {
+                let entity = Entity {
+                    index: index as u32,
+                    generation: *generation,
+                };
+                (entity, component)
+            })
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+            slot.as_mut().map(|(generation, component)| {
+                let entity = Entity {
+                    index: index as u32,
+                    generation: *generation,
+                };
+                (entity, component)
+            })
+        })
+    }
+
+    pub fn entities(&self) -> Vec<Entity> {
+        self.iter().map(|(entity, _)| entity).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_handles_are_rejected_after_the_slot_is_reused() {
+        let mut entities = Entities::default();
+        let mut names = Storage::default();
+        let first = entities.spawn();
+        names.insert(first, "first");
+
+        assert!(entities.despawn(first));
+        assert!(!entities.despawn(first));
+        assert_eq!(names.remove(first), Some("first"));
+
+        let second = entities.spawn();
+        names.insert(second, "second");
+        assert_eq!(second.index, first.index);
+        assert_ne!(second, first);
+        assert!(!entities.is_alive(first));
+        assert!(entities.is_alive(second));
+        assert_eq!(names.get(first), None);
+        assert_eq!(names.remove(first), None);
+        assert!(!entities.despawn(first));
+        assert_eq!(names.get(second), Some(&"second"));
+        assert_eq!(entities.len(), 1);
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Saved {
+        entities: Entities,
+        values: Storage<u32>,
+    }
+
+    #[test]
+    fn storage_round_trip() {
+        let mut entities = Entities::default();
+        let mut values = Storage::default();
+        let handles: Vec<Entity> = (0..4).map(|_| entities.spawn()).collect();
+        for (value, entity) in handles.iter().enumerate() {
+            values.insert(*entity, value as u32 * 10);
+        }
+        // Leaves a gap at slot 1 and a reused slot 2 with a newer generation
+        entities.despawn(handles[1]);
+        values.remove(handles[1]);
+        entities.despawn(handles[2]);
+        values.remove(handles[2]);
+        let reused = entities.spawn();
+        values.insert(reused, 99);
+
+        let text = toml::to_string(&Saved { entities, values }).unwrap();
+        let loaded: Saved = toml::from_str(&text).unwrap();
+
+        assert!(loaded.entities.validate().is_ok());
+        assert_eq!(loaded.values.len(), 3);
+        assert_eq!(loaded.values.get(handles[0]), Some(&0));
+        assert_eq!(loaded.values.get(handles[1]), None);
+        assert_eq!(loaded.values.get(handles[2]), None);
+        assert_eq!(loaded.values.get(reused), Some(&99));
+        assert_eq!(loaded.values.get(handles[3]), Some(&30));
+        assert!(loaded.entities.is_alive(reused));
+        assert!(!loaded.entities.is_alive(handles[1]));
+        // Slot 1 is still free and handed out next
+        assert_eq!(loaded.entities.free, vec![1]);
+    }
+
+    #[test]
+    fn validate_rejects_inconsistent_entities() {
+        let mut entities = Entities::default();
+        let a = entities.spawn();
+        entities.spawn();
+        entities.despawn(a);
+        assert!(entities.validate().is_ok());
+
+        let corrupt = |change: fn(&mut Entities)| {
+            let mut entities = Entities {
+                generations: entities.generations.clone(),
+                alive: entities.alive.clone(),
+                free: entities.free.clone(),
+            };
+            change(&mut entities);
+            entities.validate()
+        };
+        assert!(corrupt(|e| e.free.push(7)).is_err());
+        assert!(corrupt(|e| e.free.push(1)).is_err());
+        assert!(corrupt(|e| e.free.push(0)).is_err());
+        assert!(corrupt(|e| {
+            e.generations.pop();
+        })
+        .is_err());
+        assert!(corrupt(|e| e.alive.push(false)).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+
+// thread::sleep can overshoot by a scheduler tick, so it is only used until this close to the
+// deadline and the rest is spent yielding
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+// How often the effective frame rate is printed
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+// Software frame rate cap, on top of or instead of vsync. Frames are scheduled against fixed
+// deadlines so the average rate matches the cap instead of drifting below it.
+pub struct FrameLimiter {
+    frame_time: Option<Duration>,
+    next_frame: Instant,
+    frames: u32,
+    // Longest frame since the last log line
+    slowest: Duration,
+    last_frame: Instant,
+    last_log: Instant,
+}
+
+impl FrameLimiter {
+    // No cap for `max_fps` 0
+    pub fn new(max_fps: u32) -> FrameLimiter {
+        let now = Instant::now();
+        FrameLimiter {
+            frame_time: (max_fps > 0).then(|| Duration::from_secs_f64(1.0 / max_fps as f64)),
+            next_frame: now,
+            frames: 0,
+            slowest: Duration::ZERO,
+            last_frame: now,
+            last_log: now,
+        }
+    }
+
+    // Blocks until the next frame is due, call once per frame before measuring its time step
+    pub fn wait(&mut self) {
+        if let Some(frame_time) = self.frame_time {
+            self.next_frame += frame_time;
+            let now = Instant::now();
+            if self.next_frame < now {
+                // Too far behind to catch up, start over rather than rushing the next frames
+                self.next_frame = now;
+            } else {
+                let remaining = self.next_frame - now;
+                if remaining > SPIN_MARGIN {
+                    std::thread::sleep(remaining - SPIN_MARGIN);
+                }
+                while Instant::now() < self.next_frame {
+                    std::thread::yield_now();
+                }
+            }
+        }
+        self.log_frame();
+    }
+
+    fn log_frame(&mut self) {
+        let now = Instant::now();
+        self.slowest = self.slowest.max(now - self.last_frame);
+        self.last_frame = now;
+        self.frames += 1;
+
+        let elapsed = now - self.last_log;
+        if elapsed >= LOG_INTERVAL {
+            println!(
+                "{:.1} fps, slowest frame {:.1} ms",
+                self.frames as f64 / elapsed.as_secs_f64(),
+                self.slowest.as_secs_f64() * 1000.0
+            );
+            self.frames = 0;
+            self.slowest = Duration::ZERO;
+            self.last_log = now;
+        }
+    }
+}
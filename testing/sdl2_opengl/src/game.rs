@@ -0,0 +1,538 @@
+use crate::audio::Audio;
+use crate::camera::Camera;
+use crate::components::Position;
+use crate::config::{Config, CONFIG_PATH};
+use crate::controls_menu::{ControlsMenu, MENU_KEY};
+use crate::debug_overlay::DebugOverlay;
+use crate::display::{self, Resolution};
+use crate::ecs::Storage;
+use crate::game_state::{GameState, StateScreen};
+use crate::highscores::{HighScores, HIGH_SCORES_PATH};
+use crate::hud::Hud;
+use crate::input::{Action, FrameInput, InputMap};
+use crate::level::{self, Level, LEVELS_DIR};
+use crate::net::{NetEvent, NetSession};
+use crate::particles::{Particles, EXPLOSION_COLOR, HIT_COLOR, TRAIL_COLOR};
+use crate::renderer::Renderer;
+use crate::replay::{Playback, Replay, REPLAY_PATH};
+use crate::save::{SaveGame, SAVE_PATH};
+use crate::spatial_hash::SpatialHash;
+use crate::systems;
+use crate::world::World;
+use crate::{parse_arg, parse_number_arg, run_seed};
+use crate::{BROAD_PHASE_CELL_SIZE, DIM_OVERLAY_ALPHA, HUD_FONT_PATH, HUD_FONT_SIZE, WINDOW_TITLE};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::ttf::Sdl2TtfContext;
+use sdl2::video::{FullscreenType, Window};
+use sdl2::{EventPump, Sdl, VideoSubsystem};
+
+// A new run in the given level, and the replay recording it
+fn new_run(player_speed: f32, level_index: usize, levels: &[Level], seed: u64) -> (World, Replay) {
+    let world = World::new(player_speed, &levels[level_index], seed);
+    (world, Replay::new(seed, level_index, player_speed))
+}
+
+fn save_replay(replay: &Replay) {
+    match replay.save(REPLAY_PATH) {
+        Ok(()) => println!("Saved replay of {} frames to {}", replay.len(), REPLAY_PATH),
+        Err(e) => eprintln!("Failed to save {}: {}", REPLAY_PATH, e),
+    }
+}
+
+// Everything the main loop works with besides the window and SDL itself. Each frame handles
+// input once, steps the world by a fixed time step as often as the elapsed time allows, then
+// renders once, interpolating between the last two steps.
+pub struct Game<'ttf> {
+    pub running: bool,
+    config: Config,
+    // `--seed`, reused for every run when given
+    fixed_seed: Option<u64>,
+    // Display mode for fullscreen
+    resolution: Option<Resolution>,
+    renderer: Renderer,
+    camera: Camera,
+    particles: Particles,
+    spatial_hash: SpatialHash,
+    show_broad_phase: bool,
+    debug_overlay: DebugOverlay,
+    // Optional, the game runs without the HUD if SDL2_ttf fails to initialize or the font can't
+    // be loaded. The SDL2_ttf library itself is linked in and has to be present.
+    hud: Option<Hud<'ttf>>,
+    // Same for sound, without an audio device the game is just silent. SDL2_mixer has to be
+    // present either way.
+    audio: Option<Audio>,
+    high_scores: HighScores,
+    // Rank of the last finished run, highlighted on the game over screen
+    new_rank: Option<usize>,
+    levels: Vec<Level>,
+    level_index: usize,
+    world: World,
+    // Entity positions before the last step, what rendering interpolates from
+    previous_positions: Storage<Position>,
+    // Every run started here is recorded, loaded save games aren't
+    recording: Option<Replay>,
+    // Set while playing back a `--replay` file
+    playback: Option<Playback>,
+    // The debug overlay changed the current run, which then neither records nor counts for
+    // the high scores
+    tweaked: bool,
+    state: GameState,
+    state_screen: StateScreen,
+    net: Option<NetSession>,
+    input_map: InputMap,
+    controls_menu: ControlsMenu,
+    // Held keys as of the last process_input, used by every step until the next one
+    input: FrameInput,
+}
+
+impl<'ttf> Game<'ttf> {
+    pub fn new(
+        sdl: &Sdl,
+        window: &Window,
+        ttf: &'ttf Result<Sdl2TtfContext, String>,
+        config: Config,
+        fixed_seed: Option<u64>,
+        resolution: Option<Resolution>,
+    ) -> Game<'ttf> {
+        let renderer = Renderer::new(window, config.window.crt_filter, config.window.scaling)
+            .unwrap_or_else(|e| panic!("Failed to initialize the renderer: {}", e));
+
+        let hud = match ttf {
+            Ok(ttf) => Hud::new(ttf, HUD_FONT_PATH, HUD_FONT_SIZE)
+                .map_err(|e| eprintln!("Failed to load HUD font {}: {}", HUD_FONT_PATH, e))
+                .ok(),
+            Err(e) => {
+                eprintln!("Failed to initialize SDL2_ttf: {}", e);
+                None
+            }
+        };
+        let audio = Audio::new(sdl)
+            .map_err(|e| eprintln!("Failed to initialize audio: {}", e))
+            .ok();
+        if let Some(audio) = &audio {
+            audio.play_music();
+        }
+
+        let levels = level::load_levels(LEVELS_DIR);
+        let (world, replay) = new_run(config.player.speed, 0, &levels, run_seed(fixed_seed));
+        let input_map = InputMap::from_bindings(&config.bindings, CONFIG_PATH);
+        let mut game = Game {
+            running: true,
+            fixed_seed,
+            resolution,
+            renderer,
+            camera: Camera::new(),
+            particles: Particles::new(),
+            spatial_hash: SpatialHash::new(BROAD_PHASE_CELL_SIZE),
+            show_broad_phase: false,
+            debug_overlay: DebugOverlay::new(),
+            hud,
+            audio,
+            high_scores: HighScores::load(HIGH_SCORES_PATH),
+            new_rank: None,
+            levels,
+            level_index: 0,
+            previous_positions: world.positions.clone(),
+            world,
+            recording: Some(replay),
+            playback: None,
+            tweaked: false,
+            state: GameState::Title,
+            state_screen: StateScreen::new(),
+            net: None,
+            input_map,
+            controls_menu: ControlsMenu::new(),
+            input: FrameInput::default(),
+            config,
+        };
+
+        // `--replay <file>` starts right away with the recorded run instead of the title screen.
+        // Starting another run or loading a save ends the playback.
+        if let Some(path) = parse_arg("--replay") {
+            match Replay::load(&path) {
+                Ok(replay) if replay.level_index < game.levels.len() => {
+                    println!("Playing back {} ({} frames, seed {})", path, replay.len(), replay.seed);
+                    game.level_index = replay.level_index;
+                    game.set_world(World::new(replay.player_speed, &game.levels[game.level_index], replay.seed));
+                    game.recording = None;
+                    game.playback = Some(Playback::new(replay));
+                    game.state = GameState::Playing;
+                }
+                Ok(replay) => eprintln!(
+                    "Failed to play back {}: recorded in level {}, there are only {}",
+                    path,
+                    replay.level_index,
+                    game.levels.len()
+                ),
+                Err(e) => eprintln!("Failed to load replay {}: {}", path, e),
+            }
+        }
+
+        // `--host <port>` waits for a second player to join with `--join <address>`, then both
+        // dodge the host's obstacles
+        let net_session = if let Some(port) = parse_number_arg("--host") {
+            println!("Hosting on port {}, waiting for someone to join", port);
+            Some(NetSession::host(port))
+        } else {
+            parse_arg("--join").map(|address| {
+                println!("Joining {}", address);
+                NetSession::join(&address)
+            })
+        };
+        game.net = net_session.and_then(|session| {
+            session
+                .map_err(|e| eprintln!("Failed to start the network session: {}", e))
+                .ok()
+        });
+        game
+    }
+
+    // Replaces the world, without interpolating from the old one's positions
+    fn set_world(&mut self, world: World) {
+        self.previous_positions = world.positions.clone();
+        self.world = world;
+        self.tweaked = false;
+    }
+
+    // A recorded run in the current level, which a network peer switches to as well
+    fn start_run(&mut self) {
+        let (world, replay) = new_run(self.config.player.speed, self.level_index, &self.levels, run_seed(self.fixed_seed));
+        if let Some(net) = &mut self.net {
+            net.run_started(replay.seed, self.level_index);
+        }
+        self.set_world(world);
+        self.recording = Some(replay);
+        self.playback = None;
+    }
+
+    // Handles this frame's events and network messages and reads the held keys. `frame_time`
+    // is the real time since the last frame.
+    pub fn process_input(
+        &mut self,
+        event_pump: &mut EventPump,
+        window: &mut Window,
+        video_subsystem: &VideoSubsystem,
+        frame_time: f32,
+    ) {
+        for event in event_pump.poll_iter() {
+            if self.debug_overlay.handle_event(&event) {
+                continue;
+            }
+            match event {
+                Event::Quit { .. } => self.running = false,
+                // Also sent for size changes made by the program, unlike Resized
+                Event::Window { win_event: WindowEvent::SizeChanged(..), .. } => self.renderer.resize(window),
+                Event::MouseWheel { y, .. } if self.state != GameState::Title => self.camera.zoom_by(y),
+                // Don't keep playing in the background after alt-tabbing away
+                Event::Window { win_event: WindowEvent::FocusLost, .. } if self.state == GameState::Playing => {
+                    self.state = GameState::Paused;
+                }
+                // The menu needs the HUD font to draw anything
+                Event::KeyDown { keycode: Some(MENU_KEY), repeat: false, .. } if self.hud.is_some() => {
+                    self.controls_menu.toggle();
+                }
+                Event::KeyDown { keycode: Some(keycode), .. } if self.controls_menu.is_open() => {
+                    // Saved right away so a rebinding survives a crash or a killed process
+                    let changed = self.controls_menu.handle_key(keycode, &mut self.input_map);
+                    if changed {
+                        self.config.bindings = self.input_map.to_bindings();
+                        if let Err(e) = self.config.save(CONFIG_PATH) {
+                            eprintln!("Failed to save {}: {}", CONFIG_PATH, e);
+                        }
+                    }
+                }
+                Event::KeyDown { keycode: Some(keycode), repeat, .. } => {
+                    let action = self.input_map.action_for(keycode);
+                    // State changes (start, pause, restart) take precedence over other actions
+                    if let Some(next) = self.state.next(keycode, action).filter(|_| !repeat) {
+                        if self.state.starts_new_run(next) {
+                            self.start_run();
+                        }
+                        self.state = next;
+                        continue;
+                    }
+
+                    match action {
+                        Some(Action::Quit) => self.running = false,
+                        // Switching levels starts a new run in the next one, the title screen
+                        // only changes its selection
+                        Some(Action::NextLevel) if !repeat => {
+                            self.level_index = (self.level_index + 1) % self.levels.len();
+                            self.start_run();
+                            if self.state != GameState::Title {
+                                self.state = GameState::Playing;
+                            }
+                        }
+                        // Only a run in progress can be saved
+                        Some(Action::QuickSave)
+                            if !repeat && matches!(self.state, GameState::Playing | GameState::Paused) =>
+                        {
+                            match SaveGame::save(SAVE_PATH, self.level_index, &self.world) {
+                                Ok(()) => println!("Saved to {}", SAVE_PATH),
+                                Err(e) => eprintln!("Failed to save {}: {}", SAVE_PATH, e),
+                            }
+                        }
+                        // Loaded paused, so the player has a moment to see where everything is
+                        Some(Action::QuickLoad) if !repeat => match SaveGame::load(SAVE_PATH) {
+                            Ok(save) => {
+                                self.level_index = save.level_index.min(self.levels.len() - 1);
+                                self.set_world(save.world);
+                                self.recording = None;
+                                self.playback = None;
+                                self.state = GameState::Paused;
+                                println!("Loaded {}", SAVE_PATH);
+                            }
+                            Err(e) => eprintln!("Failed to load {}: {}", SAVE_PATH, e),
+                        },
+                        Some(Action::ToggleBroadPhase) if !repeat => {
+                            self.show_broad_phase = !self.show_broad_phase;
+                            if !self.show_broad_phase {
+                                window.set_title(WINDOW_TITLE).unwrap();
+                            }
+                        }
+                        Some(Action::ToggleDebugOverlay) if !repeat => self.debug_overlay.toggle(),
+                        Some(Action::ToggleCrt) if !repeat => self.renderer.toggle_crt(),
+                        Some(Action::ToggleFullscreen) if !repeat => {
+                            let fullscreen = window.fullscreen_state() == FullscreenType::Off;
+                            match display::set_fullscreen(window, video_subsystem, fullscreen, self.resolution) {
+                                Ok(()) => self.renderer.resize(window),
+                                Err(e) => eprintln!("Failed to toggle fullscreen: {}", e),
+                            }
+                        }
+                        Some(Action::VolumeUp) => {
+                            if let Some(audio) = &mut self.audio {
+                                audio.change_volume(1);
+                            }
+                        }
+                        Some(Action::VolumeDown) => {
+                            if let Some(audio) = &mut self.audio {
+                                audio.change_volume(-1);
+                            }
+                        }
+                        Some(Action::ToggleMute) if !repeat => {
+                            if let Some(audio) = &mut self.audio {
+                                audio.toggle_mute();
+                            }
+                        }
+                        // Movement is read from the held keys below, not from key repeat
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.input = self.input_map.frame_input(&event_pump.keyboard_state());
+        self.process_network(frame_time);
+    }
+
+    // Both sides start the host's run together. The joining side takes the host's obstacles
+    // before it steps, so its collisions are against where the host has them.
+    fn process_network(&mut self, frame_time: f32) {
+        let Some(net) = &mut self.net else {
+            return;
+        };
+        let (x, y) = self.world.player_position();
+        let obstacles = if net.is_host() { self.world.obstacle_states() } else { Vec::new() };
+        for event in net.update(frame_time, Position { x, y }, &obstacles) {
+            match event {
+                NetEvent::PeerJoined(address) => {
+                    println!("{} joined", address);
+                    let (world, replay) =
+                        new_run(self.config.player.speed, self.level_index, &self.levels, run_seed(self.fixed_seed));
+                    net.welcome(replay.seed, self.level_index);
+                    self.previous_positions = world.positions.clone();
+                    self.world = world;
+                    self.tweaked = false;
+                    self.recording = Some(replay);
+                    self.playback = None;
+                    self.state = GameState::Playing;
+                }
+                NetEvent::Welcomed { seed, level_index } => {
+                    self.level_index = level_index.min(self.levels.len() - 1);
+                    println!("Joined, playing {}", self.levels[self.level_index].name);
+                    let world = World::new(self.config.player.speed, &self.levels[self.level_index], seed);
+                    self.previous_positions = world.positions.clone();
+                    self.world = world;
+                    self.tweaked = false;
+                    // The mirrored obstacles aren't part of a replay
+                    self.recording = None;
+                    self.playback = None;
+                    self.state = GameState::Playing;
+                }
+                NetEvent::PeerLost => {
+                    println!("Lost the other player, playing on alone");
+                    self.world.mirrored_obstacles = false;
+                }
+            }
+        }
+        if let Some(obstacles) = net.remote_obstacles().filter(|_| !net.is_host()) {
+            self.world.sync_obstacles(&obstacles);
+        }
+    }
+
+    // Whether fixed_update advances the world. It is frozen outside of Playing and while the
+    // controls menu is open, including obstacle movement and the score and invulnerability
+    // timers. Rendering runs either way so a paused game stays on screen.
+    fn is_simulating(&self) -> bool {
+        self.state.is_simulating() && !self.controls_menu.is_open()
+    }
+
+    // Advances the world by one step of `dt` seconds with the last read input
+    pub fn fixed_update(&mut self, dt: f32) {
+        // A played back run that ends without a game over was quit while it was recorded
+        if self.state.is_simulating() && self.playback.as_ref().is_some_and(Playback::is_finished) {
+            println!("Replay finished with score {}", self.world.score);
+            self.playback = None;
+            self.state = GameState::Paused;
+        }
+        if !self.is_simulating() {
+            return;
+        }
+
+        // During playback the recorded time step and input replace the real ones
+        let (dt, input) = match self.playback.as_mut().and_then(Playback::next_frame) {
+            Some(frame) => frame,
+            None => (dt, self.input),
+        };
+        if let Some(recording) = &mut self.recording {
+            recording.record(dt, input);
+        }
+        self.previous_positions = self.world.positions.clone();
+        let hit = self.world.update(dt, &input, &mut self.spatial_hash);
+        for position in &self.world.destroyed_at {
+            self.particles.burst(*position, EXPLOSION_COLOR);
+        }
+        if input.direction() != (0.0, 0.0) {
+            self.particles.trail(self.world.player_position(), TRAIL_COLOR, dt);
+        }
+        self.particles.update(dt);
+        if !hit {
+            return;
+        }
+        self.particles.burst(self.world.player_position(), HIT_COLOR);
+        self.renderer.hit();
+        if let Some(audio) = &self.audio {
+            audio.play_hit();
+        }
+        if self.world.is_over() {
+            self.state = GameState::GameOver;
+            if let Some(replay) = self.recording.take() {
+                save_replay(&replay);
+            }
+            // A played back run was already counted when it was recorded
+            self.new_rank = if self.playback.is_some() || self.tweaked {
+                None
+            } else {
+                self.high_scores.insert(self.world.score)
+            };
+            if self.new_rank.is_some() {
+                if let Err(e) = self.high_scores.save(HIGH_SCORES_PATH) {
+                    eprintln!("Failed to save {}: {}", HIGH_SCORES_PATH, e);
+                }
+            }
+        }
+    }
+
+    // Draws the frame. `alpha` is how far the time left in the accumulator is into the next
+    // step, entities are drawn that far from their previous position to their current one.
+    // The world goes through the post-processing pass, overlays and text are drawn on top of
+    // its result so they don't shake or get the CRT filter.
+    pub fn render(&mut self, window: &mut Window, frame_time: f32, alpha: f32) {
+        // A frozen world stays where it stopped instead of swinging back and forth
+        let alpha = if self.is_simulating() { alpha } else { 1.0 };
+        let positions = systems::interpolate_positions(&self.previous_positions, &self.world.positions, alpha);
+        let player = positions.get(self.world.player).map_or((0.0, 0.0), |position| (position.x, position.y));
+
+        self.renderer.begin_frame(frame_time);
+        self.camera.follow(player, frame_time);
+        self.renderer.set_camera(&self.camera);
+
+        // The title screen is text only, every other state shows the (possibly frozen) world
+        if self.state != GameState::Title {
+            systems::render(&self.world, &positions, &mut self.renderer);
+            if let Some(position) = self.net.as_ref().and_then(NetSession::remote_player) {
+                systems::render_remote_player(&self.world, position, &mut self.renderer);
+            }
+            self.renderer.draw_particles(self.particles.instances());
+            if self.debug_overlay.show_colliders {
+                systems::render_colliders(&self.world, &mut self.renderer);
+            }
+            if self.show_broad_phase {
+                for cell in self.spatial_hash.occupied_cells() {
+                    let bounds = self.spatial_hash.cell_bounds(cell);
+                    let center = ((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0);
+                    self.renderer.draw_rect_outline(
+                        center,
+                        (BROAD_PHASE_CELL_SIZE, BROAD_PHASE_CELL_SIZE),
+                        [1.0, 1.0, 0.0, 1.0],
+                    );
+                }
+            }
+        }
+        self.renderer.end_scene();
+
+        if self.show_broad_phase {
+            let title = format!(
+                "{} | cells: {} | pairs: {}",
+                WINDOW_TITLE,
+                self.spatial_hash.occupied_cells().count(),
+                self.spatial_hash.candidate_pairs().len()
+            );
+            window.set_title(&title).unwrap();
+        }
+
+        if self.state.is_dimmed() || self.controls_menu.is_open() {
+            self.renderer.draw_rect((0.0, 0.0), (2.0, 2.0), [0.0, 0.0, 0.0, DIM_OVERLAY_ALPHA]);
+        }
+
+        if let Some(hud) = &mut self.hud {
+            hud.frame();
+            if self.controls_menu.is_open() {
+                self.controls_menu.draw(hud.font(), &self.input_map, &mut self.renderer);
+            } else {
+                if self.state != GameState::Title {
+                    hud.score = self.world.score;
+                    hud.lives = self.world.lives;
+                    hud.difficulty = self.world.difficulty;
+                    hud.ammo = self.world.gun.ammo;
+                    hud.effects = self.world.effects.active();
+                    hud.draw(&mut self.renderer);
+                }
+                let lines = self.state.lines(
+                    self.world.score,
+                    &self.levels[self.level_index].name,
+                    &self.input_map,
+                    &self.high_scores,
+                    self.new_rank,
+                );
+                self.state_screen.draw(hud.font(), lines, &mut self.renderer);
+            }
+        }
+
+        // The broad phase grid can also be switched off from the overlay
+        let showed_broad_phase = self.show_broad_phase;
+        let tweaked = self.debug_overlay.draw(
+            window,
+            frame_time,
+            &mut self.world,
+            self.particles.len(),
+            &mut self.show_broad_phase,
+            &mut self.renderer,
+        );
+        if showed_broad_phase && !self.show_broad_phase {
+            window.set_title(WINDOW_TITLE).unwrap();
+        }
+        // The replay would play back without the tweak, so it isn't kept
+        if tweaked {
+            self.tweaked = true;
+            self.recording = None;
+        }
+    }
+
+    // Quitting in the middle of a run still keeps it
+    pub fn quit(self) {
+        if let Some(replay) = self.recording.filter(|replay| replay.len() > 0) {
+            save_replay(&replay);
+        }
+    }
+}
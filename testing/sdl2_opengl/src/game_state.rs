@@ -0,0 +1,132 @@
+use crate::highscores::HighScores;
+use crate::input::{Action, InputMap};
+use crate::renderer::Renderer;
+use crate::text;
+use crate::texture::Texture;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::ttf::Font;
+
+const LINE_SPACING: f32 = 8.0;
+
+// Only Playing advances the world, the other states keep it frozen (or, for the title
+// screen, don't show one at all)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+impl GameState {
+    // State to switch to for a key press, if any. Entering Playing from Title or GameOver
+    // starts a new run, coming back from Paused resumes the current one. Escape always
+    // toggles the pause during a run, so it only quits from the title and game over screens.
+    pub fn next(self, key: Keycode, action: Option<Action>) -> Option<GameState> {
+        let confirm = key == Keycode::Return || key == Keycode::KpEnter;
+        let pause = key == Keycode::Escape || action == Some(Action::Pause);
+        match self {
+            GameState::Title | GameState::GameOver if confirm => Some(GameState::Playing),
+            GameState::Playing if pause => Some(GameState::Paused),
+            GameState::Paused if confirm || pause => Some(GameState::Playing),
+            _ => None,
+        }
+    }
+
+    // Whether the world advances this frame, all states are still rendered
+    pub fn is_simulating(self) -> bool {
+        self == GameState::Playing
+    }
+
+    // Drawn with the dimmed overlay between the world and the text
+    pub fn is_dimmed(self) -> bool {
+        self == GameState::Paused
+    }
+
+    pub fn starts_new_run(self, next: GameState) -> bool {
+        next == GameState::Playing && matches!(self, GameState::Title | GameState::GameOver)
+    }
+
+    // Text shown over the scene, empty while playing. `new_rank` is the high score table
+    // position of the run that just ended, if it made the table.
+    pub fn lines(
+        self,
+        score: u32,
+        level: &str,
+        input_map: &InputMap,
+        high_scores: &HighScores,
+        new_rank: Option<usize>,
+    ) -> Vec<String> {
+        let pause_key = input_map.key(Action::Pause).name();
+        match self {
+            GameState::Title => vec![
+                "SDL2 + OpenGL in Rust".to_string(),
+                "Press Enter to start".to_string(),
+                format!("Level: {} ({} to change)", level, input_map.key(Action::NextLevel).name()),
+                format!("{} fire  {} / Escape pause  F1 controls", input_map.key(Action::Fire).name(), pause_key),
+            ],
+            GameState::Playing => Vec::new(),
+            GameState::Paused => vec![
+                "Paused".to_string(),
+                format!("Press {}, Escape or Enter to resume", pause_key),
+            ],
+            GameState::GameOver => {
+                let mut lines = vec!["Game over".to_string(), format!("Score: {}", score)];
+                if !high_scores.entries().is_empty() {
+                    lines.push("High scores".to_string());
+                }
+                for (rank, entry) in high_scores.entries().iter().enumerate() {
+                    let marker = if new_rank == Some(rank) { "  <- new" } else { "" };
+                    lines.push(format!("{:>2}. {:>6}  {}{}", rank + 1, entry.score, entry.date(), marker));
+                }
+                lines.push("Press Enter to play again".to_string());
+                lines
+            }
+        }
+    }
+}
+
+// Centered text for the title, pause and game over screens. Like the HUD the textures are only
+// rebuilt when the text changes.
+pub struct StateScreen {
+    text: Vec<String>,
+    lines: Vec<Texture>,
+}
+
+impl StateScreen {
+    pub fn new() -> StateScreen {
+        StateScreen {
+            text: Vec::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn draw(&mut self, font: &Font, content: Vec<String>, renderer: &mut Renderer) {
+        if content != self.text {
+            let rendered: Result<Vec<Texture>, String> = content
+                .iter()
+                .map(|line| text::render_text(font, line, Color::RGBA(255, 255, 255, 255)))
+                .collect();
+            match rendered {
+                Ok(lines) => self.lines = lines,
+                Err(e) => {
+                    eprintln!("Failed to render screen text: {}", e);
+                    return;
+                }
+            }
+            self.text = content;
+        }
+
+        let window_size = renderer.window_size();
+        let total_height: f32 = self.lines.iter().map(|line| line.height() as f32 + LINE_SPACING).sum();
+        let mut y = (window_size.1 as f32 - total_height) / 2.0;
+        for (i, line) in self.lines.iter().enumerate() {
+            let x = (window_size.0 as f32 - line.width() as f32) / 2.0;
+            // The first line is the heading
+            let tint = if i == 0 { [1.0, 0.9, 0.2, 1.0] } else { [1.0, 1.0, 1.0, 1.0] };
+            renderer.draw_texture_at_pixel(line, (x, y), tint);
+            y += line.height() as f32 + LINE_SPACING;
+        }
+    }
+}
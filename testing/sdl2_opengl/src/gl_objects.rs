@@ -0,0 +1,167 @@
+use gl::types::*;
+
+// Owning handles for GL objects, each deletes its object on drop. Like Texture they need a
+// current GL context for their whole lifetime, so they must be dropped before the context.
+
+// A linked shader program
+pub struct ShaderProgram {
+    id: GLuint,
+}
+
+impl ShaderProgram {
+    // Takes ownership of an existing program object
+    pub fn from_raw(id: GLuint) -> ShaderProgram {
+        ShaderProgram { id }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+}
+
+impl Drop for ShaderProgram {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+    }
+}
+
+pub struct Vao {
+    id: GLuint,
+}
+
+impl Vao {
+    pub fn new() -> Vao {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut id);
+        }
+        Vao { id }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindVertexArray(self.id);
+        }
+    }
+
+    pub fn unbind() {
+        unsafe {
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+impl Drop for Vao {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.id);
+        }
+    }
+}
+
+// A buffer object holding vertices or indices depending on `target`. Static unless created
+// with `dynamic`.
+pub struct Vbo {
+    id: GLuint,
+}
+
+impl Vbo {
+    // Leaves the buffer bound to `target`
+    pub fn new<T>(target: GLenum, data: &[T]) -> Vbo {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+            gl::BindBuffer(target, id);
+            gl::BufferData(
+                target,
+                std::mem::size_of_val(data) as GLsizeiptr,
+                data.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+        }
+        Vbo { id }
+    }
+
+    // `size` bytes of storage for data rewritten every frame with `update`, left bound too
+    pub fn dynamic(target: GLenum, size: usize) -> Vbo {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+            gl::BindBuffer(target, id);
+            gl::BufferData(target, size as GLsizeiptr, std::ptr::null(), gl::DYNAMIC_DRAW);
+        }
+        Vbo { id }
+    }
+
+    pub fn bind(&self, target: GLenum) {
+        unsafe {
+            gl::BindBuffer(target, self.id);
+        }
+    }
+
+    // Replaces the contents and size of a dynamic buffer
+    pub fn upload<T>(&self, target: GLenum, data: &[T]) {
+        unsafe {
+            gl::BindBuffer(target, self.id);
+            gl::BufferData(
+                target,
+                std::mem::size_of_val(data) as GLsizeiptr,
+                data.as_ptr() as *const _,
+                gl::DYNAMIC_DRAW,
+            );
+        }
+    }
+
+    // Overwrites the start of the buffer, `data` must fit in it
+    pub fn update<T>(&self, target: GLenum, data: &[T]) {
+        unsafe {
+            gl::BindBuffer(target, self.id);
+            gl::BufferSubData(target, 0, std::mem::size_of_val(data) as GLsizeiptr, data.as_ptr() as *const _);
+        }
+    }
+}
+
+impl Drop for Vbo {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
+
+pub struct Framebuffer {
+    id: GLuint,
+}
+
+impl Framebuffer {
+    pub fn new() -> Framebuffer {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut id);
+        }
+        Framebuffer { id }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        }
+    }
+
+    // Back to drawing to the window
+    pub fn unbind() {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
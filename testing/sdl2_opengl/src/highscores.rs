@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const HIGH_SCORES_PATH: &str = "highscores.json";
+const MAX_ENTRIES: usize = 10;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// <path>.corrupt-<timestamp>, with a counter on top if that is taken, so earlier backups are
+// never overwritten
+fn backup_path(path: &Path, timestamp: u64) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    (0..)
+        .map(|n| match n {
+            0 => path.with_file_name(format!("{}.corrupt-{}", name, timestamp)),
+            n => path.with_file_name(format!("{}.corrupt-{}-{}", name, timestamp, n)),
+        })
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    // Seconds since the Unix epoch, UTC
+    pub timestamp: u64,
+}
+
+impl HighScore {
+    // YYYY-MM-DD, computed by hand to avoid pulling in a date crate
+    pub fn date(&self) -> String {
+        // Days since 1970-01-01 to a civil date, see Howard Hinnant's civil_from_days
+        let days = (self.timestamp / 86_400) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+// Best scores first, at most MAX_ENTRIES of them
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    // A missing file is an empty table. A corrupt one is renamed out of the way, so the next
+    // save doesn't overwrite it, and the table starts over. Every corrupt file gets its own
+    // timestamped backup.
+    pub fn load<P: AsRef<Path>>(path: P) -> HighScores {
+        let path = path.as_ref();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return HighScores::default(),
+        };
+
+        match serde_json::from_str::<HighScores>(&contents) {
+            Ok(mut high_scores) => {
+                high_scores.entries.sort_by_key(|entry| Reverse(entry.score));
+                high_scores.entries.truncate(MAX_ENTRIES);
+                high_scores
+            }
+            Err(e) => {
+                let backup = backup_path(path, now());
+                eprintln!("High score file {} is invalid ({}), moving it to {}", path.display(), e, backup.display());
+                if let Err(e) = fs::rename(path, &backup) {
+                    eprintln!("Failed to move {}: {}", path.display(), e);
+                }
+                HighScores::default()
+            }
+        }
+    }
+
+    // Written to a temporary file first so a crash mid-write can't lose the table
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, contents).map_err(|e| e.to_string())?;
+        fs::rename(&temp_path, path).map_err(|e| e.to_string())
+    }
+
+    // Adds the score if it makes the table and returns its rank, 0 being the best. Ties go
+    // below the existing entries.
+    pub fn insert(&mut self, score: u32) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+        let rank = self.entries.iter().position(|entry| score > entry.score).unwrap_or(self.entries.len());
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+
+        self.entries.insert(rank, HighScore { score, timestamp: now() });
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory per test, removed again by the test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sdl2_opengl_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn scores(high_scores: &HighScores) -> Vec<u32> {
+        high_scores.entries().iter().map(|entry| entry.score).collect()
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_table() {
+        let dir = temp_dir("highscores_missing");
+        let high_scores = HighScores::load(dir.join("highscores.json"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(high_scores.entries().is_empty());
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = temp_dir("highscores_round_trip");
+        let path = dir.join("highscores.json");
+        let mut high_scores = HighScores::default();
+        for score in [5, 30, 12] {
+            high_scores.insert(score);
+        }
+        high_scores.save(&path).unwrap();
+        let loaded = HighScores::load(&path);
+        let leftover_temp = dir.join("highscores.tmp").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(scores(&loaded), vec![30, 12, 5]);
+        assert!(!leftover_temp);
+    }
+
+    #[test]
+    fn corrupt_files_are_moved_to_separate_backups() {
+        let dir = temp_dir("highscores_corrupt");
+        let path = dir.join("highscores.json");
+        fs::write(&path, "{ not json").unwrap();
+        let first = HighScores::load(&path);
+        fs::write(&path, "[1, 2, 3]").unwrap();
+        let second = HighScores::load(&path);
+
+        let mut backups: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        backups.sort();
+        let original_left = path.exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(first.entries().is_empty());
+        assert!(second.entries().is_empty());
+        assert!(!original_left);
+        assert_eq!(backups, vec!["[1, 2, 3]", "{ not json"]);
+    }
+
+    #[test]
+    fn backup_path_skips_taken_names() {
+        let dir = temp_dir("highscores_backup_path");
+        let path = dir.join("highscores.json");
+        let first = backup_path(&path, 1_700_000_000);
+        fs::write(&first, "").unwrap();
+        let second = backup_path(&path, 1_700_000_000);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first, dir.join("highscores.json.corrupt-1700000000"));
+        assert_eq!(second, dir.join("highscores.json.corrupt-1700000000-1"));
+    }
+
+    #[test]
+    fn insert_returns_the_rank_and_puts_ties_below() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.insert(0), None);
+        assert_eq!(high_scores.insert(10), Some(0));
+        assert_eq!(high_scores.insert(20), Some(0));
+        assert_eq!(high_scores.insert(10), Some(2));
+        assert_eq!(high_scores.insert(15), Some(1));
+        assert_eq!(high_scores.insert(20), Some(1));
+        assert_eq!(scores(&high_scores), vec![20, 20, 15, 10, 10]);
+    }
+
+    #[test]
+    fn insert_into_a_full_table() {
+        let mut high_scores = HighScores::default();
+        for score in (1..=MAX_ENTRIES as u32).map(|n| n * 10) {
+            high_scores.insert(score);
+        }
+        // Not better than the last entry, a tie goes below it and drops off
+        assert_eq!(high_scores.insert(5), None);
+        assert_eq!(high_scores.insert(10), None);
+        assert_eq!(high_scores.insert(11), Some(MAX_ENTRIES - 1));
+        assert_eq!(high_scores.entries().last().unwrap().score, 11);
+        // The lowest entry drops off
+        assert_eq!(high_scores.insert(1_000), Some(0));
+        assert_eq!(high_scores.entries().len(), MAX_ENTRIES);
+        assert_eq!(high_scores.entries().last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn date_from_timestamp() {
+        let date = |timestamp| HighScore { score: 1, timestamp }.date();
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(86_399), "1970-01-01");
+        assert_eq!(date(86_400), "1970-01-02");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(1_000_000_000), "2001-09-09");
+        assert_eq!(date(1_709_164_800), "2024-02-29");
+        assert_eq!(date(1_735_689_599), "2024-12-31");
+        assert_eq!(date(4_107_542_400), "2100-03-01");
+    }
+}
@@ -0,0 +1,97 @@
+use crate::powerups::PowerUpKind;
+use crate::projectiles::MAX_AMMO;
+use crate::renderer::Renderer;
+use crate::text;
+use crate::texture::Texture;
+use sdl2::pixels::Color;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use std::time::{Duration, Instant};
+
+// Distance of the text from the top left corner of the window, in pixels
+const HUD_MARGIN: f32 = 10.0;
+
+// On-screen text overlay. The text is rendered with SDL2_ttf into a texture which is only
+// rebuilt when the text actually changes.
+pub struct Hud<'ttf> {
+    font: Font<'ttf, 'static>,
+    text: String,
+    texture: Option<Texture>,
+    pub score: u32,
+    pub lives: u32,
+    // Obstacle speed multiplier
+    pub difficulty: f32,
+    pub ammo: u32,
+    // Active power-up effects and their remaining seconds
+    pub effects: Vec<(PowerUpKind, f32)>,
+    fps: u32,
+    frames: u32,
+    fps_timer: Instant,
+}
+
+impl<'ttf> Hud<'ttf> {
+    pub fn new(ttf: &'ttf Sdl2TtfContext, font_path: &str, point_size: u16) -> Result<Hud<'ttf>, String> {
+        let font = ttf.load_font(font_path, point_size)?;
+        Ok(Hud {
+            font,
+            text: String::new(),
+            texture: None,
+            score: 0,
+            lives: 0,
+            difficulty: 1.0,
+            ammo: 0,
+            effects: Vec::new(),
+            fps: 0,
+            frames: 0,
+            fps_timer: Instant::now(),
+        })
+    }
+
+    // The controls menu renders with the same font
+    pub fn font(&self) -> &Font<'ttf, 'static> {
+        &self.font
+    }
+
+    // Call once per frame, FPS is averaged over the last second
+    pub fn frame(&mut self) {
+        self.frames += 1;
+        let elapsed = self.fps_timer.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.fps = (self.frames as f32 / elapsed.as_secs_f32()).round() as u32;
+            self.frames = 0;
+            self.fps_timer = Instant::now();
+        }
+    }
+
+    fn update_text(&mut self) -> Result<(), String> {
+        // Ammo is shown as one bar per round left
+        let ammo: String = (0..MAX_AMMO).map(|i| if i < self.ammo { '|' } else { '.' }).collect();
+        // Whole seconds so the texture is only rebuilt once a second
+        let effects: String = self
+            .effects
+            .iter()
+            .map(|(kind, remaining)| format!("  {} {}s", kind.label(), remaining.ceil()))
+            .collect();
+        let text = format!(
+            "Score: {}  Lives: {}  Ammo: {}  Speed: x{:.1}  FPS: {}{}",
+            self.score, self.lives, ammo, self.difficulty, self.fps, effects
+        );
+        if text == self.text && self.texture.is_some() {
+            return Ok(());
+        }
+
+        self.texture = Some(text::render_text(&self.font, &text, Color::RGBA(255, 255, 255, 255))?);
+        self.text = text;
+        Ok(())
+    }
+
+    // Drawn after everything else so it stays on top
+    pub fn draw(&mut self, renderer: &mut Renderer) {
+        if let Err(e) = self.update_text() {
+            eprintln!("Failed to render HUD text: {}", e);
+            return;
+        }
+        if let Some(texture) = &self.texture {
+            renderer.draw_texture_at_pixel(texture, (HUD_MARGIN, HUD_MARGIN), [1.0, 1.0, 1.0, 1.0]);
+        }
+    }
+}
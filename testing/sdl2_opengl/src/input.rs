@@ -0,0 +1,223 @@
+use sdl2::keyboard::{KeyboardState, Keycode, Scancode};
+use std::collections::BTreeMap;
+
+// Gameplay input for one simulated frame, what replays record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameInput {
+    // -1, 0 or 1 on each axis, positive is right and up
+    pub x: i8,
+    pub y: i8,
+    pub firing: bool,
+}
+
+impl FrameInput {
+    // Unit length, or zero when not moving
+    pub fn direction(&self) -> (f32, f32) {
+        let (x, y) = (self.x as f32, self.y as f32);
+        let length = (x * x + y * y).sqrt();
+        if length > 0.0 {
+            (x / length, y / length)
+        } else {
+            (0.0, 0.0)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    ToggleBroadPhase,
+    ToggleCrt,
+    ToggleFullscreen,
+    ToggleDebugOverlay,
+    VolumeUp,
+    VolumeDown,
+    ToggleMute,
+    Pause,
+    NextLevel,
+    QuickSave,
+    QuickLoad,
+    Quit,
+}
+
+impl Action {
+    // Also the order the controls menu lists them in
+    pub const ALL: [Action; 17] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::ToggleBroadPhase,
+        Action::ToggleCrt,
+        Action::ToggleFullscreen,
+        Action::ToggleDebugOverlay,
+        Action::VolumeUp,
+        Action::VolumeDown,
+        Action::ToggleMute,
+        Action::Pause,
+        Action::NextLevel,
+        Action::QuickSave,
+        Action::QuickLoad,
+        Action::Quit,
+    ];
+
+    // Name used in the config file
+    pub fn id(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Fire => "fire",
+            Action::ToggleBroadPhase => "toggle_broad_phase",
+            Action::ToggleCrt => "toggle_crt",
+            Action::ToggleFullscreen => "toggle_fullscreen",
+            Action::ToggleDebugOverlay => "toggle_debug_overlay",
+            Action::VolumeUp => "volume_up",
+            Action::VolumeDown => "volume_down",
+            Action::ToggleMute => "toggle_mute",
+            Action::Pause => "pause",
+            Action::NextLevel => "next_level",
+            Action::QuickSave => "quick_save",
+            Action::QuickLoad => "quick_load",
+            Action::Quit => "quit",
+        }
+    }
+
+    // Name shown in the controls menu
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Fire => "Fire",
+            Action::ToggleBroadPhase => "Broad phase overlay",
+            Action::ToggleCrt => "CRT filter",
+            Action::ToggleFullscreen => "Fullscreen",
+            Action::ToggleDebugOverlay => "Debug overlay",
+            Action::VolumeUp => "Volume up",
+            Action::VolumeDown => "Volume down",
+            Action::ToggleMute => "Mute",
+            Action::Pause => "Pause",
+            Action::NextLevel => "Next level",
+            Action::QuickSave => "Save game",
+            Action::QuickLoad => "Load game",
+            Action::Quit => "Quit",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| action.id() == id)
+    }
+
+    pub fn default_key(self) -> Keycode {
+        match self {
+            Action::MoveUp => Keycode::W,
+            Action::MoveDown => Keycode::S,
+            Action::MoveLeft => Keycode::A,
+            Action::MoveRight => Keycode::D,
+            Action::Fire => Keycode::Space,
+            Action::ToggleBroadPhase => Keycode::F3,
+            Action::ToggleCrt => Keycode::F4,
+            Action::ToggleFullscreen => Keycode::F11,
+            Action::ToggleDebugOverlay => Keycode::F2,
+            Action::VolumeUp => Keycode::Equals,
+            Action::VolumeDown => Keycode::Minus,
+            Action::ToggleMute => Keycode::M,
+            Action::Pause => Keycode::P,
+            Action::NextLevel => Keycode::L,
+            Action::QuickSave => Keycode::F5,
+            Action::QuickLoad => Keycode::F9,
+            Action::Quit => Keycode::Escape,
+        }
+    }
+}
+
+// One key per action. Several actions may end up on the same key, those are reported as
+// conflicts and the first action in Action::ALL wins.
+#[derive(Debug, Clone)]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Keycode>,
+}
+
+impl Default for InputMap {
+    fn default() -> InputMap {
+        InputMap {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| (*action, action.default_key()))
+                .collect(),
+        }
+    }
+}
+
+impl InputMap {
+    // Builds the map from the config file's [bindings] table. Actions missing from it keep
+    // their default key, bad entries are skipped with a warning.
+    pub fn from_bindings(bindings: &BTreeMap<String, String>, source: &str) -> InputMap {
+        let mut input_map = InputMap::default();
+        for (id, key_name) in bindings {
+            match (Action::from_id(id), Keycode::from_name(key_name)) {
+                (Some(action), Some(key)) => input_map.bind(action, key),
+                (None, _) => eprintln!("Ignoring unknown action '{}' in {}", id, source),
+                (_, None) => eprintln!("Ignoring unknown key '{}' for {} in {}", key_name, id, source),
+            }
+        }
+        input_map
+    }
+
+    pub fn to_bindings(&self) -> BTreeMap<String, String> {
+        self.bindings
+            .iter()
+            .map(|(action, key)| (action.id().to_string(), key.name()))
+            .collect()
+    }
+
+    pub fn key(&self, action: Action) -> Keycode {
+        self.bindings[&action]
+    }
+
+    pub fn bind(&mut self, action: Action, key: Keycode) {
+        self.bindings.insert(action, key);
+    }
+
+    pub fn reset(&mut self, action: Action) {
+        self.bind(action, action.default_key());
+    }
+
+    pub fn action_for(&self, key: Keycode) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| self.bindings[action] == key)
+    }
+
+    pub fn is_held(&self, keyboard: &KeyboardState, action: Action) -> bool {
+        Scancode::from_keycode(self.key(action)).is_some_and(|scancode| keyboard.is_scancode_pressed(scancode))
+    }
+
+    // Unit length direction from the held movement keys, so diagonals aren't faster
+    pub fn frame_input(&self, keyboard: &KeyboardState) -> FrameInput {
+        let axis = |negative, positive| self.is_held(keyboard, positive) as i8 - self.is_held(keyboard, negative) as i8;
+        FrameInput {
+            x: axis(Action::MoveLeft, Action::MoveRight),
+            y: axis(Action::MoveDown, Action::MoveUp),
+            firing: self.is_held(keyboard, Action::Fire),
+        }
+    }
+
+    // True if another action shares this action's key
+    pub fn is_conflicting(&self, action: Action) -> bool {
+        let key = self.key(action);
+        self.bindings
+            .iter()
+            .any(|(other, other_key)| *other != action && *other_key == key)
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        Action::ALL.iter().any(|action| self.is_conflicting(*action))
+    }
+}
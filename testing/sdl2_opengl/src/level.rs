@@ -0,0 +1,101 @@
+use crate::platforms::{MovingPlatform, LEVEL_TILES};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// Every .toml file in here is a level, played in file name order
+pub const LEVELS_DIR: &str = "assets/levels";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlatformDef {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+    pub half_size: (f32, f32),
+    // Fraction of the path covered per second
+    pub speed: f32,
+}
+
+// Level layout in world units. `tiles` uses the TileMap characters, the first row is the top of
+// the screen. Obstacles start at, and later spawn from, the spawn points, anywhere if there
+// are none.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Level {
+    pub name: String,
+    #[serde(default)]
+    pub player_start: (f32, f32),
+    #[serde(default)]
+    pub tiles: Vec<String>,
+    #[serde(default)]
+    pub obstacle_spawns: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub platforms: Vec<PlatformDef>,
+}
+
+impl Level {
+    // Used when no level file could be loaded, the layout the game had before levels existed
+    pub fn builtin() -> Level {
+        Level {
+            name: "Built-in".to_string(),
+            player_start: (0.0, 0.0),
+            tiles: LEVEL_TILES.iter().map(|row| row.to_string()).collect(),
+            obstacle_spawns: Vec::new(),
+            platforms: vec![
+                PlatformDef {
+                    start: (-0.7, -0.4),
+                    end: (0.2, -0.4),
+                    half_size: (0.15, 0.08),
+                    speed: 0.3,
+                },
+                PlatformDef {
+                    start: (0.6, -0.7),
+                    end: (0.6, 0.1),
+                    half_size: (0.08, 0.15),
+                    speed: 0.24,
+                },
+            ],
+        }
+    }
+
+    pub fn platforms(&self) -> Vec<MovingPlatform> {
+        self.platforms
+            .iter()
+            .map(|def| MovingPlatform::new(def.start, def.end, def.half_size.0, def.half_size.1, def.speed))
+            .collect()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Level, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        toml::from_str(&contents).map_err(|e| e.to_string())
+    }
+}
+
+// Invalid files are skipped with a warning. Never empty, falls back to the built-in level.
+pub fn load_levels<P: AsRef<Path>>(dir: P) -> Vec<Level> {
+    let dir = dir.as_ref();
+    let mut paths: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to read levels from {}: {}", dir.display(), e);
+            Vec::new()
+        }
+    };
+    paths.sort();
+
+    let mut levels: Vec<Level> = paths
+        .iter()
+        .filter_map(|path| match Level::load(path) {
+            Ok(level) => Some(level),
+            Err(e) => {
+                eprintln!("Skipping level {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect();
+    if levels.is_empty() {
+        levels.push(Level::builtin());
+    }
+    levels
+}
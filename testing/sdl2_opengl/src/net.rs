@@ -0,0 +1,408 @@
+use crate::components::Position;
+use crate::obstacles::{Behavior, MAX_OBSTACLES};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+
+// Port `--join` connects to if the address has none
+pub const DEFAULT_PORT: u16 = 7777;
+
+// States sent per second by each side
+const TICK_RATE: f32 = 20.0;
+// How often a joining instance asks again while it hasn't heard back
+const JOIN_INTERVAL: f32 = 1.0;
+// Without a message for this long the peer is considered gone
+const TIMEOUT: f32 = 5.0;
+// Large enough for a state with MAX_OBSTACLES obstacles
+const MAX_MESSAGE_SIZE: usize = 8192;
+
+// One side's view of the arena, sent every tick
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerState {
+    // Counts up with every state sent, older states arriving late are dropped
+    tick: u32,
+    pub player: Position,
+    // Only filled in by the host, the joining side shows the host's obstacles
+    pub obstacles: Vec<(Position, Behavior)>,
+}
+
+// JSON encoded, one message per datagram
+#[derive(Debug, Serialize, Deserialize)]
+enum Message {
+    // Sent by the joining side until it is welcomed
+    Join,
+    // The run both sides start, sent by the host in answer to Join
+    Welcome { seed: u64, level_index: usize },
+    State(PeerState),
+}
+
+pub enum NetEvent {
+    // Host only: someone joined and should be sent `welcome` with the run to play
+    PeerJoined(SocketAddr),
+    // Joining side only: the host's run to start
+    Welcomed { seed: u64, level_index: usize },
+    PeerLost,
+}
+
+fn lerp(a: Position, b: Position, t: f32) -> Position {
+    Position {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+    }
+}
+
+// Two player session over UDP. The host runs the arena, the joining side mirrors its obstacles,
+// and both send their player's position TICK_RATE times per second. Remote states are shown one
+// tick late, interpolated between the last two received, so they move smoothly between
+// packets. Nothing is resent, a lost state is simply replaced by the next one.
+pub struct NetSession {
+    socket: UdpSocket,
+    hosting: bool,
+    // Who states are sent to. Fixed for the joining side, the host learns it from Join.
+    peer: Option<SocketAddr>,
+    // Set once the run has been agreed on, no states are sent before
+    connected: bool,
+    // Seed and level index of the run the peer was welcomed to, or the host welcomed us to
+    run: Option<(u64, usize)>,
+    tick: u32,
+    // Seconds until the next state or Join is sent
+    send_timer: f32,
+    // Seconds since the session started, and when the peer was last heard from
+    clock: f32,
+    last_heard: f32,
+    // The last two remote states with their arrival times, oldest first
+    previous: Option<(f32, PeerState)>,
+    latest: Option<(f32, PeerState)>,
+}
+
+impl NetSession {
+    fn new(socket: UdpSocket, hosting: bool, peer: Option<SocketAddr>) -> Result<NetSession, String> {
+        socket.set_nonblocking(true).map_err(|e| e.to_string())?;
+        Ok(NetSession {
+            socket,
+            hosting,
+            peer,
+            connected: false,
+            run: None,
+            tick: 0,
+            send_timer: 0.0,
+            clock: 0.0,
+            last_heard: 0.0,
+            previous: None,
+            latest: None,
+        })
+    }
+
+    // Waits for someone to join on `port`
+    pub fn host(port: u16) -> Result<NetSession, String> {
+        let socket = UdpSocket::bind(("0.0.0.0", port)).map_err(|e| e.to_string())?;
+        NetSession::new(socket, true, None)
+    }
+
+    // Joins the host at `address`, as host:port or just the host with the default port
+    pub fn join(address: &str) -> Result<NetSession, String> {
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, DEFAULT_PORT)
+        };
+        let peer = std::net::ToSocketAddrs::to_socket_addrs(&address)
+            .map_err(|e| e.to_string())?
+            .next()
+            .ok_or_else(|| format!("{} did not resolve to an address", address))?;
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(|e| e.to_string())?;
+        NetSession::new(socket, false, Some(peer))
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.hosting
+    }
+
+    fn send(&self, message: &Message) {
+        let Some(peer) = self.peer else {
+            return;
+        };
+        match serde_json::to_vec(message) {
+            Ok(bytes) => {
+                if let Err(e) = self.socket.send_to(&bytes, peer) {
+                    eprintln!("Failed to send to {}: {}", peer, e);
+                }
+            }
+            Err(e) => eprintln!("Failed to encode a network message: {}", e),
+        }
+    }
+
+    // Host only: starts sending states to the peer that joined, playing the given run
+    pub fn welcome(&mut self, seed: u64, level_index: usize) {
+        self.send(&Message::Welcome { seed, level_index });
+        self.run = Some((seed, level_index));
+        self.connected = true;
+    }
+
+    // Host only: has the peer switch to a run the host just started. Ignored before anyone
+    // joined and on the joining side, which plays on in the host's arena either way.
+    pub fn run_started(&mut self, seed: u64, level_index: usize) {
+        if self.hosting && self.connected {
+            self.welcome(seed, level_index);
+        }
+    }
+
+    // Receives everything that arrived and sends the local state when a tick is due. Call
+    // every frame with the local player's position and, on the host, the obstacles.
+    pub fn update(&mut self, dt: f32, player: Position, obstacles: &[(Position, Behavior)]) -> Vec<NetEvent> {
+        self.clock += dt;
+        let mut events = Vec::new();
+
+        let mut buffer = [0; MAX_MESSAGE_SIZE];
+        loop {
+            let (size, sender) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // Windows reports an unreachable peer as a failed receive, the timeout covers it
+                Err(_) => continue,
+            };
+            // Datagrams from anyone else are ignored, the host takes the first one that joins
+            if self.peer.is_some_and(|peer| peer != sender) {
+                continue;
+            }
+            let message = match serde_json::from_slice(&buffer[..size]) {
+                Ok(message) => message,
+                Err(e) => {
+                    eprintln!("Ignoring a malformed message from {}: {}", sender, e);
+                    continue;
+                }
+            };
+            self.last_heard = self.clock;
+            match message {
+                // Sent again until the welcome arrives, or in case it got lost
+                Message::Join if self.hosting => match self.run.filter(|_| self.connected) {
+                    Some((seed, level_index)) => self.send(&Message::Welcome { seed, level_index }),
+                    None => {
+                        self.peer = Some(sender);
+                        events.push(NetEvent::PeerJoined(sender));
+                    }
+                },
+                Message::Welcome { seed, level_index } if !self.hosting && self.run != Some((seed, level_index)) => {
+                    self.connected = true;
+                    self.run = Some((seed, level_index));
+                    // Stop waiting out the join interval, states are due right away
+                    self.send_timer = 0.0;
+                    events.push(NetEvent::Welcomed { seed, level_index });
+                }
+                Message::State(mut state) if self.latest.as_ref().is_none_or(|(_, latest)| state.tick > latest.tick) => {
+                    // The obstacle shaders draw at most MAX_OBSTACLES, whatever the peer sends
+                    state.obstacles.truncate(MAX_OBSTACLES);
+                    self.previous = self.latest.take();
+                    self.latest = Some((self.clock, state));
+                }
+                _ => (),
+            }
+        }
+
+        if self.connected && self.clock - self.last_heard > TIMEOUT {
+            events.push(NetEvent::PeerLost);
+            self.connected = false;
+            self.previous = None;
+            self.latest = None;
+            self.run = None;
+            if self.hosting {
+                self.peer = None;
+            }
+        }
+
+        self.send_timer -= dt;
+        if self.send_timer <= 0.0 {
+            if self.connected {
+                self.send_timer += 1.0 / TICK_RATE;
+                self.tick += 1;
+                let obstacles = if self.hosting { obstacles.to_vec() } else { Vec::new() };
+                self.send(&Message::State(PeerState {
+                    tick: self.tick,
+                    player,
+                    obstacles,
+                }));
+            } else if !self.hosting {
+                self.send_timer = JOIN_INTERVAL;
+                self.last_heard = self.clock;
+                self.send(&Message::Join);
+            }
+        }
+        events
+    }
+
+    // How far from the previous remote state to the latest one to show, reaching the latest
+    // one tick after it arrived
+    fn interpolation(&self) -> f32 {
+        match (&self.previous, &self.latest) {
+            (Some((previous, _)), Some((latest, _))) if latest > previous => {
+                ((self.clock - latest) / (latest - previous)).clamp(0.0, 1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    // The remote player's interpolated position, once a state has arrived
+    pub fn remote_player(&self) -> Option<Position> {
+        let (_, latest) = self.latest.as_ref()?;
+        Some(match &self.previous {
+            Some((_, previous)) => lerp(previous.player, latest.player, self.interpolation()),
+            None => latest.player,
+        })
+    }
+
+    // The host's interpolated obstacles. Snapped to the latest state when obstacles were
+    // spawned or destroyed in between.
+    pub fn remote_obstacles(&self) -> Option<Vec<(Position, Behavior)>> {
+        let (_, latest) = self.latest.as_ref()?;
+        let t = self.interpolation();
+        Some(match &self.previous {
+            Some((_, previous)) if previous.obstacles.len() == latest.obstacles.len() => previous
+                .obstacles
+                .iter()
+                .zip(&latest.obstacles)
+                .map(|((a, _), (b, behavior))| (lerp(*a, *b, t), *behavior))
+                .collect(),
+            _ => latest.obstacles.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn position(x: f32, y: f32) -> Position {
+        Position { x, y }
+    }
+
+    fn local_address(session: &NetSession) -> String {
+        format!("127.0.0.1:{}", session.socket.local_addr().unwrap().port())
+    }
+
+    // Updates the session until it reports an event or `timeout` passes, loopback delivery
+    // isn't instant
+    fn wait_for_events(session: &mut NetSession, timeout: Duration) -> Vec<NetEvent> {
+        let start = Instant::now();
+        loop {
+            let events = session.update(0.0, position(0.0, 0.0), &[]);
+            if !events.is_empty() || start.elapsed() > timeout {
+                return events;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    // Updates the session until the latest remote state has the given tick
+    fn wait_for_tick(session: &mut NetSession, tick: u32) {
+        let start = Instant::now();
+        while session.latest.as_ref().is_none_or(|(_, state)| state.tick != tick) {
+            assert!(start.elapsed() < Duration::from_secs(2), "state {} never arrived", tick);
+            session.update(0.0, position(0.0, 0.0), &[]);
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn send_state(socket: &UdpSocket, to: &str, tick: u32, player: Position, obstacles: Vec<(Position, Behavior)>) {
+        let bytes = serde_json::to_vec(&Message::State(PeerState { tick, player, obstacles })).unwrap();
+        socket.send_to(&bytes, to).unwrap();
+    }
+
+    #[test]
+    fn join_and_welcome_handshake() {
+        let mut host = NetSession::host(0).unwrap();
+        let mut joiner = NetSession::join(&local_address(&host)).unwrap();
+        assert!(host.is_host() && !joiner.is_host());
+
+        // The joiner's first update sends Join
+        assert!(joiner.update(0.0, position(0.0, 0.0), &[]).is_empty());
+        match wait_for_events(&mut host, Duration::from_secs(2)).as_slice() {
+            [NetEvent::PeerJoined(_)] => (),
+            _ => panic!("the host didn't see the join"),
+        }
+
+        host.welcome(42, 1);
+        match wait_for_events(&mut joiner, Duration::from_secs(2)).as_slice() {
+            [NetEvent::Welcomed { seed: 42, level_index: 1 }] => (),
+            _ => panic!("the joiner wasn't welcomed"),
+        }
+
+        // A repeated Join is answered with the same run instead of a second PeerJoined, and
+        // the joiner doesn't restart it
+        joiner.send(&Message::Join);
+        assert!(wait_for_events(&mut host, Duration::from_millis(100)).is_empty());
+        assert!(wait_for_events(&mut joiner, Duration::from_millis(100)).is_empty());
+        assert_eq!(joiner.run, Some((42, 1)));
+
+        // Both sides now send states, the host's include its obstacles
+        let obstacles = [(position(0.5, 0.5), Behavior::Chase)];
+        host.update(1.0, position(1.0, 2.0), &obstacles);
+        wait_for_tick(&mut joiner, host.tick);
+        assert_eq!(joiner.remote_player(), Some(position(1.0, 2.0)));
+        assert_eq!(joiner.remote_obstacles(), Some(obstacles.to_vec()));
+    }
+
+    #[test]
+    fn stale_states_are_dropped() {
+        let mut host = NetSession::host(0).unwrap();
+        let address = local_address(&host);
+        let peer = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+
+        send_state(&peer, &address, 5, position(5.0, 0.0), Vec::new());
+        wait_for_tick(&mut host, 5);
+        // Arrives late, after a newer state
+        send_state(&peer, &address, 3, position(3.0, 0.0), Vec::new());
+        send_state(&peer, &address, 6, position(6.0, 0.0), Vec::new());
+        wait_for_tick(&mut host, 6);
+
+        let (_, previous) = host.previous.as_ref().unwrap();
+        assert_eq!(previous.tick, 5);
+    }
+
+    #[test]
+    fn received_obstacles_are_capped() {
+        let mut joiner = NetSession::host(0).unwrap();
+        let address = local_address(&joiner);
+        let peer = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+
+        let obstacles = vec![(position(0.0, 0.0), Behavior::Wander); MAX_OBSTACLES + 8];
+        send_state(&peer, &address, 1, position(0.0, 0.0), obstacles);
+        wait_for_tick(&mut joiner, 1);
+        assert_eq!(joiner.remote_obstacles().unwrap().len(), MAX_OBSTACLES);
+    }
+
+    #[test]
+    fn interpolation_is_clamped() {
+        let mut session = NetSession::host(0).unwrap();
+        let state = |tick, x| PeerState {
+            tick,
+            player: position(x, 0.0),
+            obstacles: vec![(position(x, 1.0), Behavior::Wander)],
+        };
+        assert_eq!(session.remote_player(), None);
+
+        // A single state is shown as is
+        session.latest = Some((1.0, state(1, 0.0)));
+        session.clock = 1.0;
+        assert_eq!(session.interpolation(), 1.0);
+        assert_eq!(session.remote_player(), Some(position(0.0, 0.0)));
+
+        // States 0.5s apart take 0.5s to move between
+        session.previous = session.latest.take();
+        session.latest = Some((1.5, state(2, 10.0)));
+        for (clock, expected) in [(1.5, 0.0), (1.75, 0.5), (2.0, 1.0), (5.0, 1.0)] {
+            session.clock = clock;
+            assert_eq!(session.interpolation(), expected);
+            assert_eq!(session.remote_player(), Some(position(10.0 * expected, 0.0)));
+            assert_eq!(session.remote_obstacles(), Some(vec![(position(10.0 * expected, 1.0), Behavior::Wander)]));
+        }
+        // Clock behind the latest arrival can't extrapolate backwards
+        session.clock = 1.0;
+        assert_eq!(session.interpolation(), 0.0);
+
+        // Arrivals at the same time have nothing to interpolate
+        session.latest = Some((1.0, state(3, 20.0)));
+        assert_eq!(session.interpolation(), 1.0);
+    }
+}
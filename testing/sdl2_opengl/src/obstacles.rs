@@ -0,0 +1,151 @@
+use crate::camera::ARENA_HALF_SIZE;
+use crate::components::{Position, Renderable, Velocity};
+use rand::rngs::SmallRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Upper bound for the spawner, also the size of the offsets array in the obstacle shaders
+pub const MAX_OBSTACLES: usize = 32;
+
+// Obstacle speed range in world units per second
+const MIN_SPEED: f32 = 0.1;
+const MAX_SPEED: f32 = 0.35;
+// New obstacles never appear closer than this to the player
+const SPAWN_CLEARANCE: f32 = 0.5;
+const SPAWN_ATTEMPTS: usize = 10;
+// Chasers turn towards the player at most this fast, in radians per second, so a sharp dodge
+// still shakes them off
+const CHASE_TURN_RATE: f32 = 1.5;
+// Share of spawned obstacles that chase instead of wandering
+const CHASER_CHANCE: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Behavior {
+    // Straight line, bouncing off the edges
+    Wander,
+    // Steers towards the player, keeping its speed
+    Chase,
+}
+
+impl Behavior {
+    // Chasers are drawn in orange so they can be told apart
+    pub fn renderable(self) -> Renderable {
+        match self {
+            Behavior::Wander => Renderable::Obstacle {
+                tint: [1.0, 1.0, 1.0, 1.0],
+                color: [1.0, 0.0, 0.0, 1.0],
+            },
+            Behavior::Chase => Renderable::Obstacle {
+                tint: [1.0, 0.55, 0.2, 1.0],
+                color: [1.0, 0.5, 0.0, 1.0],
+            },
+        }
+    }
+}
+
+// Triangle moving at a constant speed, bouncing off the arena edges and walls
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub behavior: Behavior,
+    // Set while within the near miss distance of the player
+    pub near_miss: bool,
+}
+
+impl Obstacle {
+    pub fn new(behavior: Behavior) -> Obstacle {
+        Obstacle { behavior, near_miss: false }
+    }
+}
+
+// Random direction, random speed in the obstacle speed range
+pub fn random_velocity(rng: &mut SmallRng) -> Velocity {
+    let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+    let speed = MIN_SPEED + rng.gen::<f32>() * (MAX_SPEED - MIN_SPEED);
+    Velocity {
+        x: angle.cos() * speed,
+        y: angle.sin() * speed,
+    }
+}
+
+// Rotates a chaser's velocity towards the target, by at most the chase turn rate
+pub fn steer_towards(position: &Position, velocity: &mut Velocity, target: (f32, f32), dt: f32) {
+    let max_turn = CHASE_TURN_RATE * dt;
+    let heading = velocity.y.atan2(velocity.x);
+    let desired = (target.1 - position.y).atan2(target.0 - position.x);
+    let mut turn = desired - heading;
+    // Shortest way around
+    if turn > std::f32::consts::PI {
+        turn -= std::f32::consts::TAU;
+    } else if turn < -std::f32::consts::PI {
+        turn += std::f32::consts::TAU;
+    }
+
+    let angle = heading + turn.clamp(-max_turn, max_turn);
+    let speed = (velocity.x * velocity.x + velocity.y * velocity.y).sqrt();
+    velocity.x = angle.cos() * speed;
+    velocity.y = angle.sin() * speed;
+}
+
+// Adds an obstacle every `interval` seconds until there are `max` of them
+#[derive(Serialize, Deserialize)]
+pub struct Spawner {
+    interval: f32,
+    timer: f32,
+    max: usize,
+    // Positions new obstacles appear at, anywhere if empty
+    spawn_points: Vec<(f32, f32)>,
+}
+
+impl Spawner {
+    pub fn new(interval: f32, max: usize, spawn_points: Vec<(f32, f32)>) -> Spawner {
+        Spawner {
+            interval,
+            timer: 0.0,
+            max: max.min(MAX_OBSTACLES),
+            spawn_points,
+        }
+    }
+
+    // Where and what to spawn, if it is time. `count` is the number of obstacles alive.
+    pub fn update(&mut self, dt: f32, count: usize, player: (f32, f32), rng: &mut SmallRng) -> Option<(Position, Behavior)> {
+        if count >= self.max {
+            self.timer = 0.0;
+            return None;
+        }
+
+        self.timer += dt;
+        if self.timer < self.interval {
+            return None;
+        }
+        self.timer -= self.interval;
+        Some(self.spawn_away_from(player, rng))
+    }
+
+    fn random_position(&self, rng: &mut SmallRng) -> (f32, f32) {
+        if self.spawn_points.is_empty() {
+            let range = 0.9 * ARENA_HALF_SIZE;
+            (rng.gen::<f32>() * 2.0 * range - range, rng.gen::<f32>() * 2.0 * range - range)
+        } else {
+            self.spawn_points[rng.gen_range(0..self.spawn_points.len())]
+        }
+    }
+
+    // Random position, retried a few times to keep clear of the player. Gives up on the
+    // clearance rather than skipping the spawn.
+    pub fn spawn_away_from(&self, player: (f32, f32), rng: &mut SmallRng) -> (Position, Behavior) {
+        let mut position = self.random_position(rng);
+        for _ in 0..SPAWN_ATTEMPTS {
+            let (dx, dy) = (position.0 - player.0, position.1 - player.1);
+            if (dx * dx + dy * dy).sqrt() >= SPAWN_CLEARANCE {
+                break;
+            }
+            position = self.random_position(rng);
+        }
+        let behavior = if rng.gen::<f32>() < CHASER_CHANCE {
+            Behavior::Chase
+        } else {
+            Behavior::Wander
+        };
+        (Position { x: position.0, y: position.1 }, behavior)
+    }
+}
@@ -0,0 +1,133 @@
+// Upper bound on live particles, also the size of the renderer's instance buffer. Emitting
+// while full drops the new particles.
+pub const MAX_PARTICLES: usize = 1024;
+// Floats per particle in the instance data: x, y, size, r, g, b, a
+pub const PARTICLE_FLOATS: usize = 7;
+
+pub const HIT_COLOR: [f32; 4] = [1.0, 0.25, 0.15, 1.0];
+pub const EXPLOSION_COLOR: [f32; 4] = [1.0, 0.75, 0.3, 1.0];
+pub const TRAIL_COLOR: [f32; 4] = [0.3, 1.0, 0.4, 0.5];
+
+// Particles flung out of a collision
+const BURST_COUNT: usize = 40;
+const BURST_MIN_SPEED: f32 = 0.2;
+const BURST_MAX_SPEED: f32 = 0.8;
+const BURST_LIFETIME: f32 = 0.6;
+const BURST_SIZE: f32 = 0.02;
+// Particles per second left behind the moving player
+const TRAIL_RATE: f32 = 60.0;
+const TRAIL_LIFETIME: f32 = 0.35;
+const TRAIL_SIZE: f32 = 0.025;
+const TRAIL_SPREAD: f32 = 0.04;
+// Fraction of its velocity a particle keeps per second
+const DRAG: f32 = 0.1;
+
+struct Particle {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    // Seconds since it was emitted, and how long it lives in total
+    age: f32,
+    lifetime: f32,
+    // Full width at emission, shrinks to nothing over its lifetime
+    size: f32,
+    color: [f32; 4],
+}
+
+// CPU side particles, purely visual and not part of the saved world. Both vectors are
+// allocated once, dead particles are swapped out of the live range instead of freed.
+pub struct Particles {
+    particles: Vec<Particle>,
+    // Instance data for the renderer, rebuilt every frame
+    instances: Vec<f32>,
+    // Fractional trail particles carried over to the next frame
+    trail_budget: f32,
+}
+
+impl Particles {
+    pub fn new() -> Particles {
+        Particles {
+            particles: Vec::with_capacity(MAX_PARTICLES),
+            instances: Vec::with_capacity(MAX_PARTICLES * PARTICLE_FLOATS),
+            trail_budget: 0.0,
+        }
+    }
+
+    fn emit(&mut self, particle: Particle) {
+        if self.particles.len() < MAX_PARTICLES {
+            self.particles.push(particle);
+        }
+    }
+
+    // Radial burst in `color`, fading out as the particles slow down
+    pub fn burst(&mut self, position: (f32, f32), color: [f32; 4]) {
+        for _ in 0..BURST_COUNT {
+            let angle = rand::random::<f32>() * std::f32::consts::TAU;
+            let speed = BURST_MIN_SPEED + rand::random::<f32>() * (BURST_MAX_SPEED - BURST_MIN_SPEED);
+            self.emit(Particle {
+                x: position.0,
+                y: position.1,
+                vx: angle.cos() * speed,
+                vy: angle.sin() * speed,
+                age: 0.0,
+                lifetime: BURST_LIFETIME * (0.5 + rand::random::<f32>() * 0.5),
+                size: BURST_SIZE,
+                color,
+            });
+        }
+    }
+
+    // Emits at the trail rate around `position`, call every frame the emitter moves
+    pub fn trail(&mut self, position: (f32, f32), color: [f32; 4], dt: f32) {
+        self.trail_budget += TRAIL_RATE * dt;
+        while self.trail_budget >= 1.0 {
+            self.trail_budget -= 1.0;
+            let jitter = || (rand::random::<f32>() - 0.5) * TRAIL_SPREAD;
+            self.emit(Particle {
+                x: position.0 + jitter(),
+                y: position.1 + jitter(),
+                vx: 0.0,
+                vy: 0.0,
+                age: 0.0,
+                lifetime: TRAIL_LIFETIME,
+                size: TRAIL_SIZE,
+                color,
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let drag = DRAG.powf(dt);
+        let mut i = 0;
+        while i < self.particles.len() {
+            let particle = &mut self.particles[i];
+            particle.age += dt;
+            if particle.age >= particle.lifetime {
+                self.particles.swap_remove(i);
+                continue;
+            }
+            particle.vx *= drag;
+            particle.vy *= drag;
+            particle.x += particle.vx * dt;
+            particle.y += particle.vy * dt;
+            i += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    // PARTICLE_FLOATS per live particle, size and alpha shrinking with age
+    pub fn instances(&mut self) -> &[f32] {
+        self.instances.clear();
+        for particle in &self.particles {
+            let remaining = 1.0 - particle.age / particle.lifetime;
+            let [r, g, b, a] = particle.color;
+            self.instances
+                .extend_from_slice(&[particle.x, particle.y, particle.size * remaining, r, g, b, a * remaining]);
+        }
+        &self.instances
+    }
+}
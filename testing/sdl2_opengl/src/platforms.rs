@@ -0,0 +1,211 @@
+use crate::camera::ARENA_HALF_SIZE;
+use crate::spatial_hash::Aabb;
+use serde::{Deserialize, Serialize};
+
+// Conveyor push in world units per second
+const CONVEYOR_SPEED: f32 = 0.24;
+
+// Map layout of the built-in level, first row is the top of the screen. '>' '<' '^' 'v' are
+// conveyors pushing in that direction, '#' is a wall, anything else is plain floor.
+pub const LEVEL_TILES: &[&str] = &[
+    "........",
+    ".>>>>...",
+    "........",
+    "......^.",
+    "......^.",
+    "........",
+    "...<<<<.",
+    "........",
+];
+
+// Kinematic platform going back and forth between two points. It is never pushed by
+// anything, whatever stands on it moves along with it.
+#[derive(Serialize, Deserialize)]
+pub struct MovingPlatform {
+    pub x: f32,
+    pub y: f32,
+    pub half_width: f32,
+    pub half_height: f32,
+    start: (f32, f32),
+    end: (f32, f32),
+    // Fraction of the path covered per second
+    speed: f32,
+    progress: f32,
+    forward: bool,
+    // Movement applied by the last update, handed over to riders
+    pub velocity: (f32, f32),
+}
+
+impl MovingPlatform {
+    pub fn new(start: (f32, f32), end: (f32, f32), half_width: f32, half_height: f32, speed: f32) -> MovingPlatform {
+        MovingPlatform {
+            x: start.0,
+            y: start.1,
+            half_width,
+            half_height,
+            start,
+            end,
+            speed,
+            progress: 0.0,
+            forward: true,
+            velocity: (0.0, 0.0),
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.forward {
+            self.progress += self.speed * dt;
+        } else {
+            self.progress -= self.speed * dt;
+        }
+        if self.progress >= 1.0 {
+            self.progress = 1.0;
+            self.forward = false;
+        } else if self.progress <= 0.0 {
+            self.progress = 0.0;
+            self.forward = true;
+        }
+
+        let new_x = self.start.0 + (self.end.0 - self.start.0) * self.progress;
+        let new_y = self.start.1 + (self.end.1 - self.start.1) * self.progress;
+        self.velocity = (new_x - self.x, new_y - self.y);
+        self.x = new_x;
+        self.y = new_y;
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_center(self.x, self.y, self.half_width, self.half_height)
+    }
+
+    // The game is top down, so "standing on" means the rider's center is over the platform
+    pub fn carries(&self, x: f32, y: f32) -> bool {
+        let bounds = self.bounds();
+        x >= bounds.min_x && x <= bounds.max_x && y >= bounds.min_y && y <= bounds.max_y
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Tile {
+    Floor,
+    Conveyor { dx: f32, dy: f32 },
+    // Blocks the player, projectiles and obstacles
+    Wall,
+}
+
+impl Tile {
+    fn from_char(c: char) -> Tile {
+        match c {
+            '>' => Tile::Conveyor { dx: CONVEYOR_SPEED, dy: 0.0 },
+            '<' => Tile::Conveyor { dx: -CONVEYOR_SPEED, dy: 0.0 },
+            '^' => Tile::Conveyor { dx: 0.0, dy: CONVEYOR_SPEED },
+            'v' => Tile::Conveyor { dx: 0.0, dy: -CONVEYOR_SPEED },
+            '#' => Tile::Wall,
+            _ => Tile::Floor,
+        }
+    }
+}
+
+// Grid of tiles covering the arena, anchored at its top left corner
+#[derive(Serialize, Deserialize)]
+pub struct TileMap {
+    tile_size: f32,
+    columns: usize,
+    rows: usize,
+    tiles: Vec<Tile>,
+}
+
+impl TileMap {
+    pub fn parse<S: AsRef<str>>(layout: &[S], tile_size: f32) -> TileMap {
+        let columns = layout.iter().map(|row| row.as_ref().chars().count()).max().unwrap_or(0);
+        let rows = layout.len();
+        let mut tiles = vec![Tile::Floor; columns * rows];
+        for (row, line) in layout.iter().enumerate() {
+            for (column, c) in line.as_ref().chars().enumerate() {
+                tiles[row * columns + column] = Tile::from_char(c);
+            }
+        }
+
+        TileMap {
+            tile_size,
+            columns,
+            rows,
+            tiles,
+        }
+    }
+
+    pub fn tile_at(&self, x: f32, y: f32) -> Tile {
+        let column = ((x + ARENA_HALF_SIZE) / self.tile_size).floor();
+        let row = ((ARENA_HALF_SIZE - y) / self.tile_size).floor();
+        if column < 0.0 || row < 0.0 || column as usize >= self.columns || row as usize >= self.rows {
+            return Tile::Floor;
+        }
+        self.tiles[row as usize * self.columns + column as usize]
+    }
+
+    // Push velocity, per second, for anything whose center is on a conveyor
+    pub fn conveyor_push(&self, x: f32, y: f32) -> (f32, f32) {
+        match self.tile_at(x, y) {
+            Tile::Conveyor { dx, dy } => (dx, dy),
+            Tile::Floor | Tile::Wall => (0.0, 0.0),
+        }
+    }
+
+    pub fn is_wall(&self, x: f32, y: f32) -> bool {
+        self.tile_at(x, y) == Tile::Wall
+    }
+
+    fn tile_bounds(&self, index: usize) -> Aabb {
+        let min_x = -ARENA_HALF_SIZE + (index % self.columns) as f32 * self.tile_size;
+        let max_y = ARENA_HALF_SIZE - (index / self.columns) as f32 * self.tile_size;
+        Aabb {
+            min_x,
+            min_y: max_y - self.tile_size,
+            max_x: min_x + self.tile_size,
+            max_y,
+        }
+    }
+
+    pub fn conveyors(&self) -> impl Iterator<Item = (Aabb, Tile)> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| matches!(tile, Tile::Conveyor { .. }))
+            .map(move |(i, tile)| (self.tile_bounds(i), *tile))
+    }
+
+    pub fn walls(&self) -> impl Iterator<Item = Aabb> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile == Tile::Wall)
+            .map(move |(i, _)| self.tile_bounds(i))
+    }
+
+    // Walls among the tiles `bounds` touches, so testing something against the walls doesn't
+    // get slower with the size of the map
+    pub fn walls_near(&self, bounds: &Aabb) -> impl Iterator<Item = Aabb> + '_ {
+        // Tile indices from `low` to `high` in tiles, clamped to the map
+        let range = |low: f32, high: f32, count: usize| {
+            let first = low.floor().max(0.0) as usize;
+            let last = (high.floor() + 1.0).clamp(0.0, count as f32) as usize;
+            first..last.max(first)
+        };
+        let columns = range(
+            (bounds.min_x + ARENA_HALF_SIZE) / self.tile_size,
+            (bounds.max_x + ARENA_HALF_SIZE) / self.tile_size,
+            self.columns,
+        );
+        let rows = range(
+            (ARENA_HALF_SIZE - bounds.max_y) / self.tile_size,
+            (ARENA_HALF_SIZE - bounds.min_y) / self.tile_size,
+            self.rows,
+        );
+        rows.flat_map(move |row| columns.clone().map(move |column| row * self.columns + column))
+            .filter(move |&index| self.tiles[index] == Tile::Wall)
+            .map(move |index| self.tile_bounds(index))
+    }
+
+    pub fn tile_size(&self) -> f32 {
+        self.tile_size
+    }
+}
@@ -0,0 +1,159 @@
+use crate::gl_objects::{Framebuffer, Vao, Vbo};
+use crate::texture::Texture;
+use gl::types::*;
+use std::ptr;
+
+// Seconds the screen shakes and flashes red after losing a life
+const SHAKE_TIME: f32 = 0.35;
+const FLASH_TIME: f32 = 0.25;
+// Largest shake offset in texture coordinates, scaled down as the shake wears off
+const SHAKE_STRENGTH: f32 = 0.02;
+// Opacity of the red flash right after a hit
+const FLASH_STRENGTH: f32 = 0.5;
+// Uniforms of the post program that `end` sets, looked up through the renderer's cache
+pub const UNIFORMS: [&str; 6] = ["scene", "shakeOffset", "flash", "crt", "resolution", "time"];
+
+// The scene is drawn into an offscreen color texture which is then drawn to the window as a
+// single fullscreen quad through the post program, which applies the shake, flash and CRT
+// effects. Needs a current GL context for its whole lifetime.
+pub struct PostProcess {
+    framebuffer: Framebuffer,
+    // Rebuilt at the new size when the window is resized
+    color_texture: Option<Texture>,
+    // Fullscreen quad, the VBO is only kept alive for the VAO
+    quad_vao: Vao,
+    _quad_vbo: Vbo,
+    shake_for: f32,
+    flash_for: f32,
+    pub crt: bool,
+}
+
+impl PostProcess {
+    // `size` is the drawable size of the window in pixels
+    pub fn new(size: (u32, u32), crt: bool) -> Result<PostProcess, String> {
+        // Fullscreen triangle strip, interleaved as x, y, u, v
+        let quad_vertices: [f32; 16] = [
+            -1.0, -1.0, 0.0, 0.0,
+            1.0, -1.0, 1.0, 0.0,
+            -1.0, 1.0, 0.0, 1.0,
+            1.0, 1.0, 1.0, 1.0,
+        ];
+
+        let quad_vao = Vao::new();
+        quad_vao.bind();
+        let quad_vbo = Vbo::new(gl::ARRAY_BUFFER, &quad_vertices);
+        unsafe {
+            let stride = 4 * std::mem::size_of::<GLfloat>() as GLsizei;
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride, (2 * std::mem::size_of::<GLfloat>()) as *const _);
+            gl::EnableVertexAttribArray(1);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+        Vao::unbind();
+
+        let mut post = PostProcess {
+            framebuffer: Framebuffer::new(),
+            color_texture: None,
+            quad_vao,
+            _quad_vbo: quad_vbo,
+            shake_for: 0.0,
+            flash_for: 0.0,
+            crt,
+        };
+        post.resize(size)?;
+        Ok(post)
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.color_texture
+            .as_ref()
+            .map_or((0, 0), |texture| (texture.width(), texture.height()))
+    }
+
+    // Replaces the color texture for a new window size, a no-op if the size didn't change
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), String> {
+        if size == self.size() {
+            return Ok(());
+        }
+        let texture = Texture::render_target(size.0, size.1);
+
+        self.framebuffer.bind();
+        let status = unsafe {
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture.id(), 0);
+            gl::CheckFramebufferStatus(gl::FRAMEBUFFER)
+        };
+        Framebuffer::unbind();
+        // The old texture is dropped only now that it's no longer attached
+        self.color_texture = Some(texture);
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            // Retried next frame
+            self.color_texture = None;
+            return Err(format!("framebuffer incomplete: 0x{:x}", status));
+        }
+        Ok(())
+    }
+
+    // Starts the shake and the red flash
+    pub fn hit(&mut self) {
+        self.shake_for = SHAKE_TIME;
+        self.flash_for = FLASH_TIME;
+    }
+
+    pub fn toggle_crt(&mut self) {
+        self.crt = !self.crt;
+    }
+
+    // Runs on real time, so the effects also wear off while the game is paused
+    pub fn update(&mut self, dt: f32) {
+        self.shake_for = (self.shake_for - dt).max(0.0);
+        self.flash_for = (self.flash_for - dt).max(0.0);
+    }
+
+    // Redirects drawing into an offscreen texture of `size` pixels until `end`, the caller sets
+    // the viewport. A failed resize returns false and keeps drawing straight to the window,
+    // without effects, rather than to an incomplete framebuffer.
+    pub fn begin(&mut self, size: (u32, u32)) -> bool {
+        if let Err(e) = self.resize(size) {
+            eprintln!("Post-processing disabled for this frame: {}", e);
+            return false;
+        }
+        self.framebuffer.bind();
+        true
+    }
+
+    // Draws the offscreen texture to the current viewport of the window through the post program,
+    // which the caller has already bound; `uniforms` are its locations in `UNIFORMS` order.
+    // Everything drawn afterwards goes to the window directly and isn't affected
+    pub fn end(&self, uniforms: [GLint; 6], time: f32) {
+        let shake = SHAKE_STRENGTH * self.shake_for / SHAKE_TIME;
+        let shake_offset = if shake > 0.0 {
+            ((rand::random::<f32>() * 2.0 - 1.0) * shake, (rand::random::<f32>() * 2.0 - 1.0) * shake)
+        } else {
+            (0.0, 0.0)
+        };
+        let flash = FLASH_STRENGTH * self.flash_for / FLASH_TIME;
+
+        let Some(color_texture) = &self.color_texture else {
+            return;
+        };
+        let size = self.size();
+
+        Framebuffer::unbind();
+        let [scene_location, shake_location, flash_location, crt_location, resolution_location, time_location] = uniforms;
+        unsafe {
+            gl::Uniform1i(scene_location, 0);
+            gl::Uniform2f(shake_location, shake_offset.0, shake_offset.1);
+            gl::Uniform1f(flash_location, flash);
+            gl::Uniform1i(crt_location, self.crt as GLint);
+            gl::Uniform2f(resolution_location, size.0 as f32, size.1 as f32);
+            gl::Uniform1f(time_location, time);
+        }
+        color_texture.bind(0);
+        self.quad_vao.bind();
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+        }
+        Vao::unbind();
+    }
+}
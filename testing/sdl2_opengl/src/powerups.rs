@@ -0,0 +1,159 @@
+use crate::camera::ARENA_HALF_SIZE;
+use crate::components::Position;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub const POWER_UP_HALF_SIZE: f32 = 0.04;
+// Uncollected power-ups disappear after this many seconds
+pub const POWER_UP_LIFETIME: f32 = 8.0;
+// Seconds between spawns, picked at random in this range
+const MIN_SPAWN_INTERVAL: f32 = 6.0;
+const MAX_SPAWN_INTERVAL: f32 = 12.0;
+const MAX_POWER_UPS: usize = 2;
+// Power-ups spawn this far from the center at most, away from the arena edges
+const SPAWN_RANGE: f32 = 0.9 * ARENA_HALF_SIZE;
+
+const SPEED_BOOST_FACTOR: f32 = 1.6;
+const SLOW_MOTION_FACTOR: f32 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerUpKind {
+    SpeedBoost,
+    Shield,
+    SlowMotion,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 3] = [PowerUpKind::SpeedBoost, PowerUpKind::Shield, PowerUpKind::SlowMotion];
+
+    fn index(self) -> usize {
+        match self {
+            PowerUpKind::SpeedBoost => 0,
+            PowerUpKind::Shield => 1,
+            PowerUpKind::SlowMotion => 2,
+        }
+    }
+
+    // Seconds the effect lasts after pickup
+    fn duration(self) -> f32 {
+        match self {
+            PowerUpKind::SpeedBoost => 5.0,
+            PowerUpKind::Shield => 6.0,
+            PowerUpKind::SlowMotion => 4.0,
+        }
+    }
+
+    pub fn color(self) -> [f32; 4] {
+        match self {
+            PowerUpKind::SpeedBoost => [0.2, 0.9, 1.0, 1.0],
+            PowerUpKind::Shield => [0.3, 0.4, 1.0, 1.0],
+            PowerUpKind::SlowMotion => [0.8, 0.3, 1.0, 1.0],
+        }
+    }
+
+    // Name shown in the HUD
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerUpKind::SpeedBoost => "Speed",
+            PowerUpKind::Shield => "Shield",
+            PowerUpKind::SlowMotion => "Slow-mo",
+        }
+    }
+}
+
+// Activates its effect on the player who touches it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+}
+
+fn random_interval(rng: &mut SmallRng) -> f32 {
+    MIN_SPAWN_INTERVAL + rng.gen::<f32>() * (MAX_SPAWN_INTERVAL - MIN_SPAWN_INTERVAL)
+}
+
+// Drops a random power-up at a random position every so often, at most MAX_POWER_UPS at a
+// time
+#[derive(Serialize, Deserialize)]
+pub struct PowerUpSpawner {
+    timer: f32,
+}
+
+impl PowerUpSpawner {
+    pub fn new(rng: &mut SmallRng) -> PowerUpSpawner {
+        PowerUpSpawner {
+            timer: random_interval(rng),
+        }
+    }
+
+    // Where and what to spawn, if it is time. `count` is the number of power-ups lying around.
+    pub fn update(&mut self, dt: f32, count: usize, rng: &mut SmallRng) -> Option<(Position, PowerUpKind)> {
+        if count >= MAX_POWER_UPS {
+            return None;
+        }
+        self.timer -= dt;
+        if self.timer > 0.0 {
+            return None;
+        }
+        self.timer = random_interval(rng);
+        let kind = PowerUpKind::ALL[rng.gen_range(0..PowerUpKind::ALL.len())];
+        let position = Position {
+            x: rng.gen::<f32>() * 2.0 * SPAWN_RANGE - SPAWN_RANGE,
+            y: rng.gen::<f32>() * 2.0 * SPAWN_RANGE - SPAWN_RANGE,
+        };
+        Some((position, kind))
+    }
+}
+
+// Remaining time of every timed effect on the player. Picking up a power-up that is already
+// active restarts its timer.
+#[derive(Serialize, Deserialize)]
+pub struct Effects {
+    remaining: [f32; 3],
+}
+
+impl Effects {
+    pub fn new() -> Effects {
+        Effects { remaining: [0.0; 3] }
+    }
+
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        self.remaining[kind.index()] = kind.duration();
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for remaining in &mut self.remaining {
+            *remaining = (*remaining - dt).max(0.0);
+        }
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.remaining[kind.index()] > 0.0
+    }
+
+    // Active effects and their remaining seconds, in PowerUpKind::ALL order
+    pub fn active(&self) -> Vec<(PowerUpKind, f32)> {
+        PowerUpKind::ALL
+            .iter()
+            .filter(|kind| self.is_active(**kind))
+            .map(|kind| (*kind, self.remaining[kind.index()]))
+            .collect()
+    }
+
+    pub fn speed_factor(&self) -> f32 {
+        if self.is_active(PowerUpKind::SpeedBoost) {
+            SPEED_BOOST_FACTOR
+        } else {
+            1.0
+        }
+    }
+
+    // Applied to the obstacles' time step
+    pub fn time_factor(&self) -> f32 {
+        if self.is_active(PowerUpKind::SlowMotion) {
+            SLOW_MOTION_FACTOR
+        } else {
+            1.0
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::components::Velocity;
+use crate::ecs::Entity;
+use serde::{Deserialize, Serialize};
+
+// World units per second
+const PROJECTILE_SPEED: f32 = 1.5;
+// Seconds before a projectile that hit nothing disappears, long enough to cross the window
+pub const PROJECTILE_LIFETIME: f32 = 1.5;
+pub const PROJECTILE_RADIUS: f32 = 0.02;
+
+pub const MAX_AMMO: u32 = 8;
+// Minimum time between two shots while the fire key is held
+const FIRE_COOLDOWN: f32 = 0.2;
+// One round comes back this often, in seconds, up to MAX_AMMO
+const AMMO_RECHARGE: f32 = 1.0;
+
+// Destroys the first obstacle it hits and is used up
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Projectile {
+    // Who fired it
+    pub shooter: Entity,
+}
+
+// `direction` is unit length
+pub fn velocity(direction: (f32, f32)) -> Velocity {
+    Velocity {
+        x: direction.0 * PROJECTILE_SPEED,
+        y: direction.1 * PROJECTILE_SPEED,
+    }
+}
+
+// Ammo and fire rate. Shots use up ammo which slowly recharges, so holding the fire key
+// empties the gun and then fires at the recharge rate.
+#[derive(Serialize, Deserialize)]
+pub struct Gun {
+    pub ammo: u32,
+    cooldown: f32,
+    recharge: f32,
+}
+
+impl Gun {
+    pub fn new() -> Gun {
+        Gun {
+            ammo: MAX_AMMO,
+            cooldown: 0.0,
+            recharge: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.cooldown = (self.cooldown - dt).max(0.0);
+        if self.ammo >= MAX_AMMO {
+            self.recharge = 0.0;
+            return;
+        }
+        self.recharge += dt;
+        if self.recharge >= AMMO_RECHARGE {
+            self.recharge -= AMMO_RECHARGE;
+            self.ammo += 1;
+        }
+    }
+
+    // True if a shot was fired
+    pub fn try_fire(&mut self) -> bool {
+        if self.cooldown > 0.0 || self.ammo == 0 {
+            return false;
+        }
+        self.ammo -= 1;
+        self.cooldown = FIRE_COOLDOWN;
+        true
+    }
+}
@@ -0,0 +1,554 @@
+use crate::camera::{Camera, IDENTITY};
+use crate::components::FULL_TEXTURE;
+use crate::config::Scaling;
+use crate::gl_objects::{Vao, Vbo};
+use crate::particles::{MAX_PARTICLES, PARTICLE_FLOATS};
+use crate::post_process::{self, PostProcess};
+use crate::shaders::{ProgramId, ShaderLibrary, SHADERS_DIR};
+use crate::texture::Texture;
+use crate::{OBSTACLE_SPRITE_PATH, PLAYER_SPRITE_PATH, RECT_HALF_SIZE, TRIANGLE_SIZE};
+use gl::types::*;
+use imgui::{DrawCmd, DrawCmdParams, DrawData, DrawIdx, DrawVert};
+use sdl2::video::Window;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::ptr;
+use std::time::Instant;
+
+fn load_sprite(path: &str) -> Option<Texture> {
+    match Texture::load(path) {
+        Ok(texture) => {
+            println!("Loaded sprite {} ({}x{})", path, texture.width(), texture.height());
+            Some(texture)
+        }
+        Err(e) => {
+            eprintln!("Failed to load sprite {}: {}", path, e);
+            None
+        }
+    }
+}
+
+// Uploads interleaved float vertices into a new VAO and VBO. `attributes` are the component
+// counts of the attributes at locations 0, 1, ...
+fn create_vertex_array(vertices: &[f32], attributes: &[GLint], ebo: Option<&Vbo>) -> (Vao, Vbo) {
+    let vao = Vao::new();
+    vao.bind();
+    let vbo = Vbo::new(gl::ARRAY_BUFFER, vertices);
+    // Recorded in the VAO
+    if let Some(ebo) = ebo {
+        ebo.bind(gl::ELEMENT_ARRAY_BUFFER);
+    }
+
+    unsafe {
+        let stride = attributes.iter().sum::<GLint>() * std::mem::size_of::<GLfloat>() as GLsizei;
+        let mut offset = 0;
+        for (location, size) in attributes.iter().enumerate() {
+            gl::VertexAttribPointer(
+                location as GLuint,
+                *size,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (offset * std::mem::size_of::<GLfloat>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(location as GLuint);
+            offset += *size as usize;
+        }
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+    }
+    Vao::unbind();
+    (vao, vbo)
+}
+
+// Area of the window in pixels, as passed to glViewport
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Viewport {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Viewport {
+    fn full(size: (u32, u32)) -> Viewport {
+        Viewport {
+            x: 0,
+            y: 0,
+            width: size.0 as i32,
+            height: size.1 as i32,
+        }
+    }
+
+    // Where the scene goes in a window of `size` pixels
+    fn scene(size: (u32, u32), scaling: Scaling) -> Viewport {
+        match scaling {
+            Scaling::Expand => Viewport::full(size),
+            Scaling::Letterbox => {
+                let side = size.0.min(size.1);
+                Viewport {
+                    x: ((size.0 - side) / 2) as i32,
+                    y: ((size.1 - side) / 2) as i32,
+                    width: side as i32,
+                    height: side as i32,
+                }
+            }
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width.max(1) as u32, self.height.max(1) as u32)
+    }
+
+    fn aspect(&self) -> f32 {
+        self.width.max(1) as f32 / self.height.max(1) as f32
+    }
+
+    fn apply(&self) {
+        unsafe {
+            gl::Viewport(self.x, self.y, self.width, self.height);
+        }
+    }
+}
+
+// Owns every program, vertex array and texture the game draws with. Positions and sizes are in
+// world units while the scene is drawn, through the camera set with `set_camera`, and in NDC
+// after `end_scene`. The VBOs are only kept alive for their VAOs. Needs a current GL context for
+// its whole lifetime, everything is deleted on drop.
+pub struct Renderer {
+    shaders: ShaderLibrary,
+    post_process: PostProcess,
+    // Looked up on first use, cleared whenever a program is rebuilt
+    uniform_locations: HashMap<(ProgramId, &'static str), GLint>,
+    // Player rectangle, RECT_HALF_SIZE around the origin
+    rect_vao: Vao,
+    _rect_vbo: Vbo,
+    // Two triangles, shared by the rectangle and sprite quads
+    _ebo: Vbo,
+    // Obstacle triangle, TRIANGLE_SIZE around the origin
+    triangle_vao: Vao,
+    _triangle_vbo: Vbo,
+    // Unit square from (0, 0) to (1, 1), scaled and offset for tiles, overlays and outlines
+    square_vao: Vao,
+    _square_vbo: Vbo,
+    // Textured -1..1 quad
+    sprite_vao: Vao,
+    _sprite_vbo: Vbo,
+    // -1..1 quad corners per vertex, the rest per instance from `particle_instances`
+    particle_vao: Vao,
+    _particle_vbo: Vbo,
+    particle_instances: Vbo,
+    // Debug overlay geometry, both buffers are refilled for every imgui draw list
+    imgui_vao: Vao,
+    imgui_vertices: Vbo,
+    imgui_indices: Vbo,
+    // Player and obstacle sprites, flat colored shapes are drawn if either is missing
+    sprites: Option<(Texture, Texture)>,
+    window_size: (u32, u32),
+    // The whole window and the part of it the scene is drawn to, in drawable pixels
+    window_viewport: Viewport,
+    scene_viewport: Viewport,
+    scaling: Scaling,
+    // Uploaded to every program that has a `projection` uniform
+    projection: [f32; 16],
+    // Whether this frame's scene went into the post-processing framebuffer
+    post_processed: bool,
+    start_time: Instant,
+}
+
+impl Renderer {
+    pub fn new(window: &Window, crt_filter: bool, scaling: Scaling) -> Result<Renderer, String> {
+        let shaders = ShaderLibrary::load().map_err(|e| format!("failed to load shaders from {}: {}", SHADERS_DIR, e))?;
+        let post_process = PostProcess::new(Viewport::scene(window.drawable_size(), scaling).size(), crt_filter)
+            .map_err(|e| format!("failed to create the post-processing framebuffer: {}", e))?;
+
+        let indices: [u32; 6] = [0, 1, 2, 2, 3, 0];
+        let ebo = Vbo::new(gl::ELEMENT_ARRAY_BUFFER, &indices);
+        unsafe {
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
+        }
+
+        let h = RECT_HALF_SIZE;
+        let rect_vertices: [f32; 8] = [-h, -h, h, -h, h, h, -h, h];
+        let (rect_vao, rect_vbo) = create_vertex_array(&rect_vertices, &[2], Some(&ebo));
+
+        let t = TRIANGLE_SIZE;
+        let triangle_vertices: [f32; 6] = [0.0, t, -t, -t, t, -t];
+        let (triangle_vao, triangle_vbo) = create_vertex_array(&triangle_vertices, &[2], None);
+
+        let square_vertices: [f32; 8] = [0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0];
+        let (square_vao, square_vbo) = create_vertex_array(&square_vertices, &[2], None);
+
+        // Interleaved as x, y, u, v
+        let sprite_vertices: [f32; 16] = [
+            -1.0, -1.0, 0.0, 0.0,
+            1.0, -1.0, 1.0, 0.0,
+            1.0, 1.0, 1.0, 1.0,
+            -1.0, 1.0, 0.0, 1.0,
+        ];
+        let (sprite_vao, sprite_vbo) = create_vertex_array(&sprite_vertices, &[2, 2], Some(&ebo));
+
+        let corners: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+        let (particle_vao, particle_vbo) = create_vertex_array(&corners, &[2], Some(&ebo));
+        let float_size = std::mem::size_of::<GLfloat>();
+        let particle_instances = Vbo::dynamic(gl::ARRAY_BUFFER, MAX_PARTICLES * PARTICLE_FLOATS * float_size);
+        particle_vao.bind();
+        particle_instances.bind(gl::ARRAY_BUFFER);
+        unsafe {
+            let stride = (PARTICLE_FLOATS * float_size) as GLsizei;
+            // Center, size and color, advancing once per instance
+            for (location, size, offset) in [(1, 2, 0), (2, 1, 2), (3, 4, 3)] {
+                gl::VertexAttribPointer(location, size, gl::FLOAT, gl::FALSE, stride, (offset * float_size) as *const _);
+                gl::EnableVertexAttribArray(location);
+                gl::VertexAttribDivisor(location, 1);
+            }
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+        Vao::unbind();
+
+        let imgui_vao = Vao::new();
+        imgui_vao.bind();
+        let imgui_vertices = Vbo::dynamic(gl::ARRAY_BUFFER, 0);
+        let imgui_indices = Vbo::dynamic(gl::ELEMENT_ARRAY_BUFFER, 0);
+        unsafe {
+            let stride = std::mem::size_of::<DrawVert>() as GLsizei;
+            // Position and texture coordinates as floats, then the color as four normalized bytes
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride, (2 * float_size) as *const _);
+            gl::VertexAttribPointer(2, 4, gl::UNSIGNED_BYTE, gl::TRUE, stride, (4 * float_size) as *const _);
+            for location in 0..3 {
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+        Vao::unbind();
+
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+
+        let sprites = match (load_sprite(PLAYER_SPRITE_PATH), load_sprite(OBSTACLE_SPRITE_PATH)) {
+            (Some(player), Some(obstacle)) => Some((player, obstacle)),
+            _ => None,
+        };
+
+        Ok(Renderer {
+            shaders,
+            post_process,
+            uniform_locations: HashMap::new(),
+            rect_vao,
+            _rect_vbo: rect_vbo,
+            _ebo: ebo,
+            triangle_vao,
+            _triangle_vbo: triangle_vbo,
+            square_vao,
+            _square_vbo: square_vbo,
+            sprite_vao,
+            _sprite_vbo: sprite_vbo,
+            particle_vao,
+            _particle_vbo: particle_vbo,
+            particle_instances,
+            imgui_vao,
+            imgui_vertices,
+            imgui_indices,
+            sprites,
+            window_size: window.size(),
+            window_viewport: Viewport::full(window.drawable_size()),
+            scene_viewport: Viewport::scene(window.drawable_size(), scaling),
+            scaling,
+            projection: IDENTITY,
+            post_processed: false,
+            start_time: Instant::now(),
+        })
+    }
+
+    // Call when the window size changed, the scene is refit on the next frame
+    pub fn resize(&mut self, window: &Window) {
+        self.window_size = window.size();
+        self.window_viewport = Viewport::full(window.drawable_size());
+        self.scene_viewport = Viewport::scene(window.drawable_size(), self.scaling);
+    }
+
+    // Window size in screen coordinates, what the pixel positions of text are relative to
+    pub fn window_size(&self) -> (u32, u32) {
+        self.window_size
+    }
+
+    // Shakes the screen and flashes it red
+    pub fn hit(&mut self) {
+        self.post_process.hit();
+    }
+
+    pub fn toggle_crt(&mut self) {
+        self.post_process.toggle_crt();
+    }
+
+    fn uniform_location(&mut self, id: ProgramId, name: &'static str) -> GLint {
+        let program = self.shaders.program(id);
+        *self.uniform_locations.entry((id, name)).or_insert_with(|| {
+            let c_name = CString::new(name).unwrap();
+            unsafe { gl::GetUniformLocation(program, c_name.as_ptr()) }
+        })
+    }
+
+    // Binds the program with the current projection and returns its cached uniform locations
+    // for `names`, in order
+    fn use_program<const N: usize>(&mut self, id: ProgramId, names: [&'static str; N]) -> [GLint; N] {
+        let projection = self.uniform_location(id, "projection");
+        unsafe {
+            gl::UseProgram(self.shaders.program(id));
+            gl::UniformMatrix4fv(projection, 1, gl::FALSE, self.projection.as_ptr());
+        }
+        names.map(|name| self.uniform_location(id, name))
+    }
+
+    // Used for everything drawn until `end_scene`
+    pub fn set_camera(&mut self, camera: &Camera) {
+        self.projection = camera.projection(self.scene_viewport.aspect());
+    }
+
+    // Starts the scene, which goes through post-processing until `end_scene`
+    pub fn begin_frame(&mut self, dt: f32) {
+        if self.shaders.reload_changed() {
+            self.uniform_locations.clear();
+        }
+        self.post_process.update(dt);
+
+        // Also clears the letterbox bars
+        self.window_viewport.apply();
+        unsafe {
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+
+        let scene_size = self.scene_viewport.size();
+        self.post_processed = self.post_process.begin(scene_size);
+        if self.post_processed {
+            Viewport::full(scene_size).apply();
+            unsafe {
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+        } else {
+            self.scene_viewport.apply();
+        }
+    }
+
+    // Puts the post-processed scene on screen. Everything drawn afterwards, overlays and text,
+    // goes to the window directly so it doesn't shake or get the CRT filter.
+    pub fn end_scene(&mut self) {
+        self.projection = IDENTITY;
+        if self.post_processed {
+            self.scene_viewport.apply();
+            let uniforms = self.use_program(ProgramId::Post, post_process::UNIFORMS);
+            self.post_process.end(uniforms, self.start_time.elapsed().as_secs_f32());
+        }
+        self.window_viewport.apply();
+    }
+
+    fn draw_square(&mut self, position: (f32, f32), size: (f32, f32), color: [f32; 4], mode: GLenum) {
+        let [offset, scale, line_color] = self.use_program(ProgramId::Debug, ["offset", "scale", "lineColor"]);
+        unsafe {
+            gl::Uniform2f(offset, position.0 - size.0 / 2.0, position.1 - size.1 / 2.0);
+            gl::Uniform2f(scale, size.0, size.1);
+            gl::Uniform4fv(line_color, 1, color.as_ptr());
+            self.square_vao.bind();
+            gl::DrawArrays(mode, 0, 4);
+            Vao::unbind();
+        }
+    }
+
+    // Filled rectangle centered on `position`, `size` is the full width and height
+    pub fn draw_rect(&mut self, position: (f32, f32), size: (f32, f32), color: [f32; 4]) {
+        self.draw_square(position, size, color, gl::TRIANGLE_FAN);
+    }
+
+    pub fn draw_rect_outline(&mut self, position: (f32, f32), size: (f32, f32), color: [f32; 4]) {
+        self.draw_square(position, size, color, gl::LINE_LOOP);
+    }
+
+    // The `uv` frame of the player sprite tinted with `color`, or a flat rectangle in that color
+    pub fn draw_player(&mut self, position: (f32, f32), uv: [f32; 4], color: [f32; 4]) {
+        if self.sprites.is_some() {
+            let [offset, half_size, tint, texture, uv_rect] =
+                self.use_program(ProgramId::Sprite, ["offset", "halfSize", "tint", "spriteTexture", "uvRect"]);
+            unsafe {
+                gl::Uniform1i(texture, 0);
+                gl::Uniform4fv(uv_rect, 1, uv.as_ptr());
+                gl::Uniform2f(offset, position.0, position.1);
+                gl::Uniform2f(half_size, RECT_HALF_SIZE, RECT_HALF_SIZE);
+                gl::Uniform4fv(tint, 1, color.as_ptr());
+            }
+            let (player_sprite, _) = self.sprites.as_ref().unwrap();
+            player_sprite.bind(0);
+            unsafe {
+                self.sprite_vao.bind();
+                gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
+                Vao::unbind();
+            }
+        } else {
+            let [offset, rect_color] = self.use_program(ProgramId::Rect, ["offset", "rectColor"]);
+            unsafe {
+                gl::Uniform2f(offset, position.0, position.1);
+                gl::Uniform4fv(rect_color, 1, color.as_ptr());
+                self.rect_vao.bind();
+                gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
+                Vao::unbind();
+            }
+        }
+    }
+
+    // One instanced call for all `positions`. The obstacle sprite is drawn with the frames in
+    // `uvs`, one per position, and tinted with `tint`. The flat triangles use `color`.
+    pub fn draw_triangles(&mut self, positions: &[(f32, f32)], uvs: &[[f32; 4]], tint: [f32; 4], color: [f32; 4]) {
+        let offsets: Vec<f32> = positions.iter().flat_map(|(x, y)| [*x, *y]).collect();
+        let count = positions.len() as GLsizei;
+        if self.sprites.is_some() {
+            let [offsets_location, half_size, tint_location, texture, uv_rects] = self.use_program(
+                ProgramId::ObstacleSprite,
+                ["offsets", "halfSize", "tint", "spriteTexture", "uvRects"],
+            );
+            unsafe {
+                gl::Uniform1i(texture, 0);
+                gl::Uniform4fv(uv_rects, uvs.len() as GLsizei, uvs.as_ptr() as *const GLfloat);
+                gl::Uniform2f(half_size, TRIANGLE_SIZE, TRIANGLE_SIZE);
+                gl::Uniform2fv(offsets_location, count, offsets.as_ptr());
+                gl::Uniform4fv(tint_location, 1, tint.as_ptr());
+            }
+            let (_, obstacle_sprite) = self.sprites.as_ref().unwrap();
+            obstacle_sprite.bind(0);
+            unsafe {
+                self.sprite_vao.bind();
+                gl::DrawElementsInstanced(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null(), count);
+                Vao::unbind();
+            }
+        } else {
+            let [offsets_location, obstacle_color] = self.use_program(ProgramId::Obstacle, ["offsets", "obstacleColor"]);
+            unsafe {
+                gl::Uniform2fv(offsets_location, count, offsets.as_ptr());
+                gl::Uniform4fv(obstacle_color, 1, color.as_ptr());
+                self.triangle_vao.bind();
+                gl::DrawArraysInstanced(gl::TRIANGLES, 0, 3, count);
+                Vao::unbind();
+            }
+        }
+    }
+
+    // One instanced call for all particles, `instances` as built by Particles::instances.
+    // Blended additively, so particles light up what is behind them.
+    pub fn draw_particles(&mut self, instances: &[f32]) {
+        let count = (instances.len() / PARTICLE_FLOATS).min(MAX_PARTICLES);
+        if count == 0 {
+            return;
+        }
+        let [] = self.use_program(ProgramId::Particle, []);
+        self.particle_instances
+            .update(gl::ARRAY_BUFFER, &instances[..count * PARTICLE_FLOATS]);
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE);
+            self.particle_vao.bind();
+            gl::DrawElementsInstanced(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null(), count as GLsizei);
+            Vao::unbind();
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+    }
+
+    // Draws a texture at its native size with its top left corner at (x, y) window pixels
+    pub fn draw_texture_at_pixel(&mut self, texture: &Texture, position: (f32, f32), tint: [f32; 4]) {
+        let (window_width, window_height) = (self.window_size.0 as f32, self.window_size.1 as f32);
+        // The quad spans -1..1, so half its size in NDC is the pixel size over the window size
+        let half_width = texture.width() as f32 / window_width;
+        let half_height = texture.height() as f32 / window_height;
+        let x = -1.0 + 2.0 * position.0 / window_width + half_width;
+        let y = 1.0 - 2.0 * position.1 / window_height - half_height;
+
+        let [offset, half_size, tint_location, texture_location, uv_rect] =
+            self.use_program(ProgramId::Sprite, ["offset", "halfSize", "tint", "spriteTexture", "uvRect"]);
+        unsafe {
+            gl::Uniform1i(texture_location, 0);
+            gl::Uniform4fv(uv_rect, 1, FULL_TEXTURE.as_ptr());
+            gl::Uniform2f(offset, x, y);
+            gl::Uniform2f(half_size, half_width, half_height);
+            gl::Uniform4fv(tint_location, 1, tint.as_ptr());
+
+            texture.bind(0);
+            self.sprite_vao.bind();
+            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
+            Vao::unbind();
+        }
+    }
+
+    // Draws the debug overlay on top of everything, call after all other drawing. imgui works in
+    // window coordinates with the origin at the top left, each command is clipped to its rectangle.
+    pub fn draw_imgui(&mut self, draw_data: &DrawData) {
+        let [left, top] = draw_data.display_pos;
+        let [width, height] = draw_data.display_size;
+        let [scale_x, scale_y] = draw_data.framebuffer_scale;
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        let (right, bottom) = (left + width, top + height);
+        #[rustfmt::skip]
+        let projection = [
+            2.0 / (right - left), 0.0, 0.0, 0.0,
+            0.0, 2.0 / (top - bottom), 0.0, 0.0,
+            0.0, 0.0, -1.0, 0.0,
+            (right + left) / (left - right), (top + bottom) / (bottom - top), 0.0, 1.0,
+        ];
+        let scene_projection = std::mem::replace(&mut self.projection, projection);
+        let [texture_location] = self.use_program(ProgramId::Imgui, ["fontTexture"]);
+        let framebuffer_height = self.window_viewport.height as f32;
+
+        unsafe {
+            gl::Uniform1i(texture_location, 0);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::Enable(gl::SCISSOR_TEST);
+            self.imgui_vao.bind();
+            for draw_list in draw_data.draw_lists() {
+                self.imgui_vertices.upload(gl::ARRAY_BUFFER, draw_list.vtx_buffer());
+                self.imgui_indices.upload(gl::ELEMENT_ARRAY_BUFFER, draw_list.idx_buffer());
+                for command in draw_list.commands() {
+                    let DrawCmd::Elements {
+                        count,
+                        cmd_params:
+                            DrawCmdParams {
+                                clip_rect,
+                                texture_id,
+                                vtx_offset,
+                                idx_offset,
+                            },
+                    } = command
+                    else {
+                        // No user callbacks are registered
+                        continue;
+                    };
+                    // Window coordinates to framebuffer pixels, GL's scissor box starts at the bottom
+                    let clip_left = (clip_rect[0] - left) * scale_x;
+                    let clip_top = (clip_rect[1] - top) * scale_y;
+                    let clip_right = (clip_rect[2] - left) * scale_x;
+                    let clip_bottom = (clip_rect[3] - top) * scale_y;
+                    if clip_right <= clip_left || clip_bottom <= clip_top {
+                        continue;
+                    }
+                    gl::Scissor(
+                        clip_left as GLint,
+                        (framebuffer_height - clip_bottom) as GLint,
+                        (clip_right - clip_left) as GLsizei,
+                        (clip_bottom - clip_top) as GLsizei,
+                    );
+                    gl::BindTexture(gl::TEXTURE_2D, texture_id.id() as GLuint);
+                    gl::DrawElementsBaseVertex(
+                        gl::TRIANGLES,
+                        count as GLsizei,
+                        gl::UNSIGNED_SHORT,
+                        (idx_offset * std::mem::size_of::<DrawIdx>()) as *const _,
+                        vtx_offset as GLint,
+                    );
+                }
+            }
+            Vao::unbind();
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::Disable(gl::SCISSOR_TEST);
+        }
+        self.projection = scene_projection;
+    }
+}
@@ -0,0 +1,225 @@
+use crate::input::FrameInput;
+use std::fs;
+use std::path::Path;
+
+// The last finished or quit run is written here
+pub const REPLAY_PATH: &str = "last_run.replay";
+
+const MAGIC: &[u8; 4] = b"SGRP";
+// Bumped whenever the format or the simulation changes, a replay only reproduces a run on
+// the version that recorded it
+const REPLAY_VERSION: u8 = 2;
+// Magic, version, seed, level index, player speed and frame count
+const HEADER_SIZE: usize = 4 + 1 + 8 + 4 + 4 + 4;
+// Time step and input bits
+const FRAME_SIZE: usize = 5;
+
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const DOWN: u8 = 4;
+const UP: u8 = 8;
+const FIRE: u8 = 16;
+
+fn encode(input: &FrameInput) -> u8 {
+    let mut bits = 0;
+    bits |= match input.x {
+        x if x < 0 => LEFT,
+        x if x > 0 => RIGHT,
+        _ => 0,
+    };
+    bits |= match input.y {
+        y if y < 0 => DOWN,
+        y if y > 0 => UP,
+        _ => 0,
+    };
+    if input.firing {
+        bits |= FIRE;
+    }
+    bits
+}
+
+fn decode(bits: u8) -> FrameInput {
+    let axis = |negative: u8, positive: u8| (bits & positive != 0) as i8 - (bits & negative != 0) as i8;
+    FrameInput {
+        x: axis(LEFT, RIGHT),
+        y: axis(DOWN, UP),
+        firing: bits & FIRE != 0,
+    }
+}
+
+// Everything needed to play a run back: the world's seed and settings, then the time step and
+// input of every simulated frame. Stored as little endian binary, five bytes per frame.
+pub struct Replay {
+    pub seed: u64,
+    pub level_index: usize,
+    pub player_speed: f32,
+    frames: Vec<(f32, FrameInput)>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, level_index: usize, player_speed: f32) -> Replay {
+        Replay {
+            seed,
+            level_index,
+            player_speed,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, dt: f32, input: FrameInput) {
+        self.frames.push((dt, input));
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.frames.len() * FRAME_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(REPLAY_VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.level_index as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.player_speed.to_le_bytes());
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        for (dt, input) in &self.frames {
+            bytes.extend_from_slice(&dt.to_le_bytes());
+            bytes.push(encode(input));
+        }
+        fs::write(path, bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replay, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC {
+            return Err("not a replay file".to_string());
+        }
+        if bytes[4] != REPLAY_VERSION {
+            return Err(format!("replay version {} is not supported, expected {}", bytes[4], REPLAY_VERSION));
+        }
+        // Fixed size fields at known offsets, the length was checked above
+        let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let seed = u64::from_le_bytes(bytes[5..13].try_into().unwrap());
+        let level_index = u32_at(13) as usize;
+        let player_speed = f32::from_bits(u32_at(17));
+        let count = u32_at(21) as usize;
+        if bytes.len() != HEADER_SIZE + count * FRAME_SIZE {
+            return Err(format!("replay is truncated, expected {} frames", count));
+        }
+
+        let frames = bytes[HEADER_SIZE..]
+            .chunks_exact(FRAME_SIZE)
+            .map(|frame| (f32::from_le_bytes(frame[..4].try_into().unwrap()), decode(frame[4])))
+            .collect();
+        Ok(Replay {
+            seed,
+            level_index,
+            player_speed,
+            frames,
+        })
+    }
+}
+
+// Hands out a loaded replay's frames in order
+pub struct Playback {
+    replay: Replay,
+    next_frame: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Playback {
+        Playback { replay, next_frame: 0 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_frame >= self.replay.frames.len()
+    }
+
+    // Time step and input for the next simulated frame
+    pub fn next_frame(&mut self) -> Option<(f32, FrameInput)> {
+        let frame = self.replay.frames.get(self.next_frame).copied();
+        self.next_frame += 1;
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sdl2_opengl_{}_{}", std::process::id(), name))
+    }
+
+    fn input(x: i8, y: i8, firing: bool) -> FrameInput {
+        FrameInput { x, y, firing }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut replay = Replay::new(0xDEAD_BEEF_1234, 2, 1.25);
+        let frames = [
+            (1.0 / 60.0, input(0, 0, false)),
+            (1.0 / 60.0, input(-1, 1, true)),
+            (0.25, input(1, -1, false)),
+            (0.0, input(0, 1, true)),
+        ];
+        for (dt, input) in frames {
+            replay.record(dt, input);
+        }
+
+        let path = temp_path("round_trip.replay");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.level_index, replay.level_index);
+        assert_eq!(loaded.player_speed, replay.player_speed);
+        assert_eq!(loaded.frames, frames);
+
+        let mut playback = Playback::new(loaded);
+        for frame in frames {
+            assert!(!playback.is_finished());
+            assert_eq!(playback.next_frame(), Some(frame));
+        }
+        assert!(playback.is_finished());
+        assert_eq!(playback.next_frame(), None);
+    }
+
+    #[test]
+    fn load_rejects_truncated_files() {
+        let mut replay = Replay::new(7, 0, 1.0);
+        replay.record(0.5, input(1, 0, false));
+        replay.record(0.5, input(0, 0, true));
+        let path = temp_path("truncated.replay");
+        replay.save(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+
+        // Missing the last frame's input byte
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        let missing_frame = Replay::load(&path).err();
+        // Cut off inside the header
+        fs::write(&path, &bytes[..HEADER_SIZE - 1]).unwrap();
+        let missing_header = Replay::load(&path).err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(missing_frame.as_deref(), Some("replay is truncated, expected 2 frames"));
+        assert_eq!(missing_header.as_deref(), Some("not a replay file"));
+    }
+
+    #[test]
+    fn load_rejects_other_versions() {
+        let path = temp_path("version.replay");
+        Replay::new(7, 0, 1.0).save(&path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[4] = REPLAY_VERSION + 1;
+        fs::write(&path, &bytes).unwrap();
+        let result = Replay::load(&path).err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, Some(format!("replay version {} is not supported, expected {}", REPLAY_VERSION + 1, REPLAY_VERSION)));
+    }
+}
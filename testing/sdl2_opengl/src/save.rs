@@ -0,0 +1,53 @@
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const SAVE_PATH: &str = "savegame.toml";
+// Bumped whenever World changes shape, older saves are refused instead of half loaded
+const SAVE_VERSION: u32 = 3;
+
+#[derive(Serialize)]
+struct SaveGameRef<'a> {
+    version: u32,
+    level_index: usize,
+    world: &'a World,
+}
+
+// A whole run: the world including its tile map, plus which level it was started from
+#[derive(Deserialize)]
+pub struct SaveGame {
+    version: u32,
+    pub level_index: usize,
+    pub world: World,
+}
+
+impl SaveGame {
+    // Written to a temporary file first so a crash mid-write can't destroy the previous save
+    pub fn save<P: AsRef<Path>>(path: P, level_index: usize, world: &World) -> Result<(), String> {
+        let path = path.as_ref();
+        let save = SaveGameRef {
+            version: SAVE_VERSION,
+            level_index,
+            world,
+        };
+        let contents = toml::to_string(&save).map_err(|e| e.to_string())?;
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, contents).map_err(|e| e.to_string())?;
+        fs::rename(&temp_path, path).map_err(|e| e.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<SaveGame, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let save: SaveGame = toml::from_str(&contents).map_err(|e| e.to_string())?;
+        if save.version != SAVE_VERSION {
+            return Err(format!("save version {} is not supported, expected {}", save.version, SAVE_VERSION));
+        }
+        // A hand edited or damaged save would otherwise crash on the next spawn
+        save.world.entities.validate()?;
+        if !save.world.entities.is_alive(save.world.player) {
+            return Err("the player entity is not alive".to_string());
+        }
+        Ok(save)
+    }
+}
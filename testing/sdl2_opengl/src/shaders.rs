@@ -0,0 +1,205 @@
+use crate::gl_objects::ShaderProgram;
+use gl::types::*;
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::{Duration, Instant, SystemTime};
+
+pub const SHADERS_DIR: &str = "shaders";
+// How often the shader files are checked for changes
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProgramId {
+    // Flat colored player rectangle
+    Rect,
+    // Flat colored obstacle triangles, instanced
+    Obstacle,
+    // Scaled and offset unit square in a single color: grid cells, tiles, overlays
+    Debug,
+    // Textured quad, also used for text
+    Sprite,
+    // Instanced textured obstacle quads
+    ObstacleSprite,
+    // Fullscreen pass over the offscreen scene: shake, damage flash, CRT filter
+    Post,
+    // Instanced soft dots with per-instance position, size and color
+    Particle,
+    // Debug overlay widgets, vertex colored and textured with the font atlas
+    Imgui,
+}
+
+impl ProgramId {
+    const ALL: [ProgramId; 8] = [
+        ProgramId::Rect,
+        ProgramId::Obstacle,
+        ProgramId::Debug,
+        ProgramId::Sprite,
+        ProgramId::ObstacleSprite,
+        ProgramId::Post,
+        ProgramId::Particle,
+        ProgramId::Imgui,
+    ];
+
+    // Vertex and fragment shader file names in SHADERS_DIR
+    fn files(self) -> (&'static str, &'static str) {
+        match self {
+            ProgramId::Rect => ("rect.vert", "rect.frag"),
+            ProgramId::Obstacle => ("obstacle.vert", "obstacle.frag"),
+            ProgramId::Debug => ("debug.vert", "debug.frag"),
+            ProgramId::Sprite => ("sprite.vert", "sprite.frag"),
+            ProgramId::ObstacleSprite => ("obstacle_sprite.vert", "sprite.frag"),
+            ProgramId::Post => ("post.vert", "post.frag"),
+            ProgramId::Particle => ("particle.vert", "particle.frag"),
+            ProgramId::Imgui => ("imgui.vert", "imgui.frag"),
+        }
+    }
+}
+
+type GetParameter = unsafe fn(GLuint, GLenum, *mut GLint);
+type GetInfoLog = unsafe fn(GLuint, GLsizei, *mut GLsizei, *mut GLchar);
+
+// Works for both shaders and programs given the matching getters
+fn info_log(object: GLuint, get_parameter: GetParameter, get_info_log: GetInfoLog) -> String {
+    let mut length: GLint = 0;
+    unsafe {
+        get_parameter(object, gl::INFO_LOG_LENGTH, &mut length);
+    }
+    let mut log = vec![0u8; length.max(1) as usize];
+    let mut written: GLsizei = 0;
+    unsafe {
+        get_info_log(object, log.len() as GLsizei, &mut written, log.as_mut_ptr() as *mut GLchar);
+    }
+    log.truncate(written.max(0) as usize);
+    String::from_utf8_lossy(&log).trim_end().to_string()
+}
+
+fn compile_shader(src: &str, kind: GLenum) -> Result<GLuint, String> {
+    let c_str = CString::new(src.as_bytes()).map_err(|e| e.to_string())?;
+    unsafe {
+        let shader = gl::CreateShader(kind);
+        gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
+        gl::CompileShader(shader);
+
+        let mut success = gl::FALSE as GLint;
+        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+        if success == gl::FALSE as GLint {
+            let log = info_log(shader, gl::GetShaderiv, gl::GetShaderInfoLog);
+            gl::DeleteShader(shader);
+            return Err(log);
+        }
+        Ok(shader)
+    }
+}
+
+pub fn create_program(vertex_src: &str, fragment_src: &str) -> Result<ShaderProgram, String> {
+    let vertex_shader = compile_shader(vertex_src, gl::VERTEX_SHADER).map_err(|e| format!("vertex shader: {}", e))?;
+    let fragment_shader = match compile_shader(fragment_src, gl::FRAGMENT_SHADER) {
+        Ok(shader) => shader,
+        Err(e) => {
+            unsafe { gl::DeleteShader(vertex_shader) };
+            return Err(format!("fragment shader: {}", e));
+        }
+    };
+
+    unsafe {
+        // Deleted by ShaderProgram's drop if linking fails
+        let program = ShaderProgram::from_raw(gl::CreateProgram());
+        gl::AttachShader(program.id(), vertex_shader);
+        gl::AttachShader(program.id(), fragment_shader);
+        gl::LinkProgram(program.id());
+        gl::DeleteShader(vertex_shader);
+        gl::DeleteShader(fragment_shader);
+
+        let mut success = gl::FALSE as GLint;
+        gl::GetProgramiv(program.id(), gl::LINK_STATUS, &mut success);
+        if success == gl::FALSE as GLint {
+            let log = info_log(program.id(), gl::GetProgramiv, gl::GetProgramInfoLog);
+            return Err(format!("link: {}", log));
+        }
+        Ok(program)
+    }
+}
+
+struct WatchedProgram {
+    vertex_path: PathBuf,
+    fragment_path: PathBuf,
+    program: ShaderProgram,
+    // Modification times the program was built from
+    modified: (Option<SystemTime>, Option<SystemTime>),
+}
+
+fn modified_times(vertex_path: &Path, fragment_path: &Path) -> (Option<SystemTime>, Option<SystemTime>) {
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    (modified(vertex_path), modified(fragment_path))
+}
+
+fn build(vertex_path: &Path, fragment_path: &Path) -> Result<ShaderProgram, String> {
+    let read = |path: &Path| fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e));
+    create_program(&read(vertex_path)?, &read(fragment_path)?)
+}
+
+// Every program of the game, built from the files in SHADERS_DIR. Programs are rebuilt when
+// their files change, a change that doesn't compile keeps the previous program running.
+pub struct ShaderLibrary {
+    programs: Vec<WatchedProgram>,
+    last_check: Instant,
+}
+
+impl ShaderLibrary {
+    pub fn load() -> Result<ShaderLibrary, String> {
+        let mut library = ShaderLibrary {
+            programs: Vec::new(),
+            last_check: Instant::now(),
+        };
+        for id in ProgramId::ALL {
+            let (vertex_file, fragment_file) = id.files();
+            let vertex_path = PathBuf::from(SHADERS_DIR).join(vertex_file);
+            let fragment_path = PathBuf::from(SHADERS_DIR).join(fragment_file);
+            let modified = modified_times(&vertex_path, &fragment_path);
+            let program = build(&vertex_path, &fragment_path).map_err(|e| format!("{:?} program: {}", id, e))?;
+            library.programs.push(WatchedProgram {
+                vertex_path,
+                fragment_path,
+                program,
+                modified,
+            });
+        }
+        Ok(library)
+    }
+
+    pub fn program(&self, id: ProgramId) -> GLuint {
+        self.programs[id as usize].program.id()
+    }
+
+    // Rebuilds the programs whose files changed since the last build. Cheap to call every
+    // frame, the files are only looked at every RELOAD_CHECK_INTERVAL. Returns true if any
+    // program was replaced, uniform locations looked up before are stale then.
+    pub fn reload_changed(&mut self) -> bool {
+        if self.last_check.elapsed() < RELOAD_CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+
+        let mut reloaded = false;
+        for (id, watched) in ProgramId::ALL.iter().zip(&mut self.programs) {
+            let modified = modified_times(&watched.vertex_path, &watched.fragment_path);
+            if modified == watched.modified {
+                continue;
+            }
+            // Recorded even on failure so a broken file isn't recompiled until it changes again
+            watched.modified = modified;
+            match build(&watched.vertex_path, &watched.fragment_path) {
+                Ok(program) => {
+                    // Drops, and deletes, the old program
+                    watched.program = program;
+                    reloaded = true;
+                    println!("Reloaded {:?} program", id);
+                }
+                Err(e) => eprintln!("Keeping the previous {:?} program: {}", id, e),
+            }
+        }
+        reloaded
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Aabb {
+    pub fn from_center(x: f32, y: f32, half_w: f32, half_h: f32) -> Aabb {
+        Aabb {
+            min_x: x - half_w,
+            min_y: y - half_h,
+            max_x: x + half_w,
+            max_y: y + half_h,
+        }
+    }
+}
+
+pub type Cell = (i32, i32);
+
+// Uniform grid used as a broad phase: objects are bucketed by the cells their bounds touch,
+// and only objects sharing a cell are handed to the narrow phase. Ids index into `bounds` and
+// are expected to be small and dense, 0..n for n objects.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<usize>>,
+    bounds: Vec<Aabb>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> SpatialHash {
+        SpatialHash {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: Vec::new(),
+        }
+    }
+
+    // Keeps the allocated buckets around so rebuilding every frame doesn't churn memory
+    pub fn clear(&mut self) {
+        for ids in self.cells.values_mut() {
+            ids.clear();
+        }
+        self.bounds.clear();
+    }
+
+    fn cell_of(&self, x: f32, y: f32) -> Cell {
+        ((x / self.cell_size).floor() as i32, (y / self.cell_size).floor() as i32)
+    }
+
+    fn cell_range(&self, bounds: &Aabb) -> (Cell, Cell) {
+        (self.cell_of(bounds.min_x, bounds.min_y), self.cell_of(bounds.max_x, bounds.max_y))
+    }
+
+    pub fn insert(&mut self, id: usize, bounds: Aabb) {
+        if id >= self.bounds.len() {
+            self.bounds.resize(id + 1, bounds);
+        }
+        self.bounds[id] = bounds;
+        let (min, max) = self.cell_range(&bounds);
+        for cx in min.0..=max.0 {
+            for cy in min.1..=max.1 {
+                self.cells.entry((cx, cy)).or_default().push(id);
+            }
+        }
+    }
+
+    // Unique (lower id, higher id) pairs of objects whose bounds overlap, sorted so the
+    // result doesn't depend on the hash map's iteration order. Two objects can share several
+    // cells, a pair is only reported from the cell holding the corner of their overlap.
+    pub fn candidate_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (cell, ids) in &self.cells {
+            for (i, &a) in ids.iter().enumerate() {
+                for &b in &ids[i + 1..] {
+                    let (first, second) = (&self.bounds[a], &self.bounds[b]);
+                    let overlaps = first.min_x <= second.max_x
+                        && second.min_x <= first.max_x
+                        && first.min_y <= second.max_y
+                        && second.min_y <= first.max_y;
+                    if a != b && overlaps && self.cell_of(first.min_x.max(second.min_x), first.min_y.max(second.min_y)) == *cell {
+                        pairs.push((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+
+    pub fn occupied_cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.cells
+            .iter()
+            .filter(|(_, ids)| !ids.is_empty())
+            .map(|(cell, _)| *cell)
+    }
+
+    pub fn cell_bounds(&self, cell: Cell) -> Aabb {
+        let min_x = cell.0 as f32 * self.cell_size;
+        let min_y = cell.1 as f32 * self.cell_size;
+        Aabb {
+            min_x,
+            min_y,
+            max_x: min_x + self.cell_size,
+            max_y: min_y + self.cell_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn aabb(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Aabb {
+        Aabb { min_x, min_y, max_x, max_y }
+    }
+
+    fn pairs(cell_size: f32, bounds: &[Aabb]) -> Vec<(usize, usize)> {
+        let mut hash = SpatialHash::new(cell_size);
+        for (id, bounds) in bounds.iter().enumerate() {
+            hash.insert(id, *bounds);
+        }
+        hash.candidate_pairs()
+    }
+
+    fn brute_force(bounds: &[Aabb]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (a, first) in bounds.iter().enumerate() {
+            for (b, second) in bounds.iter().enumerate().skip(a + 1) {
+                if first.min_x <= second.max_x
+                    && second.min_x <= first.max_x
+                    && first.min_y <= second.max_y
+                    && second.min_y <= first.max_y
+                {
+                    pairs.push((a, b));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn objects_spanning_several_cells_are_reported_once() {
+        // Both cover a 3x3 block of cells, sharing all but the outer row and column
+        let bounds = [aabb(0.5, 0.5, 2.5, 2.5), aabb(1.5, 1.5, 3.5, 3.5), aabb(0.1, 0.1, 3.9, 3.9)];
+        assert_eq!(pairs(1.0, &bounds), vec![(0, 1), (0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn touching_edges_on_a_cell_boundary() {
+        // The first box ends exactly where the cells meet, the others touch it on its right
+        // edge, its top edge and its top right corner
+        let bounds = [
+            aabb(0.2, 0.2, 1.0, 1.0),
+            aabb(1.0, 0.4, 1.6, 0.6),
+            aabb(0.5, 1.0, 0.9, 1.5),
+            aabb(1.0, 1.0, 1.5, 1.5),
+        ];
+        assert_eq!(pairs(1.0, &bounds), vec![(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(pairs(1.0, &bounds), brute_force(&bounds));
+        // Just apart is no pair
+        let apart = [aabb(0.2, 0.2, 0.99, 0.8), aabb(1.0, 0.4, 1.6, 0.6)];
+        assert!(pairs(1.0, &apart).is_empty());
+    }
+
+    #[test]
+    fn negative_coordinates() {
+        let bounds = [
+            aabb(-2.5, -2.5, -0.5, -0.5),
+            aabb(-1.0, -1.0, 1.0, 1.0),
+            aabb(-0.4, -3.0, 0.4, -2.0),
+            aabb(-3.0, -0.2, -2.8, 0.2),
+        ];
+        assert_eq!(pairs(1.0, &bounds), brute_force(&bounds));
+        assert_eq!(pairs(1.0, &bounds), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn same_pairs_as_brute_force() {
+        let mut rng = SmallRng::seed_from_u64(3075);
+        for cell_size in [0.1, 0.25, 1.0, 5.0] {
+            let bounds: Vec<Aabb> = (0..200)
+                .map(|_| {
+                    let (x, y) = (rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0));
+                    Aabb::from_center(x, y, rng.gen_range(0.01..0.4), rng.gen_range(0.01..0.4))
+                })
+                .collect();
+            assert_eq!(pairs(cell_size, &bounds), brute_force(&bounds), "cell size {}", cell_size);
+        }
+    }
+
+    #[test]
+    fn clear_forgets_previous_objects() {
+        let mut hash = SpatialHash::new(1.0);
+        hash.insert(0, aabb(0.0, 0.0, 1.0, 1.0));
+        hash.insert(1, aabb(0.5, 0.5, 1.5, 1.5));
+        assert_eq!(hash.candidate_pairs(), vec![(0, 1)]);
+        hash.clear();
+        hash.insert(0, aabb(0.0, 0.0, 1.0, 1.0));
+        assert!(hash.candidate_pairs().is_empty());
+        assert_eq!(hash.occupied_cells().count(), 4);
+    }
+}
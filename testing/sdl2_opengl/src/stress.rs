@@ -0,0 +1,88 @@
+use crate::camera::ARENA_HALF_SIZE;
+use crate::collision;
+use crate::components::Position;
+use crate::ecs::Entity;
+use crate::input::FrameInput;
+use crate::level::Level;
+use crate::obstacles::Behavior;
+use crate::spatial_hash::SpatialHash;
+use crate::systems;
+use crate::world::World;
+use crate::BROAD_PHASE_CELL_SIZE;
+use rand::Rng;
+use std::time::Instant;
+
+// Simulated frames per entity count, at 60 per second
+const FRAMES: u32 = 300;
+const FRAME_TIME: f32 = 1.0 / 60.0;
+// The entity count is doubled this many times after the first run
+const DOUBLINGS: u32 = 3;
+
+// Every collider against every other one, what the broad phase saves
+fn all_pairs(world: &World) -> Vec<(Entity, Entity)> {
+    let shapes: Vec<_> = world
+        .colliders
+        .iter()
+        .filter_map(|(entity, collider)| world.positions.get(entity).map(|position| (entity, collider.shape(position))))
+        .collect();
+    let mut contacts = Vec::new();
+    for (i, (a, shape_a)) in shapes.iter().enumerate() {
+        for (b, shape_b) in &shapes[i + 1..] {
+            if collision::shapes(shape_a, shape_b).is_some() {
+                contacts.push((*a, *b));
+            }
+        }
+    }
+    contacts
+}
+
+// `--stress <n>`: runs the simulation without a window with n obstacles, then twice and four
+// times as many and so on, and prints how long a frame and the collision pass take with the
+// broad phase and without it. Both find the same contacts, the counts are printed to show it.
+// Every run starts from `seed`, so the numbers can be compared between builds.
+pub fn run(level: &Level, player_speed: f32, count: u32, seed: u64) {
+    println!("{} frames per run, times are per frame", FRAMES);
+    for doubling in 0..=DOUBLINGS {
+        let obstacles = count << doubling;
+        let mut world = World::new(player_speed, level, seed);
+        for _ in 0..obstacles {
+            let range = 0.9 * ARENA_HALF_SIZE;
+            let position = Position {
+                x: world.rng.gen::<f32>() * 2.0 * range - range,
+                y: world.rng.gen::<f32>() * 2.0 * range - range,
+            };
+            world.spawn_obstacle(position, Behavior::Wander);
+        }
+
+        let mut spatial_hash = SpatialHash::new(BROAD_PHASE_CELL_SIZE);
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            world.update(FRAME_TIME, &FrameInput::default(), &mut spatial_hash);
+        }
+        let update_time = start.elapsed().as_secs_f64() * 1000.0 / FRAMES as f64;
+
+        let start = Instant::now();
+        let mut grid_contacts = 0;
+        for _ in 0..FRAMES {
+            grid_contacts = systems::contacts(&world, &mut spatial_hash).len();
+        }
+        let grid_time = start.elapsed().as_secs_f64() * 1000.0 / FRAMES as f64;
+
+        let start = Instant::now();
+        let mut all_contacts = 0;
+        for _ in 0..FRAMES {
+            all_contacts = all_pairs(&world).len();
+        }
+        let all_pairs_time = start.elapsed().as_secs_f64() * 1000.0 / FRAMES as f64;
+
+        println!(
+            "{:>6} entities: update {:.3} ms, contacts {:.3} ms with the grid ({} found), {:.3} ms testing all pairs ({} found)",
+            world.colliders.len(),
+            update_time,
+            grid_time,
+            grid_contacts,
+            all_pairs_time,
+            all_contacts
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use crate::camera::ARENA_HALF_SIZE;
+use crate::collision;
+use crate::components::{Clock, Confinement, Position, Renderable, FULL_TEXTURE};
+use crate::ecs::{Entity, Storage};
+use crate::obstacles::{self, Behavior};
+use crate::powerups::PowerUpKind;
+use crate::renderer::Renderer;
+use crate::spatial_hash::{Aabb, SpatialHash};
+use crate::world::World;
+
+// Systems run over every entity that has the components they use, in the order World::update
+// calls them. A new kind of entity is a spawn function in World combining components, and only
+// needs a system of its own if it does something none of the existing ones do.
+
+// An obstacle that comes this close (center to center) and leaves again without a hit counts
+// as dodged
+const NEAR_MISS_DISTANCE: f32 = 0.35;
+
+// Sets the player's velocity from the unit length input direction
+pub fn player_control(world: &mut World, direction: (f32, f32)) {
+    if direction != (0.0, 0.0) {
+        world.facing = direction;
+    }
+    let speed = world.player_speed * world.effects.speed_factor();
+    if let Some(velocity) = world.velocities.get_mut(world.player) {
+        velocity.x = direction.0 * speed;
+        velocity.y = direction.1 * speed;
+    }
+}
+
+// Chasers turn towards the player, on the scaled clock like their movement
+pub fn obstacle_ai(world: &mut World, dt: f32) {
+    let target = world.player_position();
+    for (entity, obstacle) in world.obstacles.iter() {
+        if obstacle.behavior != Behavior::Chase {
+            continue;
+        }
+        if let (Some(position), Some(velocity)) = (world.positions.get(entity), world.velocities.get_mut(entity)) {
+            obstacles::steer_towards(position, velocity, target, dt);
+        }
+    }
+}
+
+// Everything with a velocity, `scaled_dt` is used for entities on the scaled clock
+pub fn movement(world: &mut World, dt: f32, scaled_dt: f32) {
+    for (entity, velocity) in world.velocities.iter() {
+        let dt = match world.clocks.get(entity) {
+            Some(Clock::Scaled) => scaled_dt,
+            _ => dt,
+        };
+        if let Some(position) = world.positions.get_mut(entity) {
+            position.x += velocity.x * dt;
+            position.y += velocity.y * dt;
+        }
+    }
+}
+
+// Moves the platforms and carries the player with the one they stand on, or pushes them along
+// the conveyor they stand on
+pub fn platforms(world: &mut World, dt: f32) {
+    let position = match world.positions.get_mut(world.player) {
+        Some(position) => position,
+        None => return,
+    };
+    // Riders are picked before the platforms move so they follow this frame's movement
+    let riding = world
+        .platforms
+        .iter()
+        .position(|platform| platform.carries(position.x, position.y));
+    for platform in &mut world.platforms {
+        platform.update(dt);
+    }
+    let (carry_x, carry_y) = match riding {
+        Some(index) => world.platforms[index].velocity,
+        None => {
+            let (push_x, push_y) = world.tile_map.conveyor_push(position.x, position.y);
+            (push_x * dt, push_y * dt)
+        }
+    };
+    position.x += carry_x;
+    position.y += carry_y;
+}
+
+// Keeps entities inside the arena and out of walls, or removes them when they leave
+pub fn confinement(world: &mut World) {
+    let mut despawned = Vec::new();
+    for (entity, confinement) in world.confinements.iter() {
+        let (position, collider) = match (world.positions.get_mut(entity), world.colliders.get(entity)) {
+            (Some(position), Some(collider)) => (position, collider),
+            _ => continue,
+        };
+        let mut velocity = world.velocities.get_mut(entity);
+
+        if *confinement == Confinement::Despawn {
+            let bounds = collider.bounds(position);
+            let outside = bounds.max_x < -ARENA_HALF_SIZE
+                || bounds.min_x > ARENA_HALF_SIZE
+                || bounds.max_y < -ARENA_HALF_SIZE
+                || bounds.min_y > ARENA_HALF_SIZE;
+            if outside || world.tile_map.is_wall(position.x, position.y) {
+                despawned.push(entity);
+            }
+            continue;
+        }
+
+        let bounce = *confinement == Confinement::Bounce;
+        if bounce {
+            let bounds = collider.bounds(position);
+            let limit_x = ARENA_HALF_SIZE - (bounds.max_x - bounds.min_x) / 2.0;
+            let limit_y = ARENA_HALF_SIZE - (bounds.max_y - bounds.min_y) / 2.0;
+            if position.x.abs() > limit_x {
+                position.x = position.x.clamp(-limit_x, limit_x);
+                if let Some(velocity) = velocity.as_deref_mut() {
+                    velocity.x = -velocity.x;
+                }
+            }
+            if position.y.abs() > limit_y {
+                position.y = position.y.clamp(-limit_y, limit_y);
+                if let Some(velocity) = velocity.as_deref_mut() {
+                    velocity.y = -velocity.y;
+                }
+            }
+        }
+
+        // The contact normal points from the entity towards the wall
+        for wall in world.tile_map.walls_near(&collider.bounds(position)) {
+            if let Some(contact) = collision::aabb_aabb(&collider.bounds(position), &wall) {
+                let (normal_x, normal_y) = contact.normal;
+                position.x -= normal_x * contact.depth;
+                position.y -= normal_y * contact.depth;
+                if let Some(velocity) = velocity.as_deref_mut().filter(|_| bounce) {
+                    let towards = velocity.x * normal_x + velocity.y * normal_y;
+                    if towards > 0.0 {
+                        velocity.x -= 2.0 * towards * normal_x;
+                        velocity.y -= 2.0 * towards * normal_y;
+                    }
+                }
+            }
+        }
+    }
+    for entity in despawned {
+        world.despawn(entity);
+    }
+}
+
+pub fn lifetimes(world: &mut World, dt: f32) {
+    let mut expired = Vec::new();
+    for (entity, lifetime) in world.lifetimes.iter_mut() {
+        lifetime.0 -= dt;
+        if lifetime.0 <= 0.0 {
+            expired.push(entity);
+        }
+    }
+    for entity in expired {
+        world.despawn(entity);
+    }
+}
+
+// Advances every animation on its entity's clock and picks the frame to draw
+pub fn animation(world: &mut World, dt: f32, scaled_dt: f32) {
+    for (entity, animation) in world.animations.iter_mut() {
+        animation.time += match world.clocks.get(entity) {
+            Some(Clock::Scaled) => scaled_dt,
+            _ => dt,
+        };
+        if let Some(uv) = animation.frames.get(animation.frame_index()) {
+            animation.uv = *uv;
+        }
+    }
+}
+
+// Rebuilds the broad phase from every collider and returns the pairs of entities that touch
+pub fn contacts(world: &World, spatial_hash: &mut SpatialHash) -> Vec<(Entity, Entity)> {
+    // Spatial hash ids are indices into this
+    let mut shapes = Vec::new();
+    spatial_hash.clear();
+    for (entity, collider) in world.colliders.iter() {
+        if let Some(position) = world.positions.get(entity) {
+            spatial_hash.insert(shapes.len(), collider.bounds(position));
+            shapes.push((entity, collider.shape(position)));
+        }
+    }
+
+    spatial_hash
+        .candidate_pairs()
+        .into_iter()
+        .filter_map(|(a, b)| {
+            let ((a, shape_a), (b, shape_b)) = (&shapes[a], &shapes[b]);
+            collision::shapes(shape_a, shape_b).map(|_| (*a, *b))
+        })
+        .collect()
+}
+
+// The contacts between an entity accepted by `first` and one accepted by `second`, in that order
+fn matching(
+    contacts: &[(Entity, Entity)],
+    first: impl Fn(Entity) -> bool,
+    second: impl Fn(Entity) -> bool,
+) -> Vec<(Entity, Entity)> {
+    contacts
+        .iter()
+        .filter_map(|&(a, b)| {
+            if first(a) && second(b) {
+                Some((a, b))
+            } else if first(b) && second(a) {
+                Some((b, a))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn player_touches_obstacle(world: &World, contacts: &[(Entity, Entity)]) -> bool {
+    !matching(contacts, |entity| entity == world.player, |entity| world.obstacles.contains(entity)).is_empty()
+}
+
+// A projectile destroys the first obstacle it touches and is used up. Returns the number of
+// obstacles destroyed.
+pub fn projectile_hits(world: &mut World, contacts: &[(Entity, Entity)]) -> u32 {
+    let hits = matching(
+        contacts,
+        |entity| world.projectiles.contains(entity),
+        |entity| world.obstacles.contains(entity),
+    );
+    let mut destroyed = 0;
+    for (projectile, obstacle) in hits {
+        // Either one may already be gone through an earlier pair
+        if world.entities.is_alive(projectile) && world.entities.is_alive(obstacle) {
+            if let Some(position) = world.positions.get(obstacle) {
+                world.destroyed_at.push((position.x, position.y));
+            }
+            world.despawn(projectile);
+            world.despawn(obstacle);
+            destroyed += 1;
+        }
+    }
+    destroyed
+}
+
+// Power-ups the player touches take effect and disappear
+pub fn pickups(world: &mut World, contacts: &[(Entity, Entity)]) {
+    let picked_up = matching(
+        contacts,
+        |entity| entity == world.player,
+        |entity| world.power_ups.contains(entity),
+    );
+    for (_, power_up) in picked_up {
+        if let Some(power_up) = world.power_ups.get(power_up) {
+            world.effects.activate(power_up.kind);
+        }
+        world.despawn(power_up);
+    }
+}
+
+// Marks obstacles that come close to the player, returns how many of the marked ones left
+// again this frame
+pub fn near_misses(world: &mut World, colliding: bool) -> u32 {
+    let (player_x, player_y) = world.player_position();
+    let mut dodged = 0;
+    for (entity, obstacle) in world.obstacles.iter_mut() {
+        let position = match world.positions.get(entity) {
+            Some(position) => position,
+            None => continue,
+        };
+        let (dx, dy) = (position.x - player_x, position.y - player_y);
+        let close = (dx * dx + dy * dy).sqrt() < NEAR_MISS_DISTANCE;
+        if close && !colliding {
+            obstacle.near_miss = true;
+        } else if !close && obstacle.near_miss {
+            obstacle.near_miss = false;
+            dodged += 1;
+        }
+    }
+    dodged
+}
+
+// Mirrored obstacles come and go with the network host's
+pub fn spawning(world: &mut World, dt: f32) {
+    let player = world.player_position();
+    if !world.mirrored_obstacles {
+        if let Some((position, behavior)) = world.spawner.update(dt, world.obstacles.len(), player, &mut world.rng) {
+            world.spawn_obstacle(position, behavior);
+        }
+    }
+    if let Some((position, kind)) = world.power_up_spawner.update(dt, world.power_ups.len(), &mut world.rng) {
+        world.spawn_power_up(position, kind);
+    }
+}
+
+// Tint, color, positions and animation frames of obstacles drawn in one instanced call
+type ObstacleBatch = ([f32; 4], [f32; 4], Vec<(f32, f32)>, Vec<[f32; 4]>);
+
+// Blinks while invulnerable after losing a life
+fn player_color(world: &World) -> [f32; 4] {
+    if world.is_invulnerable() {
+        let visible = (world.invulnerable_for * 5.0).fract() < 0.5;
+        [1.0, 0.0, 0.0, if visible { 1.0 } else { 0.25 }]
+    } else if world.effects.is_active(PowerUpKind::Shield) {
+        PowerUpKind::Shield.color()
+    } else {
+        [0.0, 1.0, 0.0, 1.0]
+    }
+}
+
+// Where to draw the entities between two steps, `alpha` going from 0 at the `previous`
+// positions to 1 at the `current` ones. Entities spawned in the last step have no previous
+// position and are drawn where they are.
+pub fn interpolate_positions(previous: &Storage<Position>, current: &Storage<Position>, alpha: f32) -> Storage<Position> {
+    let mut positions = Storage::default();
+    for (entity, position) in current.iter() {
+        let from = previous.get(entity).unwrap_or(position);
+        positions.insert(
+            entity,
+            Position {
+                x: from.x + (position.x - from.x) * alpha,
+                y: from.y + (position.y - from.y) * alpha,
+            },
+        );
+    }
+    positions
+}
+
+// Everything in the world, back to front: the level, then players, obstacles and squares.
+// Entities are drawn at `positions`, see interpolate_positions.
+pub fn render(world: &World, positions: &Storage<Position>, renderer: &mut Renderer) {
+    let center = |bounds: &Aabb| ((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0);
+    let size = |bounds: &Aabb| (bounds.max_x - bounds.min_x, bounds.max_y - bounds.min_y);
+
+    let tile_size = world.tile_map.tile_size();
+    for (bounds, _) in world.tile_map.conveyors() {
+        renderer.draw_rect(center(&bounds), (tile_size, tile_size), [0.15, 0.15, 0.35, 1.0]);
+    }
+    for bounds in world.tile_map.walls() {
+        renderer.draw_rect(center(&bounds), size(&bounds), [0.35, 0.3, 0.25, 1.0]);
+    }
+    for platform in &world.platforms {
+        let bounds = platform.bounds();
+        renderer.draw_rect(center(&bounds), size(&bounds), [0.5, 0.5, 0.5, 1.0]);
+    }
+
+    let mut players = Vec::new();
+    let mut obstacles: Vec<ObstacleBatch> = Vec::new();
+    let mut squares = Vec::new();
+    for (entity, renderable) in world.renderables.iter() {
+        let position = match positions.get(entity) {
+            Some(position) => position,
+            None => continue,
+        };
+        let center = (position.x, position.y);
+        let uv = world.animations.get(entity).map_or(FULL_TEXTURE, |animation| animation.uv);
+        match *renderable {
+            Renderable::Player => players.push((center, uv)),
+            Renderable::Obstacle { tint, color } => {
+                match obstacles.iter_mut().find(|(t, c, _, _)| *t == tint && *c == color) {
+                    Some((_, _, positions, uvs)) => {
+                        positions.push(center);
+                        uvs.push(uv);
+                    }
+                    None => obstacles.push((tint, color, vec![center], vec![uv])),
+                }
+            }
+            Renderable::Square { color } => {
+                if let Some(collider) = world.colliders.get(entity) {
+                    squares.push((collider.bounds(position), color));
+                }
+            }
+        }
+    }
+
+    let color = player_color(world);
+    for (position, uv) in players {
+        renderer.draw_player(position, uv, color);
+    }
+    for (tint, color, positions, uvs) in &obstacles {
+        renderer.draw_triangles(positions, uvs, *tint, *color);
+    }
+    for (bounds, color) in squares {
+        renderer.draw_rect(center(&bounds), size(&bounds), color);
+    }
+}
+
+// The other player of a network session, in blue with the local player's animation frame
+pub fn render_remote_player(world: &World, position: Position, renderer: &mut Renderer) {
+    let uv = world.animations.get(world.player).map_or(FULL_TEXTURE, |animation| animation.uv);
+    renderer.draw_player((position.x, position.y), uv, [0.3, 0.5, 1.0, 1.0]);
+}
+
+// Debug view of every collider's bounding box, green while the player touches an obstacle and
+// cyan otherwise
+pub fn render_colliders(world: &World, renderer: &mut Renderer) {
+    let color = if world.colliding { [0.2, 1.0, 0.2, 1.0] } else { [0.2, 0.9, 1.0, 1.0] };
+    for (entity, collider) in world.colliders.iter() {
+        if let Some(position) = world.positions.get(entity) {
+            let bounds = collider.bounds(position);
+            let center = ((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0);
+            renderer.draw_rect_outline(center, (bounds.max_x - bounds.min_x, bounds.max_y - bounds.min_y), color);
+        }
+    }
+}
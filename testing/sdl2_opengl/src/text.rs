@@ -0,0 +1,23 @@
+use crate::texture::Texture;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::ttf::Font;
+
+// Renders a single line of text with SDL2_ttf into a texture
+pub fn render_text(font: &Font, text: &str, color: Color) -> Result<Texture, String> {
+    let surface = font.render(text).blended(color).map_err(|e| e.to_string())?;
+    // ABGR8888 is R, G, B, A in memory on little endian, which is what GL_RGBA wants
+    let surface = surface.convert_format(PixelFormatEnum::ABGR8888)?;
+    let (width, height) = surface.size();
+    let pitch = surface.pitch() as usize;
+    let row_len = width as usize * 4;
+
+    // SDL surfaces start at the top row, textures at the bottom one
+    let mut pixels = Vec::with_capacity(row_len * height as usize);
+    surface.with_lock(|data| {
+        for row in (0..height as usize).rev() {
+            pixels.extend_from_slice(&data[row * pitch..row * pitch + row_len]);
+        }
+    });
+
+    Ok(Texture::from_rgba(width, height, &pixels))
+}
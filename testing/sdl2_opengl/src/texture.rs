@@ -0,0 +1,88 @@
+use gl::types::*;
+use std::path::Path;
+use std::ptr;
+
+// 2D RGBA texture, from an image file, rendered text or as a render target. Needs a current GL context for its whole
+// lifetime, the GL object is deleted on drop.
+pub struct Texture {
+    id: GLuint,
+    width: u32,
+    height: u32,
+}
+
+impl Texture {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Texture, image::ImageError> {
+        // GL expects the first row at the bottom
+        let image = image::open(path)?.flipv().into_rgba8();
+        let (width, height) = image.dimensions();
+        Ok(Texture::from_rgba(width, height, image.as_raw()))
+    }
+
+    // Tightly packed RGBA8 pixels, bottom row first
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8]) -> Texture {
+        // Nearest filtering keeps small sprites crisp when scaled up
+        Texture::create(width, height, pixels.as_ptr(), gl::NEAREST)
+    }
+
+    // Uninitialized texture to render into through a framebuffer. Filtered linearly so it can
+    // be sampled at fractional offsets.
+    pub fn render_target(width: u32, height: u32) -> Texture {
+        Texture::create(width, height, ptr::null(), gl::LINEAR)
+    }
+
+    fn create(width: u32, height: u32, pixels: *const u8, filter: GLenum) -> Texture {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels as *const _,
+            );
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+
+        Texture { id, width, height }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn bind(&self, unit: GLuint) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use crate::components::{Animation, Clock, Collider, Confinement, Lifetime, Position, Renderable, Velocity};
+use crate::ecs::{Entities, Entity, Storage};
+use crate::input::FrameInput;
+use crate::level::Level;
+use crate::obstacles::{self, Behavior, Obstacle, Spawner, MAX_OBSTACLES};
+use crate::platforms::{MovingPlatform, TileMap};
+use crate::powerups::{Effects, PowerUp, PowerUpKind, PowerUpSpawner, POWER_UP_HALF_SIZE, POWER_UP_LIFETIME};
+use crate::projectiles::{self, Gun, Projectile, PROJECTILE_LIFETIME, PROJECTILE_RADIUS};
+use crate::spatial_hash::SpatialHash;
+use crate::systems;
+use crate::{ANIMATION_FPS, OBSTACLE_FRAMES, PLAYER_FRAMES, RECT_HALF_SIZE, SPAWN_INTERVAL, TILE_SIZE, TRIANGLE_SIZE};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+pub const STARTING_LIVES: u32 = 3;
+// Seconds after losing a life during which obstacles pass through the player
+pub const INVULNERABILITY_TIME: f32 = 1.5;
+const DODGE_POINTS: u32 = 5;
+// Obstacle speed multiplier grows by this much per second of play, up to the maximum
+const DIFFICULTY_RAMP: f32 = 0.02;
+pub const MAX_DIFFICULTY: f32 = 3.0;
+// Destroying an obstacle is worth less than dodging one, or shooting would be the only strategy
+const SHOT_POINTS: u32 = 2;
+
+// Everything that belongs to a single run, a restart replaces the whole world. Serialized
+// as a whole for save games.
+//
+// All gameplay randomness comes from `rng`, so a run is reproduced exactly by its seed (see
+// `--seed`) and the time steps and input it was updated with. Visual effects use their own randomness.
+//
+// The player, obstacles, projectiles and power-ups are entities made of the components
+// below, the systems in systems.rs update every entity that has the components they use. The
+// tile map, platforms and the run's counters are not entities, there is only one of each.
+#[derive(Serialize, Deserialize)]
+pub struct World {
+    pub entities: Entities,
+    pub positions: Storage<Position>,
+    pub velocities: Storage<Velocity>,
+    pub clocks: Storage<Clock>,
+    pub colliders: Storage<Collider>,
+    pub confinements: Storage<Confinement>,
+    pub lifetimes: Storage<Lifetime>,
+    pub renderables: Storage<Renderable>,
+    pub animations: Storage<Animation>,
+    pub obstacles: Storage<Obstacle>,
+    pub projectiles: Storage<Projectile>,
+    pub power_ups: Storage<PowerUp>,
+    pub player: Entity,
+    // World units per second, from the config
+    pub player_speed: f32,
+    // Unit length, the last direction the player moved in. Projectiles fly this way.
+    pub facing: (f32, f32),
+    pub spawner: Spawner,
+    pub gun: Gun,
+    pub power_up_spawner: PowerUpSpawner,
+    pub effects: Effects,
+    pub tile_map: TileMap,
+    pub platforms: Vec<MovingPlatform>,
+    pub score: u32,
+    // Seconds played in this run
+    pub elapsed: f32,
+    pub lives: u32,
+    pub dodged: u32,
+    pub colliding: bool,
+    // Remaining invulnerability after a hit, in seconds
+    pub invulnerable_for: f32,
+    // Obstacle speed multiplier
+    pub difficulty: f32,
+    // Time since the last survival point
+    survival_time: f32,
+    // Set once the obstacles are copied from a network host, none are spawned here then
+    #[serde(skip)]
+    pub mirrored_obstacles: bool,
+    // Where obstacles were shot this frame, for effects
+    #[serde(skip)]
+    pub destroyed_at: Vec<(f32, f32)>,
+    // The generator's state can't be saved, a loaded run continues with a fresh seed
+    #[serde(skip, default = "SmallRng::from_entropy")]
+    pub rng: SmallRng,
+}
+
+impl World {
+    pub fn new(player_speed: f32, level: &Level, seed: u64) -> World {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut entities = Entities::default();
+        let player = entities.spawn();
+        let mut world = World {
+            entities,
+            positions: Storage::default(),
+            velocities: Storage::default(),
+            clocks: Storage::default(),
+            colliders: Storage::default(),
+            confinements: Storage::default(),
+            lifetimes: Storage::default(),
+            renderables: Storage::default(),
+            animations: Storage::default(),
+            obstacles: Storage::default(),
+            projectiles: Storage::default(),
+            power_ups: Storage::default(),
+            player,
+            player_speed,
+            facing: (0.0, 1.0),
+            spawner: Spawner::new(SPAWN_INTERVAL, MAX_OBSTACLES, level.obstacle_spawns.clone()),
+            gun: Gun::new(),
+            power_up_spawner: PowerUpSpawner::new(&mut rng),
+            effects: Effects::new(),
+            tile_map: TileMap::parse(&level.tiles, TILE_SIZE),
+            platforms: level.platforms(),
+            score: 0,
+            elapsed: 0.0,
+            lives: STARTING_LIVES,
+            dodged: 0,
+            colliding: false,
+            invulnerable_for: 0.0,
+            difficulty: 1.0,
+            survival_time: 0.0,
+            mirrored_obstacles: false,
+            destroyed_at: Vec::new(),
+            rng,
+        };
+
+        let (x, y) = level.player_start;
+        world.positions.insert(player, Position { x, y });
+        world.velocities.insert(player, Velocity { x: 0.0, y: 0.0 });
+        world.clocks.insert(player, Clock::Real);
+        world.colliders.insert(
+            player,
+            Collider::Box {
+                half_width: RECT_HALF_SIZE,
+                half_height: RECT_HALF_SIZE,
+            },
+        );
+        world.confinements.insert(player, Confinement::Slide);
+        world.renderables.insert(player, Renderable::Player);
+        world.animations.insert(player, Animation::strip(PLAYER_FRAMES, ANIMATION_FPS, true));
+
+        let (position, behavior) = world.spawner.spawn_away_from(level.player_start, &mut world.rng);
+        world.spawn_obstacle(position, behavior);
+        world
+    }
+
+    pub fn spawn_obstacle(&mut self, position: Position, behavior: Behavior) -> Entity {
+        let entity = self.entities.spawn();
+        self.positions.insert(entity, position);
+        self.velocities.insert(entity, obstacles::random_velocity(&mut self.rng));
+        self.clocks.insert(entity, Clock::Scaled);
+        self.colliders.insert(entity, Collider::Triangle { half_size: TRIANGLE_SIZE });
+        self.confinements.insert(entity, Confinement::Bounce);
+        self.renderables.insert(entity, behavior.renderable());
+        self.animations.insert(entity, Animation::strip(OBSTACLE_FRAMES, ANIMATION_FPS, true));
+        self.obstacles.insert(entity, Obstacle::new(behavior));
+        entity
+    }
+
+    // Fired from the shooter's position, `direction` is unit length
+    pub fn spawn_projectile(&mut self, shooter: Entity, direction: (f32, f32)) -> Entity {
+        let position = self.positions.get(shooter).copied().unwrap_or(Position { x: 0.0, y: 0.0 });
+        let entity = self.entities.spawn();
+        self.positions.insert(entity, position);
+        self.velocities.insert(entity, projectiles::velocity(direction));
+        self.clocks.insert(entity, Clock::Real);
+        self.colliders.insert(entity, Collider::Circle { radius: PROJECTILE_RADIUS });
+        self.confinements.insert(entity, Confinement::Despawn);
+        self.lifetimes.insert(entity, Lifetime(PROJECTILE_LIFETIME));
+        self.renderables.insert(entity, Renderable::Square { color: [1.0, 1.0, 0.4, 1.0] });
+        self.projectiles.insert(entity, Projectile { shooter });
+        entity
+    }
+
+    // Power-ups are filled squares in the color of their effect
+    pub fn spawn_power_up(&mut self, position: Position, kind: PowerUpKind) -> Entity {
+        let entity = self.entities.spawn();
+        self.positions.insert(entity, position);
+        self.colliders.insert(
+            entity,
+            Collider::Box {
+                half_width: POWER_UP_HALF_SIZE,
+                half_height: POWER_UP_HALF_SIZE,
+            },
+        );
+        self.lifetimes.insert(entity, Lifetime(POWER_UP_LIFETIME));
+        self.renderables.insert(entity, Renderable::Square { color: kind.color() });
+        self.power_ups.insert(entity, PowerUp { kind });
+        entity
+    }
+
+    // Removes the entity and all of its components, a new storage has to be added here
+    pub fn despawn(&mut self, entity: Entity) {
+        if !self.entities.despawn(entity) {
+            return;
+        }
+        self.positions.remove(entity);
+        self.velocities.remove(entity);
+        self.clocks.remove(entity);
+        self.colliders.remove(entity);
+        self.confinements.remove(entity);
+        self.lifetimes.remove(entity);
+        self.renderables.remove(entity);
+        self.animations.remove(entity);
+        self.obstacles.remove(entity);
+        self.projectiles.remove(entity);
+        self.power_ups.remove(entity);
+    }
+
+    // Positions and behaviors of all obstacles, what a network host sends
+    pub fn obstacle_states(&self) -> Vec<(Position, Behavior)> {
+        self.obstacles
+            .iter()
+            .filter_map(|(entity, obstacle)| self.positions.get(entity).map(|position| (*position, obstacle.behavior)))
+            .collect()
+    }
+
+    // Moves the obstacles to a network host's, respawning them all when the host's obstacles
+    // were spawned or destroyed. Their own velocities are kept, they only move by it for the
+    // frames until the next call.
+    pub fn sync_obstacles(&mut self, states: &[(Position, Behavior)]) {
+        self.mirrored_obstacles = true;
+        let entities = self.obstacles.entities();
+        let matching = entities.len() == states.len()
+            && entities
+                .iter()
+                .zip(states)
+                .all(|(entity, (_, behavior))| self.obstacles.get(*entity).is_some_and(|o| o.behavior == *behavior));
+        if !matching {
+            for entity in entities {
+                self.despawn(entity);
+            }
+            for (position, behavior) in states {
+                self.spawn_obstacle(*position, *behavior);
+            }
+            return;
+        }
+        for (entity, (position, _)) in entities.into_iter().zip(states) {
+            if let Some(current) = self.positions.get_mut(entity) {
+                *current = *position;
+            }
+        }
+    }
+
+    pub fn player_position(&self) -> (f32, f32) {
+        self.positions
+            .get(self.player)
+            .map(|position| (position.x, position.y))
+            .unwrap_or((0.0, 0.0))
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.lives == 0
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_for > 0.0
+    }
+
+    // Advances the run by dt seconds. The spatial hash is rebuilt here and kept by the caller
+    // for the broad phase overlay. Returns true if the player lost a life this frame.
+    pub fn update(&mut self, dt: f32, input: &FrameInput, spatial_hash: &mut SpatialHash) -> bool {
+        self.elapsed += dt;
+        self.destroyed_at.clear();
+        // Effects tick down before pickups so a fresh one lasts its full duration
+        self.effects.update(dt);
+        self.gun.update(dt);
+        self.difficulty = (self.difficulty + DIFFICULTY_RAMP * dt).min(MAX_DIFFICULTY);
+        let scaled_dt = dt * self.difficulty * self.effects.time_factor();
+
+        systems::player_control(self, input.direction());
+        if input.firing && self.gun.try_fire() {
+            self.spawn_projectile(self.player, self.facing);
+        }
+        systems::obstacle_ai(self, scaled_dt);
+        systems::movement(self, dt, scaled_dt);
+        systems::platforms(self, dt);
+        systems::confinement(self);
+        systems::lifetimes(self, dt);
+        systems::animation(self, dt, scaled_dt);
+
+        let contacts = systems::contacts(self, spatial_hash);
+        let colliding = systems::player_touches_obstacle(self, &contacts);
+        self.invulnerable_for = (self.invulnerable_for - dt).max(0.0);
+        let hit = colliding && !self.is_invulnerable() && !self.effects.is_active(PowerUpKind::Shield);
+        self.colliding = colliding;
+        if hit {
+            self.lives = self.lives.saturating_sub(1);
+            self.invulnerable_for = INVULNERABILITY_TIME;
+            self.survival_time = 0.0;
+            // Whatever was close when the hit happened doesn't count as dodged
+            for (_, obstacle) in self.obstacles.iter_mut() {
+                obstacle.near_miss = false;
+            }
+        }
+
+        // One point for every second survived, plus a bonus for every dodged obstacle
+        self.survival_time += dt;
+        if self.survival_time >= 1.0 {
+            self.score += 1;
+            self.survival_time -= 1.0;
+        }
+        let dodged = systems::near_misses(self, colliding);
+        self.dodged += dodged;
+        self.score += dodged * DODGE_POINTS;
+        self.score += systems::projectile_hits(self, &contacts) * SHOT_POINTS;
+        systems::pickups(self, &contacts);
+
+        systems::spawning(self, dt);
+        hit
+    }
+}